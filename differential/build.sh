@@ -0,0 +1,40 @@
+#!/usr/bin/env bash
+# Compiles the differential test harnesses and writes the bytecode that `cargo test` in this
+# directory executes in revm: the math harness against the vendored Uniswap sources, and the gas
+# harness together with UniswapV3Pool from v3-core.
+#
+# Requires `solc` 0.8.x on the PATH (e.g. installed through `svm install 0.8.26`), and solc 0.7.6,
+# the compiler v3-core is built with, as `solc-0.7.6` or at $SOLC_0_7_6. v3-core is cloned into
+# v3-core/ unless $V3_CORE points at a checkout.
+set -euo pipefail
+
+DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+ROOT="$(cd "$DIR/.." && pwd)"
+SOLC_0_7_6="${SOLC_0_7_6:-solc-0.7.6}"
+V3_CORE="${V3_CORE:-$DIR/v3-core}"
+
+solc \
+    --optimize \
+    --optimize-runs 200 \
+    --evm-version paris \
+    --base-path "$ROOT" \
+    --allow-paths "$ROOT/Uniswap" \
+    --bin-runtime \
+    --overwrite \
+    -o "$DIR/bytecode" \
+    "$DIR/contracts/UniswapV3MathHarness.sol" \
+    "$DIR/contracts/PoolGasHarness.sol"
+
+if [ ! -d "$V3_CORE" ]; then
+    git clone --quiet --depth 1 --branch v1.0.0 https://github.com/Uniswap/v3-core "$V3_CORE"
+fi
+
+# the settings of v3-core's hardhat config, which the deployed pools were built with
+"$SOLC_0_7_6" \
+    --optimize \
+    --optimize-runs 800 \
+    --metadata-hash none \
+    --bin \
+    --overwrite \
+    -o "$DIR/bytecode" \
+    "$V3_CORE/contracts/UniswapV3Pool.sol"
@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+interface IUniswapV3Pool {
+    function initialize(uint160 sqrtPriceX96) external;
+
+    function mint(
+        address recipient,
+        int24 tickLower,
+        int24 tickUpper,
+        uint128 amount,
+        bytes calldata data
+    ) external returns (uint256 amount0, uint256 amount1);
+
+    function swap(
+        address recipient,
+        bool zeroForOne,
+        int256 amountSpecified,
+        uint160 sqrtPriceLimitX96,
+        bytes calldata data
+    ) external returns (int256 amount0, int256 amount1);
+
+    function slot0()
+        external
+        view
+        returns (
+            uint160 sqrtPriceX96,
+            int24 tick,
+            uint16 observationIndex,
+            uint16 observationCardinality,
+            uint16 observationCardinalityNext,
+            uint8 feeProtocol,
+            bool unlocked
+        );
+}
+
+/// @notice A token with its whole supply minted to its creator
+contract TestToken {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+
+    mapping(address => uint256) public balanceOf;
+
+    constructor() {
+        balanceOf[msg.sender] = type(uint256).max;
+    }
+
+    function transfer(address to, uint256 value) external returns (bool) {
+        balanceOf[msg.sender] -= value;
+        balanceOf[to] += value;
+        emit Transfer(msg.sender, to, value);
+        return true;
+    }
+}
+
+/// @notice Deploys the UniswapV3Pool built from v3-core the way the factory does, and measures swaps the way
+/// QuoterV2's gasEstimate does: the gas used by a swap that reverts in its callback with the quote.
+contract PoolGasHarness {
+    struct Parameters {
+        address factory;
+        address token0;
+        address token1;
+        uint24 fee;
+        int24 tickSpacing;
+    }
+
+    /// @notice Read by the pool's constructor, as IUniswapV3PoolDeployer.parameters
+    Parameters public parameters;
+
+    IUniswapV3Pool public pool;
+    TestToken public token0;
+    TestToken public token1;
+
+    /// @notice Holds no tokens, like QuoterV2 which receives the output of the swaps it quotes
+    address private constant QUOTE_RECIPIENT = address(uint160(0xdead));
+
+    /// @notice Creates the pool from the creation code placed at `poolCode` and initializes it
+    function createPool(address poolCode, uint24 fee, int24 tickSpacing, uint160 sqrtPriceX96)
+        external
+        returns (address created)
+    {
+        TestToken tokenA = new TestToken();
+        TestToken tokenB = new TestToken();
+        (token0, token1) = (tokenA, tokenB);
+        if (address(tokenB) < address(tokenA)) (token0, token1) = (tokenB, tokenA);
+
+        parameters = Parameters(address(this), address(token0), address(token1), fee, tickSpacing);
+        bytes memory code = poolCode.code;
+        assembly {
+            created := create(0, add(code, 32), mload(code))
+        }
+        require(created != address(0), 'create');
+        delete parameters;
+
+        pool = IUniswapV3Pool(created);
+        pool.initialize(sqrtPriceX96);
+    }
+
+    function mint(int24 tickLower, int24 tickUpper, uint128 amount) external {
+        pool.mint(address(this), tickLower, tickUpper, amount, '');
+    }
+
+    function uniswapV3MintCallback(uint256 amount0Owed, uint256 amount1Owed, bytes calldata) external {
+        if (amount0Owed > 0) token0.transfer(msg.sender, amount0Owed);
+        if (amount1Owed > 0) token1.transfer(msg.sender, amount1Owed);
+    }
+
+    /// @notice Swaps against the pool, paying for the swap
+    function swap(bool zeroForOne, int256 amountSpecified, uint160 sqrtPriceLimitX96) external {
+        pool.swap(address(this), zeroForOne, amountSpecified, sqrtPriceLimitX96, '');
+    }
+
+    /// @notice Returns the gas QuoterV2 would estimate for the swap, leaving the pool unchanged
+    function quote(bool zeroForOne, int256 amountSpecified, uint160 sqrtPriceLimitX96)
+        external
+        returns (uint256 gasEstimate)
+    {
+        bytes memory data = abi.encode(true);
+        uint256 gasBefore = gasleft();
+        try pool.swap(QUOTE_RECIPIENT, zeroForOne, amountSpecified, sqrtPriceLimitX96, data) {} catch (bytes memory) {
+            gasEstimate = gasBefore - gasleft();
+        }
+    }
+
+    /// @notice Pays for swaps, and reverts with the amount received and the price after the swap for quotes
+    function uniswapV3SwapCallback(int256 amount0Delta, int256 amount1Delta, bytes calldata data) external {
+        if (data.length == 0) {
+            if (amount0Delta > 0) token0.transfer(msg.sender, uint256(amount0Delta));
+            if (amount1Delta > 0) token1.transfer(msg.sender, uint256(amount1Delta));
+            return;
+        }
+
+        (uint160 sqrtPriceX96After, int24 tickAfter, , , , , ) = pool.slot0();
+        uint256 amountReceived = amount0Delta > 0 ? uint256(-amount1Delta) : uint256(-amount0Delta);
+        assembly {
+            let ptr := mload(0x40)
+            mstore(ptr, amountReceived)
+            mstore(add(ptr, 0x20), sqrtPriceX96After)
+            mstore(add(ptr, 0x40), tickAfter)
+            revert(ptr, 96)
+        }
+    }
+
+    function price() external view returns (uint160 sqrtPriceX96, int24 tick) {
+        (sqrtPriceX96, tick, , , , , ) = pool.slot0();
+    }
+}
@@ -0,0 +1,169 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+import {BitMath} from '../../Uniswap/BitMath.sol';
+import {FullMath} from '../../Uniswap/FullMath.sol';
+import {LiquidityMath} from '../../Uniswap/LiquidityMath.sol';
+import {LowGasSafeMath} from '../../Uniswap/LowGasSafeMath.sol';
+import {SafeCast} from '../../Uniswap/SafeCast.sol';
+import {SqrtPriceMath} from '../../Uniswap/SqrtPriceMath.sol';
+import {SwapMath} from '../../Uniswap/SwapMath.sol';
+import {TickBitmap} from '../../Uniswap/TickBitmap.sol';
+import {TickMath} from '../../Uniswap/TickMath.sol';
+import {UnsafeMath} from '../../Uniswap/UnsafeMath.sol';
+
+/// @notice Exposes the vendored libraries as external functions so the Rust port can be
+/// executed against them in an in-process EVM. Every function reverts exactly when the
+/// underlying library does.
+contract UniswapV3MathHarness {
+    using TickBitmap for mapping(int16 => uint256);
+
+    mapping(int16 => uint256) public tickBitmap;
+
+    function mostSignificantBit(uint256 x) external pure returns (uint8) {
+        return BitMath.mostSignificantBit(x);
+    }
+
+    function leastSignificantBit(uint256 x) external pure returns (uint8) {
+        return BitMath.leastSignificantBit(x);
+    }
+
+    function mulDiv(uint256 a, uint256 b, uint256 denominator) external pure returns (uint256) {
+        return FullMath.mulDiv(a, b, denominator);
+    }
+
+    function mulDivRoundingUp(uint256 a, uint256 b, uint256 denominator) external pure returns (uint256) {
+        return FullMath.mulDivRoundingUp(a, b, denominator);
+    }
+
+    function divRoundingUp(uint256 x, uint256 y) external pure returns (uint256) {
+        return UnsafeMath.divRoundingUp(x, y);
+    }
+
+    function addDelta(uint128 x, int128 y) external pure returns (uint128) {
+        return LiquidityMath.addDelta(x, y);
+    }
+
+    function add(uint256 x, uint256 y) external pure returns (uint256) {
+        return LowGasSafeMath.add(x, y);
+    }
+
+    function sub(uint256 x, uint256 y) external pure returns (uint256) {
+        return LowGasSafeMath.sub(x, y);
+    }
+
+    function mul(uint256 x, uint256 y) external pure returns (uint256) {
+        return LowGasSafeMath.mul(x, y);
+    }
+
+    function addSigned(int256 x, int256 y) external pure returns (int256) {
+        return LowGasSafeMath.add(x, y);
+    }
+
+    function subSigned(int256 x, int256 y) external pure returns (int256) {
+        return LowGasSafeMath.sub(x, y);
+    }
+
+    function toUint160(uint256 y) external pure returns (uint160) {
+        return SafeCast.toUint160(y);
+    }
+
+    function toInt128(int256 y) external pure returns (int128) {
+        return SafeCast.toInt128(y);
+    }
+
+    function toInt256(uint256 y) external pure returns (int256) {
+        return SafeCast.toInt256(y);
+    }
+
+    function getSqrtRatioAtTick(int24 tick) external pure returns (uint160) {
+        return TickMath.getSqrtRatioAtTick(tick);
+    }
+
+    function getTickAtSqrtRatio(uint160 sqrtPriceX96) external pure returns (int24) {
+        return TickMath.getTickAtSqrtRatio(sqrtPriceX96);
+    }
+
+    function getNextSqrtPriceFromInput(
+        uint160 sqrtPX96,
+        uint128 liquidity,
+        uint256 amountIn,
+        bool zeroForOne
+    ) external pure returns (uint160) {
+        return SqrtPriceMath.getNextSqrtPriceFromInput(sqrtPX96, liquidity, amountIn, zeroForOne);
+    }
+
+    function getNextSqrtPriceFromOutput(
+        uint160 sqrtPX96,
+        uint128 liquidity,
+        uint256 amountOut,
+        bool zeroForOne
+    ) external pure returns (uint160) {
+        return SqrtPriceMath.getNextSqrtPriceFromOutput(sqrtPX96, liquidity, amountOut, zeroForOne);
+    }
+
+    function getAmount0Delta(
+        uint160 sqrtRatioAX96,
+        uint160 sqrtRatioBX96,
+        uint128 liquidity,
+        bool roundUp
+    ) external pure returns (uint256) {
+        return SqrtPriceMath.getAmount0Delta(sqrtRatioAX96, sqrtRatioBX96, liquidity, roundUp);
+    }
+
+    function getAmount1Delta(
+        uint160 sqrtRatioAX96,
+        uint160 sqrtRatioBX96,
+        uint128 liquidity,
+        bool roundUp
+    ) external pure returns (uint256) {
+        return SqrtPriceMath.getAmount1Delta(sqrtRatioAX96, sqrtRatioBX96, liquidity, roundUp);
+    }
+
+    function getAmount0DeltaSigned(
+        uint160 sqrtRatioAX96,
+        uint160 sqrtRatioBX96,
+        int128 liquidity
+    ) external pure returns (int256) {
+        return SqrtPriceMath.getAmount0Delta(sqrtRatioAX96, sqrtRatioBX96, liquidity);
+    }
+
+    function getAmount1DeltaSigned(
+        uint160 sqrtRatioAX96,
+        uint160 sqrtRatioBX96,
+        int128 liquidity
+    ) external pure returns (int256) {
+        return SqrtPriceMath.getAmount1Delta(sqrtRatioAX96, sqrtRatioBX96, liquidity);
+    }
+
+    function computeSwapStep(
+        uint160 sqrtRatioCurrentX96,
+        uint160 sqrtRatioTargetX96,
+        uint128 liquidity,
+        int256 amountRemaining,
+        uint24 feePips
+    )
+        external
+        pure
+        returns (
+            uint160 sqrtRatioNextX96,
+            uint256 amountIn,
+            uint256 amountOut,
+            uint256 feeAmount
+        )
+    {
+        return SwapMath.computeSwapStep(sqrtRatioCurrentX96, sqrtRatioTargetX96, liquidity, amountRemaining, feePips);
+    }
+
+    function flipTick(int24 tick, int24 tickSpacing) external {
+        tickBitmap.flipTick(tick, tickSpacing);
+    }
+
+    function nextInitializedTickWithinOneWord(
+        int24 tick,
+        int24 tickSpacing,
+        bool lte
+    ) external view returns (int24 next, bool initialized) {
+        return tickBitmap.nextInitializedTickWithinOneWord(tick, tickSpacing, lte);
+    }
+}
@@ -0,0 +1,194 @@
+//! In-process EVM used to execute the compiled Solidity harness.
+
+use std::path::PathBuf;
+
+use alloy_primitives::{keccak256, Address, I256, U256};
+use revm::{
+    db::{CacheDB, EmptyDB},
+    primitives::{
+        AccountInfo, Address as EvmAddress, Bytecode, Bytes, ExecutionResult, Output, TxKind,
+    },
+    Evm,
+};
+
+const HARNESS_ADDRESS: EvmAddress = EvmAddress::with_last_byte(0x42);
+const CALLER_ADDRESS: EvmAddress = EvmAddress::with_last_byte(0x01);
+
+//Path of a bytecode file produced by `build.sh`
+pub fn bytecode_path(file: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("bytecode")
+        .join(file)
+}
+
+//Panics when the file has not been built, so that a missing artifact fails the suite
+fn read_bytecode(file: &str) -> Bytes {
+    let path = bytecode_path(file);
+    let hex = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "{} could not be read ({err}), run differential/build.sh",
+            path.display()
+        )
+    });
+    Bytes::from(alloy_primitives::hex::decode(hex.trim()).expect("invalid bytecode"))
+}
+
+//A single ABI word, either an unsigned or a two's complement signed value
+pub enum Word {
+    Uint(U256),
+    Int(I256),
+    Bool(bool),
+}
+
+impl From<U256> for Word {
+    fn from(value: U256) -> Self {
+        Word::Uint(value)
+    }
+}
+
+impl From<I256> for Word {
+    fn from(value: I256) -> Self {
+        Word::Int(value)
+    }
+}
+
+impl From<bool> for Word {
+    fn from(value: bool) -> Self {
+        Word::Bool(value)
+    }
+}
+
+impl From<u128> for Word {
+    fn from(value: u128) -> Self {
+        Word::Uint(U256::from(value))
+    }
+}
+
+impl From<u32> for Word {
+    fn from(value: u32) -> Self {
+        Word::Uint(U256::from(value))
+    }
+}
+
+impl From<i128> for Word {
+    fn from(value: i128) -> Self {
+        Word::Int(I256::try_from(value).unwrap())
+    }
+}
+
+impl From<i32> for Word {
+    fn from(value: i32) -> Self {
+        Word::Int(I256::try_from(value).unwrap())
+    }
+}
+
+impl From<Address> for Word {
+    fn from(value: Address) -> Self {
+        Word::Uint(U256::from_be_slice(value.as_slice()))
+    }
+}
+
+impl Word {
+    fn encode(&self) -> [u8; 32] {
+        match self {
+            Word::Uint(value) => value.to_be_bytes(),
+            Word::Int(value) => value.into_raw().to_be_bytes(),
+            Word::Bool(value) => U256::from(*value as u8).to_be_bytes(),
+        }
+    }
+}
+
+//The decoded return words of a call that did not revert
+pub struct Returned(Vec<U256>);
+
+impl Returned {
+    pub fn uint(&self, index: usize) -> U256 {
+        self.0[index]
+    }
+
+    pub fn int(&self, index: usize) -> I256 {
+        I256::from_raw(self.0[index])
+    }
+
+    pub fn bool(&self, index: usize) -> bool {
+        !self.0[index].is_zero()
+    }
+}
+
+//In-process EVM holding the compiled harness contract
+pub struct Harness {
+    evm: Evm<'static, (), CacheDB<EmptyDB>>,
+}
+
+impl Harness {
+    //Loads the math harness
+    pub fn load() -> Harness {
+        Harness::load_contract("UniswapV3MathHarness")
+    }
+
+    //Loads the runtime bytecode of a contract compiled by `build.sh` as the harness
+    pub fn load_contract(name: &str) -> Harness {
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            HARNESS_ADDRESS,
+            AccountInfo {
+                code: Some(Bytecode::new_raw(read_bytecode(&format!(
+                    "{name}.bin-runtime"
+                )))),
+                ..Default::default()
+            },
+        );
+
+        let evm = Evm::builder()
+            .with_db(db)
+            .modify_tx_env(|tx| {
+                tx.caller = CALLER_ADDRESS;
+                tx.transact_to = TxKind::Call(HARNESS_ADDRESS);
+                tx.gas_limit = 30_000_000;
+            })
+            .build();
+
+        Harness { evm }
+    }
+
+    //Places the contents of a bytecode file as the code of `address`, e.g. creation code for the harness to deploy
+    pub fn insert_code(&mut self, address: Address, file: &str) {
+        self.evm.db_mut().insert_account_info(
+            EvmAddress::from(address.into_array()),
+            AccountInfo {
+                code: Some(Bytecode::new_raw(read_bytecode(file))),
+                ..Default::default()
+            },
+        );
+    }
+
+    pub fn set_timestamp(&mut self, timestamp: u64) {
+        self.evm.block_mut().timestamp = U256::from(timestamp);
+    }
+
+    //Calls `signature` with `args`, committing any state changes. Returns None if the call reverted.
+    pub fn call(&mut self, signature: &str, args: &[Word]) -> Option<Returned> {
+        let mut calldata = keccak256(signature.as_bytes())[..4].to_vec();
+        for arg in args {
+            calldata.extend_from_slice(&arg.encode());
+        }
+
+        self.evm.tx_mut().data = Bytes::from(calldata);
+        self.evm.tx_mut().nonce = None;
+
+        match self
+            .evm
+            .transact_commit()
+            .expect("harness transaction failed validation")
+        {
+            ExecutionResult::Success {
+                output: Output::Call(bytes),
+                ..
+            } => Some(Returned(
+                bytes.chunks(32).map(U256::from_be_slice).collect(),
+            )),
+            ExecutionResult::Success { .. } => panic!("unexpected create output"),
+            ExecutionResult::Revert { .. } | ExecutionResult::Halt { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,470 @@
+//! Differential fuzzing of the Rust port against the vendored Solidity libraries.
+//!
+//! `contracts/UniswapV3MathHarness.sol` wraps every library function in an external call. Its
+//! runtime bytecode is produced by `build.sh` and executed in an in-process revm instance, so the
+//! whole suite runs offline. For every fuzzed input the Solidity call must revert exactly when the
+//! Rust function returns an error, and must otherwise return the same values.
+//!
+//! The bytecode must be built before running the tests, which fail when it is missing.
+
+use std::{cell::RefCell, collections::HashMap, fmt::Debug};
+
+use alloy_primitives::{I256, U256};
+use proptest::{
+    prelude::*,
+    test_runner::{Config, TestCaseError, TestRunner},
+};
+use uniswap_v3_math::{
+    bit_math, full_math, liquidity_math, low_gas_safe_math, safe_cast, sqrt_price_math, swap_math,
+    tick_bitmap::{self, next_initialized_tick_within_one_word},
+    tick_math::{self, MAX_TICK, MIN_TICK},
+    unsafe_math,
+};
+use uniswap_v3_math_differential::{Harness, Returned};
+
+fn harness() -> RefCell<Harness> {
+    RefCell::new(Harness::load())
+}
+
+fn run<S: Strategy>(strategy: S, test: impl Fn(S::Value) -> Result<(), TestCaseError>)
+where
+    S::Value: Debug,
+{
+    TestRunner::new(Config::default())
+        .run(&strategy, test)
+        .unwrap();
+}
+
+//Checks that the EVM reverted iff the Rust function errored and that both agree on the result
+fn check<T: PartialEq + Debug, E: Debug>(
+    function: &str,
+    evm: Option<T>,
+    rust: Result<T, E>,
+) -> Result<(), TestCaseError> {
+    match (evm, rust) {
+        (Some(expected), Ok(actual)) => {
+            prop_assert_eq!(expected, actual, "{} returned a different value", function)
+        }
+        (None, Err(_)) => {}
+        (Some(expected), Err(err)) => {
+            return Err(TestCaseError::fail(format!(
+                "{function} returned {expected:?} in the EVM but errored in Rust: {err:?}"
+            )))
+        }
+        (None, Ok(actual)) => {
+            return Err(TestCaseError::fail(format!(
+                "{function} reverted in the EVM but returned {actual:?} in Rust"
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn uint256() -> impl Strategy<Value = U256> {
+    prop_oneof![
+        any::<[u64; 4]>().prop_map(U256::from_limbs),
+        any::<u128>().prop_map(U256::from),
+        (0..256usize).prop_map(|bit| U256::from(1) << bit),
+        (0..256usize).prop_map(|bit| (U256::from(1) << bit) - U256::from(1)),
+    ]
+}
+
+fn uint160() -> impl Strategy<Value = U256> {
+    uint256().prop_map(|value| value & sqrt_price_math::MAX_U160)
+}
+
+//Sqrt prices concentrated around the valid tick range with occasional out of range values
+fn sqrt_price() -> impl Strategy<Value = U256> {
+    prop_oneof![
+        3 => (MIN_TICK..=MAX_TICK).prop_map(|tick| tick_math::get_sqrt_ratio_at_tick(tick).unwrap()),
+        1 => uint160(),
+    ]
+}
+
+fn liquidity() -> impl Strategy<Value = u128> {
+    prop_oneof![
+        any::<u128>(),
+        0..1_000_000u128,
+        (0..128u32).prop_map(|bit| 1u128 << bit)
+    ]
+}
+
+fn signed_liquidity() -> impl Strategy<Value = i128> {
+    prop_oneof![any::<i128>(), -1_000_000..1_000_000i128]
+}
+
+fn returned(returned: Option<Returned>) -> Option<U256> {
+    returned.map(|returned| returned.uint(0))
+}
+
+#[test]
+fn differential_bit_math() {
+    let harness = harness();
+
+    run(uint256(), |x| {
+        let mut harness = harness.borrow_mut();
+        check(
+            "mostSignificantBit",
+            harness
+                .call("mostSignificantBit(uint256)", &[x.into()])
+                .map(|r| r.uint(0).to::<u8>()),
+            bit_math::most_significant_bit(x),
+        )?;
+        check(
+            "leastSignificantBit",
+            harness
+                .call("leastSignificantBit(uint256)", &[x.into()])
+                .map(|r| r.uint(0).to::<u8>()),
+            bit_math::least_significant_bit(x),
+        )
+    });
+}
+
+#[test]
+fn differential_full_math() {
+    let harness = harness();
+
+    run((uint256(), uint256(), uint256()), |(a, b, denominator)| {
+        let mut harness = harness.borrow_mut();
+        let args = [a.into(), b.into(), denominator.into()];
+        check(
+            "mulDiv",
+            returned(harness.call("mulDiv(uint256,uint256,uint256)", &args)),
+            full_math::mul_div(a, b, denominator),
+        )?;
+        check(
+            "mulDivRoundingUp",
+            returned(harness.call("mulDivRoundingUp(uint256,uint256,uint256)", &args)),
+            full_math::mul_div_rounding_up(a, b, denominator),
+        )
+    });
+}
+
+#[test]
+fn differential_unsafe_math() {
+    let harness = harness();
+
+    run((uint256(), uint256()), |(x, y)| {
+        let mut harness = harness.borrow_mut();
+        check::<_, ()>(
+            "divRoundingUp",
+            returned(harness.call("divRoundingUp(uint256,uint256)", &[x.into(), y.into()])),
+            Ok(unsafe_math::div_rounding_up(x, y)),
+        )
+    });
+}
+
+#[test]
+fn differential_liquidity_math() {
+    let harness = harness();
+
+    run((liquidity(), signed_liquidity()), |(x, y)| {
+        let mut harness = harness.borrow_mut();
+        check(
+            "addDelta",
+            harness
+                .call("addDelta(uint128,int128)", &[x.into(), y.into()])
+                .map(|r| r.uint(0).to::<u128>()),
+            liquidity_math::add_delta(x, y),
+        )
+    });
+}
+
+#[test]
+fn differential_low_gas_safe_math() {
+    let harness = harness();
+
+    run((uint256(), uint256()), |(x, y)| {
+        let mut harness = harness.borrow_mut();
+        let args = [x.into(), y.into()];
+        check(
+            "add",
+            returned(harness.call("add(uint256,uint256)", &args)),
+            low_gas_safe_math::add(x, y),
+        )?;
+        check(
+            "sub",
+            returned(harness.call("sub(uint256,uint256)", &args)),
+            low_gas_safe_math::sub(x, y),
+        )?;
+        check(
+            "mul",
+            returned(harness.call("mul(uint256,uint256)", &args)),
+            low_gas_safe_math::mul(x, y),
+        )?;
+
+        let (x, y) = (I256::from_raw(x), I256::from_raw(y));
+        let args = [x.into(), y.into()];
+        check(
+            "addSigned",
+            harness
+                .call("addSigned(int256,int256)", &args)
+                .map(|r| r.int(0)),
+            low_gas_safe_math::add_signed(x, y),
+        )?;
+        check(
+            "subSigned",
+            harness
+                .call("subSigned(int256,int256)", &args)
+                .map(|r| r.int(0)),
+            low_gas_safe_math::sub_signed(x, y),
+        )
+    });
+}
+
+#[test]
+fn differential_safe_cast() {
+    let harness = harness();
+
+    run(uint256(), |y| {
+        let mut harness = harness.borrow_mut();
+        check(
+            "toUint160",
+            returned(harness.call("toUint160(uint256)", &[y.into()])),
+            safe_cast::to_uint160(y),
+        )?;
+        check(
+            "toInt256",
+            harness
+                .call("toInt256(uint256)", &[y.into()])
+                .map(|r| r.int(0)),
+            safe_cast::to_int256(y),
+        )?;
+        check(
+            "toInt128",
+            harness
+                .call("toInt128(int256)", &[I256::from_raw(y).into()])
+                .map(|r| i128::try_from(r.int(0)).unwrap()),
+            safe_cast::to_int128(I256::from_raw(y)),
+        )
+    });
+}
+
+#[test]
+fn differential_tick_math() {
+    let harness = harness();
+
+    run(MIN_TICK - 1000..=MAX_TICK + 1000, |tick| {
+        let mut harness = harness.borrow_mut();
+        check(
+            "getSqrtRatioAtTick",
+            returned(harness.call("getSqrtRatioAtTick(int24)", &[tick.into()])),
+            tick_math::get_sqrt_ratio_at_tick(tick),
+        )
+    });
+
+    run(sqrt_price(), |sqrt_price_x_96| {
+        let mut harness = harness.borrow_mut();
+        check(
+            "getTickAtSqrtRatio",
+            harness
+                .call("getTickAtSqrtRatio(uint160)", &[sqrt_price_x_96.into()])
+                .map(|r| r.int(0).as_i32()),
+            tick_math::get_tick_at_sqrt_ratio(sqrt_price_x_96),
+        )
+    });
+}
+
+#[test]
+fn differential_sqrt_price_math() {
+    let harness = harness();
+
+    run(
+        (sqrt_price(), liquidity(), uint256(), any::<bool>()),
+        |(sqrt_price_x_96, liquidity, amount, zero_for_one)| {
+            let mut harness = harness.borrow_mut();
+            let args = [
+                sqrt_price_x_96.into(),
+                liquidity.into(),
+                amount.into(),
+                zero_for_one.into(),
+            ];
+            check(
+                "getNextSqrtPriceFromInput",
+                returned(harness.call(
+                    "getNextSqrtPriceFromInput(uint160,uint128,uint256,bool)",
+                    &args,
+                )),
+                sqrt_price_math::get_next_sqrt_price_from_input(
+                    sqrt_price_x_96,
+                    liquidity,
+                    amount,
+                    zero_for_one,
+                ),
+            )?;
+            check(
+                "getNextSqrtPriceFromOutput",
+                returned(harness.call(
+                    "getNextSqrtPriceFromOutput(uint160,uint128,uint256,bool)",
+                    &args,
+                )),
+                sqrt_price_math::get_next_sqrt_price_from_output(
+                    sqrt_price_x_96,
+                    liquidity,
+                    amount,
+                    zero_for_one,
+                ),
+            )
+        },
+    );
+
+    run(
+        (sqrt_price(), sqrt_price(), liquidity(), any::<bool>()),
+        |(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity, round_up)| {
+            let mut harness = harness.borrow_mut();
+            let args = [
+                sqrt_ratio_a_x_96.into(),
+                sqrt_ratio_b_x_96.into(),
+                liquidity.into(),
+                round_up.into(),
+            ];
+            check(
+                "getAmount0Delta",
+                returned(harness.call("getAmount0Delta(uint160,uint160,uint128,bool)", &args)),
+                sqrt_price_math::_get_amount_0_delta(
+                    sqrt_ratio_a_x_96,
+                    sqrt_ratio_b_x_96,
+                    liquidity,
+                    round_up,
+                ),
+            )?;
+            check(
+                "getAmount1Delta",
+                returned(harness.call("getAmount1Delta(uint160,uint160,uint128,bool)", &args)),
+                sqrt_price_math::_get_amount_1_delta(
+                    sqrt_ratio_a_x_96,
+                    sqrt_ratio_b_x_96,
+                    liquidity,
+                    round_up,
+                ),
+            )
+        },
+    );
+
+    run(
+        (sqrt_price(), sqrt_price(), signed_liquidity()),
+        |(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity)| {
+            let mut harness = harness.borrow_mut();
+            let args = [
+                sqrt_ratio_a_x_96.into(),
+                sqrt_ratio_b_x_96.into(),
+                liquidity.into(),
+            ];
+            check(
+                "getAmount0DeltaSigned",
+                harness
+                    .call("getAmount0DeltaSigned(uint160,uint160,int128)", &args)
+                    .map(|r| r.int(0)),
+                sqrt_price_math::get_amount_0_delta(
+                    sqrt_ratio_a_x_96,
+                    sqrt_ratio_b_x_96,
+                    liquidity,
+                ),
+            )?;
+            check(
+                "getAmount1DeltaSigned",
+                harness
+                    .call("getAmount1DeltaSigned(uint160,uint160,int128)", &args)
+                    .map(|r| r.int(0)),
+                sqrt_price_math::get_amount_1_delta(
+                    sqrt_ratio_a_x_96,
+                    sqrt_ratio_b_x_96,
+                    liquidity,
+                ),
+            )
+        },
+    );
+}
+
+#[test]
+fn differential_swap_math() {
+    let harness = harness();
+
+    let amount_remaining = prop_oneof![
+        uint256().prop_map(I256::from_raw),
+        any::<i128>().prop_map(|amount| I256::try_from(amount).unwrap()),
+    ];
+
+    run(
+        (
+            sqrt_price(),
+            sqrt_price(),
+            liquidity(),
+            amount_remaining,
+            0..1_000_000u32,
+        ),
+        |(
+            sqrt_ratio_current_x_96,
+            sqrt_ratio_target_x_96,
+            liquidity,
+            amount_remaining,
+            fee_pips,
+        )| {
+            let mut harness = harness.borrow_mut();
+            check(
+                "computeSwapStep",
+                harness
+                    .call(
+                        "computeSwapStep(uint160,uint160,uint128,int256,uint24)",
+                        &[
+                            sqrt_ratio_current_x_96.into(),
+                            sqrt_ratio_target_x_96.into(),
+                            liquidity.into(),
+                            amount_remaining.into(),
+                            fee_pips.into(),
+                        ],
+                    )
+                    .map(|r| (r.uint(0), r.uint(1), r.uint(2), r.uint(3))),
+                swap_math::compute_swap_step(
+                    sqrt_ratio_current_x_96,
+                    sqrt_ratio_target_x_96,
+                    liquidity,
+                    amount_remaining,
+                    fee_pips,
+                ),
+            )
+        },
+    );
+}
+
+#[test]
+fn differential_tick_bitmap() {
+    let tick_spacing = prop_oneof![Just(1), Just(10), Just(60), Just(200)];
+    let flips = prop::collection::vec(-10_000..10_000i32, 1..32);
+    let queries = prop::collection::vec((-12_000..12_000i32, any::<bool>()), 1..32);
+
+    //Each case starts from a fresh contract so that its bitmap matches the empty HashMap
+    run(
+        (tick_spacing, flips, queries),
+        |(tick_spacing, flips, queries)| {
+            let mut harness = Harness::load();
+            let mut tick_bitmap = HashMap::new();
+
+            for tick in flips {
+                let tick = tick - tick % tick_spacing;
+                check(
+                    "flipTick",
+                    harness
+                        .call("flipTick(int24,int24)", &[tick.into(), tick_spacing.into()])
+                        .map(|_| ()),
+                    tick_bitmap::flip_tick(&mut tick_bitmap, tick, tick_spacing),
+                )?;
+            }
+
+            for (tick, lte) in queries {
+                check(
+                    "nextInitializedTickWithinOneWord",
+                    harness
+                        .call(
+                            "nextInitializedTickWithinOneWord(int24,int24,bool)",
+                            &[tick.into(), tick_spacing.into(), lte.into()],
+                        )
+                        .map(|r| (r.int(0).as_i32(), r.bool(1))),
+                    next_initialized_tick_within_one_word(&tick_bitmap, tick, tick_spacing, lte),
+                )?;
+            }
+
+            Ok(())
+        },
+    );
+}
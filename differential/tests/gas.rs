@@ -0,0 +1,134 @@
+//! Checks the default gas model against UniswapV3Pool.swap as QuoterV2 measures it.
+//!
+//! `contracts/PoolGasHarness.sol` deploys the pool compiled from v3-core by `build.sh` and quotes swaps the way
+//! QuoterV2 does, returning the gas used by a swap that reverts in its callback. The same swaps are simulated on the
+//! Rust pool and estimated from their traces. Each quote runs in its own transaction, so every slot starts cold as
+//! it does for a quote sent with eth_call.
+
+use alloy_primitives::{Address, I256, U256};
+use uniswap_v3_math::{
+    dialect::{Dialect, PoolConfig},
+    gas::{GasModel, SwapGasUsage},
+    pool::Pool,
+    tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO},
+};
+use uniswap_v3_math_differential::{Harness, Word};
+
+const SQRT_PRICE_1_1: U256 = U256::from_limbs([0, 4294967296, 0, 0]);
+const POOL_CODE: Address = Address::with_last_byte(0x43);
+
+//The largest relative difference allowed between an estimate and the measured gas. The model does not tell apart
+//writes to zero slots, which cost 17,100 more, nor the oracle observation a swap writes when it moves the tick.
+const TOLERANCE: f64 = 0.1;
+
+//A wide position and narrower ones on either side of the price, so that swaps cross from none to several initialized
+//ticks
+const POSITIONS: [(i32, i32, u128); 5] = [
+    (-60000, 60000, 1e18 as u128),
+    (-120, 120, 1e18 as u128),
+    (-3000, 3000, 2e17 as u128),
+    (-1200, -600, 5e17 as u128),
+    (600, 1200, 5e17 as u128),
+];
+
+fn limit(zero_for_one: bool) -> U256 {
+    if zero_for_one {
+        MIN_SQRT_RATIO + U256::from(1)
+    } else {
+        MAX_SQRT_RATIO - U256::from(1)
+    }
+}
+
+fn swap_args(zero_for_one: bool, amount_specified: I256) -> [Word; 3] {
+    [
+        zero_for_one.into(),
+        amount_specified.into(),
+        limit(zero_for_one).into(),
+    ]
+}
+
+#[test]
+fn gas_model_matches_quoter_estimates() {
+    let mut harness = Harness::load_contract("PoolGasHarness");
+    harness.insert_code(POOL_CODE, "UniswapV3Pool.bin");
+    let config = PoolConfig::new(Dialect::UniswapV3, 3000).unwrap();
+    let mut pool = Pool::initialize(config, SQRT_PRICE_1_1).unwrap();
+
+    harness.set_timestamp(1);
+    harness
+        .call(
+            "createPool(address,uint24,int24,uint160)",
+            &[
+                POOL_CODE.into(),
+                3000u32.into(),
+                60i32.into(),
+                SQRT_PRICE_1_1.into(),
+            ],
+        )
+        .expect("the pool could not be created");
+    for (tick_lower, tick_upper, amount) in POSITIONS {
+        harness
+            .call(
+                "mint(int24,int24,uint128)",
+                &[tick_lower.into(), tick_upper.into(), amount.into()],
+            )
+            .expect("mint reverted");
+        pool.mint(Address::ZERO, tick_lower, tick_upper, amount)
+            .unwrap();
+    }
+
+    // swaps back and forth accrue fees on both sides and cross the inner ticks, so that crossings write nonzero
+    // accumulators over nonzero ones as they do in an active pool
+    for (zero_for_one, amount) in [(false, 3e17), (true, 6e17), (false, 3e17)] {
+        let amount_specified = I256::try_from(amount as i128).unwrap();
+        harness
+            .call(
+                "swap(bool,int256,uint160)",
+                &swap_args(zero_for_one, amount_specified),
+            )
+            .expect("swap reverted");
+        pool.swap(zero_for_one, amount_specified, limit(zero_for_one))
+            .unwrap();
+    }
+    let price = harness.call("price()", &[]).unwrap();
+    assert_eq!(price.uint(0), pool.slot0.sqrt_price_x_96);
+    assert_eq!(price.int(1), I256::try_from(pool.slot0.tick).unwrap());
+
+    // quotes come in a later block than the pool's last observation, as they usually do
+    harness.set_timestamp(13);
+
+    let model = GasModel::default();
+    let mut samples = vec![];
+    for zero_for_one in [true, false] {
+        for amount in [1e15, 1e16, 1e17, 5e17, 1e18, -1e15, -1e17] {
+            let amount_specified = I256::try_from(amount as i128).unwrap();
+            let (_, trace) = pool
+                .clone()
+                .swap_with_trace(zero_for_one, amount_specified, limit(zero_for_one))
+                .unwrap();
+            let usage = SwapGasUsage::from_trace(&trace);
+            let measured = harness
+                .call(
+                    "quote(bool,int256,uint160)",
+                    &swap_args(zero_for_one, amount_specified),
+                )
+                .expect("quote reverted")
+                .uint(0)
+                .to::<u64>();
+            samples.push((usage, measured));
+        }
+    }
+
+    let mismatches = samples
+        .iter()
+        .filter(|(usage, measured)| {
+            let estimate = model.estimate(usage);
+            estimate.abs_diff(*measured) as f64 > TOLERANCE * *measured as f64
+        })
+        .collect::<Vec<_>>();
+    assert!(
+        mismatches.is_empty(),
+        "estimates off by more than {TOLERANCE}: {mismatches:#?}, calibrated model: {:#?}",
+        model.calibrate(&samples)
+    );
+}
@@ -0,0 +1,237 @@
+use alloy_primitives::U256;
+
+use crate::error::UniswapV3MathError;
+
+//The fee charged when volatility is zero, in hundredths of a bip (1e-6)
+pub const BASE_FEE: u16 = 100;
+//The initial lower bound of the dynamic fee, in hundredths of a bip (1e-6)
+pub const INITIAL_MIN_FEE: u16 = 100;
+
+//Parameters of the two sigmoids whose sum, plus the base fee, make up the adaptive fee
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlgebraFeeConfiguration {
+    //max value of the first sigmoid, in hundredths of a bip (1e-6)
+    pub alpha_1: u16,
+    //max value of the second sigmoid, in hundredths of a bip (1e-6)
+    pub alpha_2: u16,
+    //shift along the x-axis (volatility) for the first sigmoid
+    pub beta_1: u32,
+    //shift along the x-axis (volatility) for the second sigmoid
+    pub beta_2: u32,
+    //horizontal stretch factor for the first sigmoid
+    pub gamma_1: u16,
+    //horizontal stretch factor for the second sigmoid
+    pub gamma_2: u16,
+    //in hundredths of a bip (1e-6)
+    pub base_fee: u16,
+}
+
+impl Default for AlgebraFeeConfiguration {
+    fn default() -> Self {
+        initial_fee_configuration()
+    }
+}
+
+//The configuration used by the Algebra plugin at deployment
+pub fn initial_fee_configuration() -> AlgebraFeeConfiguration {
+    AlgebraFeeConfiguration {
+        alpha_1: 3000 - BASE_FEE,
+        alpha_2: 15000 - 3000,
+        beta_1: 360,
+        beta_2: 60000,
+        gamma_1: 59,
+        gamma_2: 8500,
+        base_fee: BASE_FEE,
+    }
+}
+
+//Validates fee configuration, the maximum fee must fit in a uint16 and the sigmoids must have a non zero stretch
+pub fn validate_fee_configuration(
+    config: &AlgebraFeeConfiguration,
+) -> Result<(), UniswapV3MathError> {
+    if config.alpha_1 as u32 + config.alpha_2 as u32 + config.base_fee as u32 > u16::MAX as u32 {
+        return Err(UniswapV3MathError::MaxFeeExceeded);
+    }
+    if config.gamma_1 == 0 || config.gamma_2 == 0 {
+        return Err(UniswapV3MathError::GammasMustBeNonZero);
+    }
+    Ok(())
+}
+
+//Calculates fee based on formula:
+//baseFee + sigmoid1(volatility) + sigmoid2(volatility)
+//maximum value capped by baseFee + alpha1 + alpha2. The configuration is validated first, as the plugin does when it
+//is changed.
+pub fn get_fee(
+    volatility: u128,
+    config: &AlgebraFeeConfiguration,
+) -> Result<u16, UniswapV3MathError> {
+    validate_fee_configuration(config)?;
+
+    // normalize for 15 sec interval
+    let volatility = U256::from(volatility / 15);
+
+    let sum_of_sigmoids = sigmoid(
+        volatility,
+        config.gamma_1,
+        config.alpha_1,
+        U256::from(config.beta_1),
+    ) + sigmoid(
+        volatility,
+        config.gamma_2,
+        config.alpha_2,
+        U256::from(config.beta_2),
+    );
+
+    // safe since alpha1 + alpha2 + baseFee _must_ be <= type(uint16).max
+    Ok((U256::from(config.base_fee) + sum_of_sigmoids).to::<u16>())
+}
+
+//Calculates α / (1 + e^( (β-x) / γ)), that is a sigmoid with a maximum value of α, x-shifted by β, and stretched by γ.
+//Guaranteed that the result is not greater than alpha.
+pub fn sigmoid(x: U256, g: u16, alpha: u16, beta: U256) -> U256 {
+    let g_256 = U256::from(g);
+    let alpha = U256::from(alpha);
+
+    if x > beta {
+        let x = x - beta;
+        if x >= U256::from(6) * g_256 {
+            return alpha;
+        }
+        let g4 = g_256.pow(U256::from(4));
+        let ex = exp_x_g4(x, g, g4);
+        // in worst case: (16 + 155 bits) / 155 bits, so res <= alpha
+        (alpha * ex) / (g4 + ex)
+    } else {
+        let x = beta - x;
+        if x >= U256::from(6) * g_256 {
+            return U256::ZERO;
+        }
+        let g4 = g_256.pow(U256::from(4));
+        let ex = g4 + exp_x_g4(x, g, g4);
+        // g8 <= ex, so res <= alpha
+        (alpha * g4) / ex
+    }
+}
+
+//Calculates e^(x/g) * g^4 in a series, since (around zero):
+//e^x = 1 + x + x^2/2 + ... + x^n/n! + ...
+//e^(x/g) = 1 + x/g + x^2/(2*g^2) + ... + x^(n)/(g^n * n!) + ...
+//Has good accuracy only if x/g < 6
+pub fn exp_x_g4(x: U256, g: u16, g_highest_degree: U256) -> U256 {
+    let g_256 = U256::from(g);
+
+    // values rounded to 8 digits after point
+    let mut closest_value = U256::from(match (x / g_256).to::<u64>() {
+        0 => 100000000u64, // 1
+        1 => 271828183,    // ~= e
+        2 => 738905610,    // ~= e^2
+        3 => 2008553692,   // ~= e^3
+        4 => 5459815003,   // ~= e^4
+        _ => 14841315910,  // ~= e^5
+    });
+    let mut x = x % g_256;
+
+    let half_g = g_256 / U256::from(2);
+    if x >= half_g {
+        // (x - closestValue) >= 0.5, so closestValue := closestValue * e^0.5
+        x -= half_g;
+        closest_value = (closest_value * U256::from(164872127)) / U256::from(100000000);
+    }
+
+    // After calculating the closestValue x/g is <= 0.5, so that the series in the neighborhood of zero converges
+    // with sufficient speed
+    let mut x_lowest_degree = x;
+    let mut g_highest_degree = g_highest_degree;
+    let mut res = g_highest_degree; // g**4, res = 1
+
+    g_highest_degree /= g_256; // g**3
+    res += x_lowest_degree * g_highest_degree; // x, res = 1 + x
+
+    g_highest_degree /= g_256; // g**2
+    x_lowest_degree *= x; // x**2
+    res += (x_lowest_degree * g_highest_degree) / U256::from(2); // 1 + x + x^2/2
+
+    // 1 + x + x^2/2 + x^3/6 + x^4/24
+    x_lowest_degree *= x; // x**3
+    res += (x_lowest_degree * g_256 * U256::from(4) + x_lowest_degree * x) / U256::from(24);
+
+    // res = g^4 * (1 + x/g + x^2/(2*g^2) + x^3/(6*g^3) + x^4/(24*g^4)) * closestValue / 10^8
+    (res * closest_value) / U256::from(100000000)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate_fee_configuration() {
+        assert!(validate_fee_configuration(&initial_fee_configuration()).is_ok());
+
+        let result = validate_fee_configuration(&AlgebraFeeConfiguration {
+            alpha_1: u16::MAX,
+            ..Default::default()
+        });
+        assert_eq!(result.unwrap_err().to_string(), "Max fee exceeded");
+
+        let result = validate_fee_configuration(&AlgebraFeeConfiguration {
+            gamma_2: 0,
+            ..Default::default()
+        });
+        assert_eq!(result.unwrap_err().to_string(), "Gammas must be > 0");
+    }
+
+    #[test]
+    fn test_get_fee() {
+        let config = initial_fee_configuration();
+
+        // both sigmoids are zero without volatility
+        assert_eq!(get_fee(0, &config).unwrap(), BASE_FEE);
+        // the first sigmoid is at half its maximum at beta1
+        assert_eq!(
+            get_fee(360 * 15, &config).unwrap(),
+            BASE_FEE + config.alpha_1 / 2
+        );
+        // capped by baseFee + alpha1 + alpha2
+        assert_eq!(get_fee(u128::MAX >> 40, &config).unwrap(), 15000);
+
+        // the fee is monotonic in volatility
+        let mut last_fee = 0;
+        for volatility in (0..2_000_000u128).step_by(997) {
+            let fee = get_fee(volatility, &config).unwrap();
+            assert!(fee >= last_fee);
+            last_fee = fee;
+        }
+
+        // a configuration the plugin would reject is an error rather than an overflowing or zero divided fee
+        let config = AlgebraFeeConfiguration {
+            alpha_1: u16::MAX,
+            alpha_2: u16::MAX,
+            ..Default::default()
+        };
+        let result = get_fee(u128::MAX >> 40, &config);
+        assert_eq!(result.unwrap_err().to_string(), "Max fee exceeded");
+        let config = AlgebraFeeConfiguration {
+            gamma_1: 0,
+            ..Default::default()
+        };
+        let result = get_fee(360 * 15, &config);
+        assert_eq!(result.unwrap_err().to_string(), "Gammas must be > 0");
+    }
+
+    #[test]
+    fn test_exp_x_g4() {
+        // the series is accurate for x/g <= 0.5, rounding g / 2 down adds an error of at most e^(1/(2g))
+        for (g, step, tolerance) in [(59u16, 1, 1e-2), (8500, 7, 5e-4)] {
+            let g4 = U256::from(g).pow(U256::from(4));
+            for x in (0..(6 * g as u64)).step_by(step) {
+                let result = exp_x_g4(U256::from(x), g, g4).to::<u128>() as f64;
+                let expected = (x as f64 / g as f64).exp() * (g as f64).powi(4);
+                assert!(
+                    (result - expected).abs() / expected < tolerance,
+                    "g = {g}, x = {x}"
+                );
+            }
+        }
+    }
+}
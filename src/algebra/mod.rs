@@ -0,0 +1,7 @@
+//! Ports of the Algebra Integral libraries used by Camelot and QuickSwap pools. Algebra shares the sqrt price and
+//! tick math of this crate, but charges an adaptive fee derived from a volatility oracle and tracks initialized
+//! ticks in a tick tree instead of a flat bitmap.
+
+pub mod adaptive_fee;
+pub mod tick_tree;
+pub mod volatility_oracle;
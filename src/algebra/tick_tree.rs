@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use alloy_primitives::U256;
+
+use crate::{
+    bit_math::{least_significant_bit, most_significant_bit},
+    error::UniswapV3MathError,
+    tick_math::{MAX_TICK, MIN_TICK},
+    U256_1,
+};
+
+//Offset of the leaf indexes in the second layer, ceil(-MIN_TICK / 256)
+pub const SECOND_LAYER_OFFSET: i32 = 3466;
+
+//Packed tick initialized state. Every tick has a bit in the leafs, every non empty leaf has a bit in the second
+//layer and every non empty second layer node has a bit in the root, so the next initialized tick is found by reading
+//at most one word per layer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TickTree {
+    pub root: u32,
+    pub second_layer: HashMap<i16, U256>,
+    pub leafs: HashMap<i16, U256>,
+}
+
+//Flips a bit in a layer, returning whether the node became empty or non-empty and the index of the node
+fn toggle_bit_in_node(row: &mut HashMap<i16, U256>, bit_index: i32) -> (bool, i32) {
+    let node_index = bit_index >> 8;
+    let node = row.get(&(node_index as i16)).copied().unwrap_or_default();
+    let new_node = node ^ (U256_1 << (bit_index & 0xFF) as usize);
+
+    if new_node.is_zero() {
+        row.remove(&(node_index as i16));
+    } else {
+        row.insert(node_index as i16, new_node);
+    }
+
+    (node.is_zero() != new_node.is_zero(), node_index)
+}
+
+//Masks a node to the bits at or above bit
+fn bits_at_or_above(node: U256, bit: i32) -> U256 {
+    node & !((U256_1 << bit as usize) - U256_1)
+}
+
+//Masks a node to the bits at or below bit
+fn bits_at_or_below(node: U256, bit: i32) -> U256 {
+    if bit == 255 {
+        node
+    } else {
+        node & ((U256_1 << (bit + 1) as usize) - U256_1)
+    }
+}
+
+impl TickTree {
+    fn node(row: &HashMap<i16, U256>, index: i32) -> U256 {
+        row.get(&(index as i16)).copied().unwrap_or_default()
+    }
+
+    //Toggles a tick on or off
+    pub fn toggle_tick(&mut self, tick: i32) -> Result<(), UniswapV3MathError> {
+        if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+            return Err(UniswapV3MathError::T);
+        }
+
+        let (toggled_node, node_index) = toggle_bit_in_node(&mut self.leafs, tick);
+        if toggled_node {
+            let (toggled_node, node_index) =
+                toggle_bit_in_node(&mut self.second_layer, node_index + SECOND_LAYER_OFFSET);
+            if toggled_node {
+                self.root ^= 1 << node_index;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_initialized(&self, tick: i32) -> bool {
+        let leaf = Self::node(&self.leafs, tick >> 8);
+        !(leaf & (U256_1 << (tick & 0xFF) as usize)).is_zero()
+    }
+
+    //Returns the lowest initialized tick in a non empty leaf
+    fn lowest_in_leaf(&self, leaf_index: i32) -> Result<i32, UniswapV3MathError> {
+        let leaf = Self::node(&self.leafs, leaf_index);
+        Ok(leaf_index * 256 + least_significant_bit(leaf)? as i32)
+    }
+
+    //Returns the highest initialized tick in a non empty leaf
+    fn highest_in_leaf(&self, leaf_index: i32) -> Result<i32, UniswapV3MathError> {
+        let leaf = Self::node(&self.leafs, leaf_index);
+        Ok(leaf_index * 256 + most_significant_bit(leaf)? as i32)
+    }
+
+    //Returns the next initialized tick strictly greater than the given tick, or MAX_TICK if there is none
+    pub fn next_active_tick(&self, tick: i32) -> Result<i32, UniswapV3MathError> {
+        // start searching from the next tick
+        let tick = tick + 1;
+        if tick >= MAX_TICK {
+            return Ok(MAX_TICK);
+        }
+
+        // if we are in the same leaf, check for initialized ticks in it
+        let leaf_index = tick >> 8;
+        let leaf = bits_at_or_above(Self::node(&self.leafs, leaf_index), tick & 0xFF);
+        if !leaf.is_zero() {
+            return Ok(leaf_index * 256 + least_significant_bit(leaf)? as i32);
+        }
+
+        // else check the following leafs of the same second layer node
+        let second_layer_index = leaf_index + 1 + SECOND_LAYER_OFFSET;
+        let node_index = second_layer_index >> 8;
+        let node = bits_at_or_above(
+            Self::node(&self.second_layer, node_index),
+            second_layer_index & 0xFF,
+        );
+        if !node.is_zero() {
+            let leaf_index =
+                node_index * 256 + least_significant_bit(node)? as i32 - SECOND_LAYER_OFFSET;
+            return self.lowest_in_leaf(leaf_index);
+        }
+
+        // else find the next non empty second layer node in the root
+        let root = if node_index >= 31 {
+            0
+        } else {
+            self.root & !((1u32 << (node_index + 1)) - 1)
+        };
+        if root == 0 {
+            return Ok(MAX_TICK);
+        }
+        let node_index = root.trailing_zeros() as i32;
+        let node = Self::node(&self.second_layer, node_index);
+        let leaf_index =
+            node_index * 256 + least_significant_bit(node)? as i32 - SECOND_LAYER_OFFSET;
+        self.lowest_in_leaf(leaf_index)
+    }
+
+    //Returns the highest initialized tick less than or equal to the given tick, or MIN_TICK if there is none
+    pub fn prev_active_tick(&self, tick: i32) -> Result<i32, UniswapV3MathError> {
+        if tick <= MIN_TICK {
+            return Ok(MIN_TICK);
+        }
+        let tick = tick.min(MAX_TICK);
+
+        // if we are in the same leaf, check for initialized ticks in it
+        let leaf_index = tick >> 8;
+        let leaf = bits_at_or_below(Self::node(&self.leafs, leaf_index), tick & 0xFF);
+        if !leaf.is_zero() {
+            return Ok(leaf_index * 256 + most_significant_bit(leaf)? as i32);
+        }
+
+        // else check the preceding leafs of the same second layer node
+        let second_layer_index = leaf_index - 1 + SECOND_LAYER_OFFSET;
+        if second_layer_index < 0 {
+            return Ok(MIN_TICK);
+        }
+        let node_index = second_layer_index >> 8;
+        let node = bits_at_or_below(
+            Self::node(&self.second_layer, node_index),
+            second_layer_index & 0xFF,
+        );
+        if !node.is_zero() {
+            let leaf_index =
+                node_index * 256 + most_significant_bit(node)? as i32 - SECOND_LAYER_OFFSET;
+            return self.highest_in_leaf(leaf_index);
+        }
+
+        // else find the previous non empty second layer node in the root
+        let root = self.root & ((1u32 << node_index) - 1);
+        if root == 0 {
+            return Ok(MIN_TICK);
+        }
+        let node_index = 31 - root.leading_zeros() as i32;
+        let node = Self::node(&self.second_layer, node_index);
+        let leaf_index =
+            node_index * 256 + most_significant_bit(node)? as i32 - SECOND_LAYER_OFFSET;
+        self.highest_in_leaf(leaf_index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(not(target_arch = "wasm32"))]
+    use proptest::prelude::*;
+    #[cfg(not(target_arch = "wasm32"))]
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_toggle_tick() {
+        let mut tree = TickTree::default();
+
+        tree.toggle_tick(MIN_TICK).unwrap();
+        tree.toggle_tick(-1).unwrap();
+        tree.toggle_tick(MAX_TICK).unwrap();
+        assert!(tree.is_initialized(MIN_TICK));
+        assert!(tree.is_initialized(-1));
+        assert!(!tree.is_initialized(0));
+        assert_eq!(tree.root.count_ones(), 3);
+
+        tree.toggle_tick(-1).unwrap();
+        assert!(!tree.is_initialized(-1));
+        assert_eq!(tree.root.count_ones(), 2);
+
+        tree.toggle_tick(MIN_TICK).unwrap();
+        tree.toggle_tick(MAX_TICK).unwrap();
+        assert_eq!(tree, TickTree::default());
+
+        assert!(tree.toggle_tick(MAX_TICK + 1).is_err());
+    }
+
+    #[test]
+    fn test_next_and_prev_active_tick() {
+        let mut tree = TickTree::default();
+        assert_eq!(tree.next_active_tick(0).unwrap(), MAX_TICK);
+        assert_eq!(tree.prev_active_tick(0).unwrap(), MIN_TICK);
+
+        for tick in [-887220, -60, 0, 60, 256 * 300 + 5, 887220] {
+            tree.toggle_tick(tick).unwrap();
+        }
+
+        assert_eq!(tree.next_active_tick(MIN_TICK).unwrap(), -887220);
+        assert_eq!(tree.next_active_tick(-887220).unwrap(), -60);
+        assert_eq!(tree.next_active_tick(-61).unwrap(), -60);
+        assert_eq!(tree.next_active_tick(-60).unwrap(), 0);
+        assert_eq!(tree.next_active_tick(60).unwrap(), 256 * 300 + 5);
+        assert_eq!(tree.next_active_tick(256 * 300 + 5).unwrap(), 887220);
+        assert_eq!(tree.next_active_tick(887220).unwrap(), MAX_TICK);
+
+        assert_eq!(tree.prev_active_tick(MAX_TICK).unwrap(), 887220);
+        assert_eq!(tree.prev_active_tick(887219).unwrap(), 256 * 300 + 5);
+        assert_eq!(tree.prev_active_tick(59).unwrap(), 0);
+        assert_eq!(tree.prev_active_tick(0).unwrap(), 0);
+        assert_eq!(tree.prev_active_tick(-1).unwrap(), -60);
+        assert_eq!(tree.prev_active_tick(-887219).unwrap(), -887220);
+        assert_eq!(tree.prev_active_tick(-887221).unwrap(), MIN_TICK);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    proptest! {
+        #[test]
+        fn prop_matches_ordered_set(
+            ticks in prop::collection::vec(MIN_TICK..=MAX_TICK, 1..32),
+            query in MIN_TICK..=MAX_TICK,
+        ) {
+            let mut tree = TickTree::default();
+            let mut set = BTreeSet::new();
+            for tick in ticks {
+                tree.toggle_tick(tick).unwrap();
+                if !set.remove(&tick) {
+                    set.insert(tick);
+                }
+            }
+
+            let next = set.range(query + 1..).next().copied().unwrap_or(MAX_TICK);
+            let prev = set.range(..=query).next_back().copied().unwrap_or(MIN_TICK);
+            prop_assert_eq!(tree.next_active_tick(query).unwrap(), next.min(MAX_TICK));
+            prop_assert_eq!(tree.prev_active_tick(query).unwrap(), prev);
+        }
+    }
+}
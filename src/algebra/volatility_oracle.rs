@@ -0,0 +1,420 @@
+use std::collections::VecDeque;
+
+use alloy_primitives::I256;
+
+use crate::{
+    algebra::adaptive_fee::{self, AlgebraFeeConfiguration},
+    error::UniswapV3MathError,
+};
+
+//The period over which the average tick and average volatility are computed
+pub const WINDOW: u32 = 86400;
+//The number of timepoints stored, older timepoints are overwritten
+pub const UINT16_MODULO: usize = 65536;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timepoint {
+    pub initialized: bool,
+    pub block_timestamp: u32,
+    //the tick accumulator, i.e. tick * time elapsed since the pool was first initialized
+    pub tick_cumulative: i64,
+    //the volatility accumulator; overflow after ~34800 years is desired :)
+    pub volatility_cumulative: u128,
+    //tick at this block timestamp
+    pub tick: i32,
+    //average tick at this block timestamp, over the last WINDOW seconds
+    pub average_tick: i32,
+}
+
+//Timepoints ordered from oldest to newest, with at most one timepoint per block. Timestamps are compared without the
+//wraparound of the contract, times before the last timepoint or before 0 are rejected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VolatilityOracle {
+    pub timepoints: VecDeque<Timepoint>,
+}
+
+//Calculates the sum of (tick(t) - averageTick(t))^2 for every second t in (0, dt], with the tick and average tick
+//changing linearly from tick0 to tick1 and from avgTick0 to avgTick1 over the interval
+pub fn volatility_on_range(
+    dt: i64,
+    tick_0: i32,
+    tick_1: i32,
+    avg_tick_0: i32,
+    avg_tick_1: i32,
+) -> u128 {
+    // On the time interval from the previous timepoint to the current
+    // we can represent tick and average tick change as two straight lines:
+    // tick = k*t + b, where k and b are some constants
+    // avgTick = p*t + q, where p and q are some constants
+    // we want to get sum of (tick(t) - avgTick(t))^2 for every t in the interval (0; dt]
+    // so: (tick(t) - avgTick(t))^2 = ((k*t + b) - (p*t + q))^2 = (k-p)^2 * t^2 + 2(k-p)(b-q)t + (b-q)^2
+    // since everything except t is a constant, we need to use progressions for t and t^2:
+    // sum(t) for t from 1 to dt = dt*(dt + 1)/2 = sumOfSequence
+    // sum(t^2) for t from 1 to dt = dt*(dt+1)*(2dt + 1)/6 = sumOfSquares
+    // so result will be: (k-p)^2 * sumOfSquares + 2(k-p)(b-q)*sumOfSequence + dt*(b-q)^2
+    // the products exceed 128 bits for dt near 2^32 and large deviations, so they are computed in int256
+    let int = |value: i64| I256::try_from(value).unwrap();
+    let (six, dt) = (int(6), int(dt));
+    let k = (int(tick_1.into()) - int(tick_0.into()))
+        - (int(avg_tick_1.into()) - int(avg_tick_0.into())); // (k - p)*dt
+    let b = (int(tick_0.into()) - int(avg_tick_0.into())) * dt; // (b - q)*dt
+    let sum_of_squares = dt * (dt + I256::ONE) * (int(2) * dt + I256::ONE); // sumOfSquares * 6
+    let sum_of_sequence = dt * (dt + I256::ONE); // sumOfSequence * 2
+
+    // a sum of squares over at most 2^32 seconds of deviations within the tick range, so it fits in 128 bits
+    ((k * k * sum_of_squares + six * b * k * sum_of_sequence + six * dt * b * b) / (six * dt * dt))
+        .into_raw()
+        .to::<u128>()
+}
+
+//Transforms a previous timepoint into a new timepoint, given the passage of time and the current tick values
+fn create_new_timepoint(
+    last: Timepoint,
+    block_timestamp: u32,
+    tick: i32,
+    average_tick: i32,
+) -> Timepoint {
+    let delta = block_timestamp.wrapping_sub(last.block_timestamp);
+
+    Timepoint {
+        initialized: true,
+        block_timestamp,
+        tick_cumulative: last.tick_cumulative + tick as i64 * delta as i64,
+        volatility_cumulative: last.volatility_cumulative.wrapping_add(volatility_on_range(
+            delta as i64,
+            tick,
+            tick,
+            last.average_tick,
+            average_tick,
+        )),
+        tick,
+        average_tick,
+    }
+}
+
+impl VolatilityOracle {
+    //Initialize the oracle with its first timepoint
+    pub fn initialize(time: u32, tick: i32) -> VolatilityOracle {
+        VolatilityOracle {
+            timepoints: VecDeque::from([Timepoint {
+                initialized: true,
+                block_timestamp: time,
+                tick,
+                average_tick: tick,
+                ..Default::default()
+            }]),
+        }
+    }
+
+    fn last(&self) -> Result<&Timepoint, UniswapV3MathError> {
+        self.timepoints
+            .back()
+            .ok_or(UniswapV3MathError::OracleNotInitialized)
+    }
+
+    fn oldest(&self) -> Result<&Timepoint, UniswapV3MathError> {
+        self.timepoints
+            .front()
+            .ok_or(UniswapV3MathError::OracleNotInitialized)
+    }
+
+    //Writes a timepoint with the tick that was in effect since the last timepoint. Only the first write of a block is
+    //recorded, returns true if a timepoint was written.
+    pub fn write(&mut self, block_timestamp: u32, tick: i32) -> Result<bool, UniswapV3MathError> {
+        let last = *self.last()?;
+        // early return if we've already written a timepoint this block
+        if last.block_timestamp == block_timestamp {
+            return Ok(false);
+        }
+
+        let average_tick = self.get_average_tick(block_timestamp, tick)?;
+        self.timepoints.push_back(create_new_timepoint(
+            last,
+            block_timestamp,
+            tick,
+            average_tick,
+        ));
+        if self.timepoints.len() > UINT16_MODULO {
+            self.timepoints.pop_front();
+        }
+
+        Ok(true)
+    }
+
+    //Returns the time weighted average tick over the last WINDOW seconds, or over the whole history if it is shorter
+    pub fn get_average_tick(
+        &self,
+        current_time: u32,
+        tick: i32,
+    ) -> Result<i32, UniswapV3MathError> {
+        let last = *self.last()?;
+        let oldest = *self.oldest()?;
+        let time_since_last = current_time.checked_sub(last.block_timestamp).ok_or(
+            UniswapV3MathError::TimeBeforeLastTimepoint(current_time, last.block_timestamp),
+        )?;
+
+        let window_start = match current_time.checked_sub(WINDOW) {
+            Some(window_start) if oldest.block_timestamp <= window_start => window_start,
+            _ => {
+                // if oldest timepoint was created less than WINDOW seconds ago
+                if last.block_timestamp == oldest.block_timestamp {
+                    return Ok(tick);
+                }
+                return Ok(((last.tick_cumulative - oldest.tick_cumulative)
+                    / (last.block_timestamp - oldest.block_timestamp) as i64)
+                    as i32);
+            }
+        };
+
+        if last.block_timestamp <= window_start {
+            // if last timepoint was created more than WINDOW seconds ago, use the average over its interval
+            let len = self.timepoints.len();
+            if len < 2 {
+                return Ok(tick);
+            }
+            let start = self.timepoints[len - 2];
+            return Ok(((last.tick_cumulative - start.tick_cumulative)
+                / (last.block_timestamp - start.block_timestamp) as i64)
+                as i32);
+        }
+
+        let current_tick_cumulative = last.tick_cumulative + tick as i64 * time_since_last as i64;
+        let tick_cumulative_at_start = self
+            .get_single_timepoint(current_time, WINDOW, tick)?
+            .tick_cumulative;
+
+        Ok(((current_tick_cumulative - tick_cumulative_at_start) / WINDOW as i64) as i32)
+    }
+
+    //Returns the accumulator values as of seconds_ago from the given time, interpolating between timepoints or
+    //extrapolating from the last timepoint with the current tick
+    pub fn get_single_timepoint(
+        &self,
+        time: u32,
+        seconds_ago: u32,
+        tick: i32,
+    ) -> Result<Timepoint, UniswapV3MathError> {
+        let target = time
+            .checked_sub(seconds_ago)
+            .ok_or(UniswapV3MathError::SecondsAgoExceedsTime(seconds_ago, time))?;
+        let last = *self.last()?;
+
+        if target >= last.block_timestamp {
+            if target == last.block_timestamp {
+                return Ok(last);
+            }
+            // we're at the right boundary, extrapolate from the last timepoint
+            let average_tick = self.get_average_tick(target, tick)?;
+            return Ok(create_new_timepoint(last, target, tick, average_tick));
+        }
+
+        if target < self.oldest()?.block_timestamp {
+            return Err(UniswapV3MathError::TargetIsTooOld(target));
+        }
+
+        // find the timepoints at or right before and right after the target
+        let after_index = self
+            .timepoints
+            .partition_point(|timepoint| timepoint.block_timestamp <= target);
+        let before_or_at = self.timepoints[after_index - 1];
+        if before_or_at.block_timestamp == target {
+            return Ok(before_or_at);
+        }
+        let at_or_after = self.timepoints[after_index];
+
+        // we're in the middle
+        let timepoint_time_delta =
+            (at_or_after.block_timestamp - before_or_at.block_timestamp) as i64;
+        let target_delta = (target - before_or_at.block_timestamp) as i64;
+
+        let tick_cumulative = before_or_at.tick_cumulative
+            + ((at_or_after.tick_cumulative - before_or_at.tick_cumulative) / timepoint_time_delta)
+                * target_delta;
+        let volatility_cumulative = before_or_at.volatility_cumulative
+            + ((at_or_after.volatility_cumulative - before_or_at.volatility_cumulative)
+                / timepoint_time_delta as u128)
+                * target_delta as u128;
+
+        Ok(Timepoint {
+            initialized: true,
+            block_timestamp: target,
+            tick_cumulative,
+            volatility_cumulative,
+            tick: before_or_at.tick,
+            average_tick: before_or_at.average_tick,
+        })
+    }
+
+    //Returns the average volatility over the last WINDOW seconds, or over the whole history if it is shorter
+    pub fn get_average_volatility(
+        &self,
+        current_time: u32,
+        tick: i32,
+    ) -> Result<u128, UniswapV3MathError> {
+        let oldest = *self.oldest()?;
+        let last_volatility_cumulative = self
+            .get_single_timepoint(current_time, 0, tick)?
+            .volatility_cumulative;
+
+        if current_time
+            .checked_sub(WINDOW)
+            .is_some_and(|window_start| oldest.block_timestamp <= window_start)
+        {
+            // oldest timepoint is earlier than WINDOW seconds ago, the sample is big enough to ignore bias of variance
+            let volatility_cumulative_at_start = self
+                .get_single_timepoint(current_time, WINDOW, tick)?
+                .volatility_cumulative;
+            Ok((last_volatility_cumulative - volatility_cumulative_at_start) / WINDOW as u128)
+        } else if current_time != oldest.block_timestamp {
+            // recalculate average volatility for the history we have
+            Ok((last_volatility_cumulative - oldest.volatility_cumulative)
+                / (current_time - oldest.block_timestamp) as u128)
+        } else {
+            Ok(0)
+        }
+    }
+
+    //Returns the adaptive fee for the current average volatility
+    pub fn get_fee(
+        &self,
+        current_time: u32,
+        tick: i32,
+        config: &AlgebraFeeConfiguration,
+    ) -> Result<u16, UniswapV3MathError> {
+        let volatility = self.get_average_volatility(current_time, tick)?;
+        adaptive_fee::get_fee(volatility, config)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        algebra::adaptive_fee::{initial_fee_configuration, BASE_FEE},
+        tick_math::{MAX_TICK, MIN_TICK},
+    };
+
+    #[test]
+    fn test_volatility_on_range() {
+        // a constant distance between tick and average tick
+        assert_eq!(volatility_on_range(10, 100, 100, 0, 0), 10 * 100 * 100);
+        // no deviation
+        assert_eq!(volatility_on_range(10, 5, 5, 5, 5), 0);
+        // matches the sum over every second
+        let (dt, tick_0, tick_1, avg_0, avg_1) = (13i64, 40, -20, 10, 4);
+        let expected: f64 = (1..=dt)
+            .map(|t| {
+                let tick = tick_0 as f64 + (tick_1 - tick_0) as f64 * t as f64 / dt as f64;
+                let avg = avg_0 as f64 + (avg_1 - avg_0) as f64 * t as f64 / dt as f64;
+                (tick - avg).powi(2)
+            })
+            .sum();
+        let result = volatility_on_range(dt, tick_0, tick_1, avg_0, avg_1) as f64;
+        assert!((result - expected).abs() <= 1.0);
+
+        // the longest interval between timepoints with the largest deviations, whose products exceed 128 bits
+        let (dt, deviation) = (1i64 << 32, (MAX_TICK - MIN_TICK) as u128);
+        assert_eq!(
+            volatility_on_range(dt, MAX_TICK, MAX_TICK, MIN_TICK, MIN_TICK),
+            dt as u128 * deviation * deviation
+        );
+        // the tick moving across the whole range while the average stays put: the sum of squares of deviation * t / dt
+        let dt_u128 = dt as u128;
+        assert_eq!(
+            volatility_on_range(dt, MIN_TICK, MAX_TICK, MIN_TICK, MIN_TICK),
+            deviation * deviation * (dt_u128 + 1) * (2 * dt_u128 + 1) / (6 * dt_u128)
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let mut oracle = VolatilityOracle::initialize(1000, 10);
+
+        // one timepoint per block
+        assert!(!oracle.write(1000, 20).unwrap());
+        assert!(oracle.write(1010, 10).unwrap());
+        assert!(oracle.write(1030, 30).unwrap());
+
+        let last = oracle.timepoints.back().unwrap();
+        assert_eq!(last.tick_cumulative, 10 * 10 + 30 * 20);
+        assert_eq!(last.tick, 30);
+        // average over the first interval
+        assert_eq!(last.average_tick, 10);
+
+        assert!(matches!(
+            VolatilityOracle::default().write(0, 0),
+            Err(UniswapV3MathError::OracleNotInitialized)
+        ));
+    }
+
+    #[test]
+    fn test_get_single_timepoint() {
+        let mut oracle = VolatilityOracle::initialize(1000, 0);
+        oracle.write(1010, 10).unwrap();
+        oracle.write(1030, 30).unwrap();
+
+        // interpolated
+        let timepoint = oracle.get_single_timepoint(1030, 10, 30).unwrap();
+        assert_eq!(timepoint.tick_cumulative, 100 + 30 * 10);
+        // extrapolated with the current tick
+        let timepoint = oracle.get_single_timepoint(1040, 0, 50).unwrap();
+        assert_eq!(timepoint.tick_cumulative, 100 + 30 * 20 + 50 * 10);
+
+        assert!(matches!(
+            oracle.get_single_timepoint(1040, 41, 50),
+            Err(UniswapV3MathError::TargetIsTooOld(999))
+        ));
+        assert!(matches!(
+            oracle.get_single_timepoint(40, 41, 50),
+            Err(UniswapV3MathError::SecondsAgoExceedsTime(41, 40))
+        ));
+    }
+
+    #[test]
+    fn test_get_average_tick_before_last_timepoint() {
+        let mut oracle = VolatilityOracle::initialize(0, 0);
+        oracle.write(WINDOW + 10, 10).unwrap();
+
+        assert!(matches!(
+            oracle.get_average_tick(WINDOW + 5, 10),
+            Err(UniswapV3MathError::TimeBeforeLastTimepoint(86405, 86410))
+        ));
+        // writes must not go back in time
+        assert!(matches!(
+            oracle.write(WINDOW + 5, 10),
+            Err(UniswapV3MathError::TimeBeforeLastTimepoint(86405, 86410))
+        ));
+        assert_eq!(oracle.timepoints.len(), 2);
+    }
+
+    #[test]
+    fn test_fee_rises_with_volatility() {
+        let config = initial_fee_configuration();
+
+        let mut oracle = VolatilityOracle::initialize(0, 0);
+        assert_eq!(oracle.get_fee(0, 0, &config).unwrap(), BASE_FEE);
+
+        // a calm price
+        for i in 1..=100 {
+            oracle.write(i * 60, 0).unwrap();
+        }
+        assert_eq!(oracle.get_fee(6000, 0, &config).unwrap(), BASE_FEE);
+
+        // a swinging price
+        for i in 101..=200 {
+            oracle
+                .write(i * 60, if i % 2 == 0 { 500 } else { -500 })
+                .unwrap();
+        }
+        let fee = oracle.get_fee(12000, 0, &config).unwrap();
+        assert!(fee > BASE_FEE);
+        assert!(fee <= 15000);
+
+        // once the average tick has settled, volatility is averaged over WINDOW
+        oracle.write(12000 + WINDOW, 0).unwrap();
+        oracle.write(12000 + 2 * WINDOW, 0).unwrap();
+        let calm_fee = oracle.get_fee(12000 + 3 * WINDOW, 0, &config).unwrap();
+        assert!(calm_fee < fee);
+        assert_eq!(calm_fee, BASE_FEE);
+    }
+}
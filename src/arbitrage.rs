@@ -0,0 +1,217 @@
+use alloy_primitives::{I256, U256, U512};
+
+use crate::{
+    error::UniswapV3MathError,
+    full_math::mul_div,
+    pool::{Pool, SwapResult},
+    price_impact::amounts_to_sqrt_price,
+    tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO},
+    U256_1,
+};
+
+const Q96: U256 = U256::from_limbs([0, 4294967296, 0, 0]);
+const FEE_DENOMINATOR: u64 = 1_000_000;
+
+//A round trip on two pools of the same pair: token1 buys token0 in the cheaper pool and the token0 is sold back for
+//token1 in the other pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arbitrage {
+    //Whether token0 is bought in the first pool and sold in the second, or the other way around
+    pub buy_in_a: bool,
+    //The token1 paid to the buy pool
+    pub amount_in: U256,
+    //The token0 bought in the buy pool and sold in the sell pool
+    pub amount_intermediate: U256,
+    //The token1 received from the sell pool
+    pub amount_out: U256,
+    //amount_out - amount_in, in token1
+    pub profit: U256,
+    pub buy: SwapResult,
+    pub sell: SwapResult,
+}
+
+//Whether buying token0 at sqrt price buy and selling it at sqrt price sell is profitable at the margin, after the fees
+//of both pools. The marginal cost of token0 is price_buy / (1 - fee_buy) and its marginal proceeds
+//price_sell * (1 - fee_sell), the comparison is done on the squared sqrt prices in 512 bits.
+fn marginally_profitable(
+    buy: &Pool,
+    sqrt_price_buy_x_96: U256,
+    sell: &Pool,
+    sqrt_price_sell_x_96: U256,
+) -> bool {
+    let price_buy = U512::from(sqrt_price_buy_x_96) * U512::from(sqrt_price_buy_x_96);
+    let price_sell = U512::from(sqrt_price_sell_x_96) * U512::from(sqrt_price_sell_x_96);
+
+    price_buy * U512::from(FEE_DENOMINATOR * FEE_DENOMINATOR)
+        < price_sell
+            * U512::from(FEE_DENOMINATOR - buy.config.fee as u64)
+            * U512::from(FEE_DENOMINATOR - sell.config.fee as u64)
+}
+
+//Simulates the round trip on copies of the pools
+// returns (uint256 amountIntermediate, uint256 amountOut, uint160 sqrtPriceBuyX96, uint160 sqrtPriceSellX96, SwapResult buy, SwapResult sell)
+fn simulate(
+    buy: &Pool,
+    sell: &Pool,
+    amount_in: U256,
+) -> Result<(U256, U256, U256, U256, SwapResult, SwapResult), UniswapV3MathError> {
+    let mut buy = buy.clone();
+    let buy_result = buy.swap(false, I256::from_raw(amount_in), MAX_SQRT_RATIO - U256_1)?;
+    let amount_intermediate = (-buy_result.amount_0).into_raw();
+
+    let mut sell = sell.clone();
+    let sell_result = if amount_intermediate.is_zero() {
+        SwapResult::default()
+    } else {
+        sell.swap(
+            true,
+            I256::from_raw(amount_intermediate),
+            MIN_SQRT_RATIO + U256_1,
+        )?
+    };
+    let amount_out = (-sell_result.amount_1).into_raw();
+
+    Ok((
+        amount_intermediate,
+        amount_out,
+        buy.slot0.sqrt_price_x_96,
+        sell.slot0.sqrt_price_x_96,
+        buy_result,
+        sell_result,
+    ))
+}
+
+//Returns the token1 input that equalizes the marginal prices of two pools of the same pair after fees, with the
+//round trip evaluated exactly on copies of the pools, or None if there is no profitable round trip.
+//The marginal price of the buy pool rises and that of the sell pool falls with the input, so the input is found by
+//binary search on the marginal condition, bounded by the input that moves the buy pool alone to the sell pool's price.
+pub fn optimal_arbitrage(
+    pool_a: &Pool,
+    pool_b: &Pool,
+) -> Result<Option<Arbitrage>, UniswapV3MathError> {
+    let (buy_in_a, buy, sell) = if pool_a.slot0.sqrt_price_x_96 <= pool_b.slot0.sqrt_price_x_96 {
+        (true, pool_a, pool_b)
+    } else {
+        (false, pool_b, pool_a)
+    };
+
+    if !marginally_profitable(
+        buy,
+        buy.slot0.sqrt_price_x_96,
+        sell,
+        sell.slot0.sqrt_price_x_96,
+    ) {
+        return Ok(None);
+    }
+
+    // the sell pool's price discounted by both fees is where the buy pool stops being profitable if the sell pool
+    // did not move
+    let ratio_x_192: U256 = (U256::from(
+        (FEE_DENOMINATOR - buy.config.fee as u64) * (FEE_DENOMINATOR - sell.config.fee as u64),
+    ) << 192)
+        / U256::from(FEE_DENOMINATOR * FEE_DENOMINATOR);
+    let sqrt_price_target_x_96 =
+        mul_div(sell.slot0.sqrt_price_x_96, ratio_x_192.root(2), Q96)?.min(MAX_SQRT_RATIO - U256_1);
+    let mut high = amounts_to_sqrt_price(buy, sqrt_price_target_x_96)?.amount_in;
+    let mut low = U256::ZERO;
+
+    // the largest input after which the round trip is still profitable at the margin
+    while low < high {
+        let mid = low + (high - low + U256_1) / U256::from(2);
+        let (_, _, sqrt_price_buy_x_96, sqrt_price_sell_x_96, _, _) = simulate(buy, sell, mid)?;
+        if marginally_profitable(buy, sqrt_price_buy_x_96, sell, sqrt_price_sell_x_96) {
+            low = mid;
+        } else {
+            high = mid - U256_1;
+        }
+    }
+
+    if low.is_zero() {
+        return Ok(None);
+    }
+
+    let (amount_intermediate, amount_out, _, _, buy_result, sell_result) =
+        simulate(buy, sell, low)?;
+    if amount_out <= low {
+        return Ok(None);
+    }
+
+    Ok(Some(Arbitrage {
+        buy_in_a,
+        amount_in: low,
+        amount_intermediate,
+        amount_out,
+        profit: amount_out - low,
+        buy: buy_result,
+        sell: sell_result,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        dialect::{Dialect, PoolConfig},
+        tick_math::get_sqrt_ratio_at_tick,
+    };
+    use alloy_primitives::Address;
+
+    fn new_pool(fee: u32, tick: i32) -> Pool {
+        let config = PoolConfig::new(Dialect::UniswapV3, fee).unwrap();
+        let spacing = config.tick_spacing;
+        let mut pool = Pool::initialize(config, get_sqrt_ratio_at_tick(tick).unwrap()).unwrap();
+        let tick = tick.div_euclid(spacing) * spacing;
+        pool.mint(Address::ZERO, tick - 600, tick + 600, 1e18 as u128)
+            .unwrap();
+        pool.mint(Address::ZERO, tick - 60, tick + 60, 5e18 as u128)
+            .unwrap();
+        pool
+    }
+
+    fn profit(buy: &Pool, sell: &Pool, amount_in: U256) -> I256 {
+        let (_, amount_out, _, _, _, _) = simulate(buy, sell, amount_in).unwrap();
+        I256::from_raw(amount_out) - I256::from_raw(amount_in)
+    }
+
+    #[test]
+    fn test_no_arbitrage_within_fees() {
+        // a 0.5% price gap is less than the 0.6% of fees of two 0.3% pools
+        let pool_a = new_pool(3000, 0);
+        let pool_b = new_pool(3000, 50);
+        assert_eq!(optimal_arbitrage(&pool_a, &pool_b).unwrap(), None);
+        assert_eq!(optimal_arbitrage(&pool_a, &pool_a).unwrap(), None);
+    }
+
+    #[test]
+    fn test_optimal_arbitrage() {
+        for (pool_a, pool_b) in [
+            // the gap closes inside the inner ranges
+            (new_pool(500, 0), new_pool(3000, 100)),
+            // the gap closes after crossing ticks in both pools
+            (new_pool(3000, 1000), new_pool(500, 0)),
+        ] {
+            let arbitrage = optimal_arbitrage(&pool_a, &pool_b).unwrap().unwrap();
+            let (buy, sell) = if arbitrage.buy_in_a {
+                (&pool_a, &pool_b)
+            } else {
+                (&pool_b, &pool_a)
+            };
+            assert_eq!(arbitrage.buy_in_a, pool_a.slot0.tick < pool_b.slot0.tick);
+            assert_eq!(arbitrage.buy.amount_1, I256::from_raw(arbitrage.amount_in));
+            assert_eq!(
+                arbitrage.sell.amount_0,
+                I256::from_raw(arbitrage.amount_intermediate)
+            );
+            assert_eq!(arbitrage.profit, arbitrage.amount_out - arbitrage.amount_in);
+
+            // the profit is maximal, up to rounding, around the solution
+            let best = I256::from_raw(arbitrage.profit);
+            assert_eq!(profit(buy, sell, arbitrage.amount_in), best);
+            for shift in [1u64, 1000, 1_000_000_000_000] {
+                let shift = U256::from(shift);
+                assert!(profit(buy, sell, arbitrage.amount_in + shift) <= best + I256::ONE);
+                assert!(profit(buy, sell, arbitrage.amount_in - shift) <= best + I256::ONE);
+            }
+        }
+    }
+}
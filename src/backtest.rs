@@ -0,0 +1,620 @@
+use std::{collections::HashMap, path::Path, str::FromStr};
+
+use alloy_primitives::{Address, I256, U256};
+
+use crate::{
+    error::UniswapV3MathError,
+    full_math::mul_div,
+    pool::Pool,
+    scenario::{apply_action, Action},
+};
+
+const Q96: U256 = U256::from_limbs([0, 4294967296, 0, 0]);
+
+//A historical pool event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolEvent {
+    //A Swap log: the pool's token deltas, positive when paid to the pool, and the sqrt price after the swap
+    Swap {
+        timestamp: u64,
+        amount_0: I256,
+        amount_1: I256,
+        sqrt_price_x_96: U256,
+    },
+    Mint {
+        timestamp: u64,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    },
+    Burn {
+        timestamp: u64,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    },
+}
+
+impl PoolEvent {
+    pub fn timestamp(&self) -> u64 {
+        match *self {
+            PoolEvent::Swap { timestamp, .. }
+            | PoolEvent::Mint { timestamp, .. }
+            | PoolEvent::Burn { timestamp, .. } => timestamp,
+        }
+    }
+
+    //Builds an event from named fields: event (swap, mint or burn) and timestamp, then amount0, amount1 and
+    //sqrtPriceX96 for swaps or owner, tickLower, tickUpper and amount for mints and burns
+    fn from_record(line: usize, record: &HashMap<&str, &str>) -> Result<Self, UniswapV3MathError> {
+        fn field<T: FromStr>(
+            line: usize,
+            record: &HashMap<&str, &str>,
+            name: &str,
+        ) -> Result<T, UniswapV3MathError> {
+            let value = record
+                .get(name)
+                .ok_or_else(|| UniswapV3MathError::InvalidEvent(line, format!("missing {name}")))?;
+            value
+                .parse()
+                .map_err(|_| UniswapV3MathError::InvalidEvent(line, format!("invalid {name}")))
+        }
+
+        let timestamp = field(line, record, "timestamp")?;
+        match record.get("event").copied() {
+            Some("swap") => Ok(PoolEvent::Swap {
+                timestamp,
+                amount_0: field(line, record, "amount0")?,
+                amount_1: field(line, record, "amount1")?,
+                sqrt_price_x_96: field(line, record, "sqrtPriceX96")?,
+            }),
+            Some(event @ ("mint" | "burn")) => {
+                let owner = field(line, record, "owner")?;
+                let tick_lower = field(line, record, "tickLower")?;
+                let tick_upper = field(line, record, "tickUpper")?;
+                let amount = field(line, record, "amount")?;
+                Ok(if event == "mint" {
+                    PoolEvent::Mint {
+                        timestamp,
+                        owner,
+                        tick_lower,
+                        tick_upper,
+                        amount,
+                    }
+                } else {
+                    PoolEvent::Burn {
+                        timestamp,
+                        owner,
+                        tick_lower,
+                        tick_upper,
+                        amount,
+                    }
+                })
+            }
+            _ => Err(UniswapV3MathError::InvalidEvent(
+                line,
+                "unknown event".to_string(),
+            )),
+        }
+    }
+}
+
+//Parses events from CSV with a header row naming the fields, see PoolEvent::from_record
+pub fn parse_events_csv(input: &str) -> Result<Vec<PoolEvent>, UniswapV3MathError> {
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let Some((_, header)) = lines.next() else {
+        return Ok(vec![]);
+    };
+    let names = header.split(',').map(str::trim).collect::<Vec<_>>();
+
+    lines
+        .map(|(index, line)| {
+            let record = names
+                .iter()
+                .copied()
+                .zip(line.split(',').map(str::trim))
+                .filter(|(_, value)| !value.is_empty())
+                .collect();
+            PoolEvent::from_record(index + 1, &record)
+        })
+        .collect()
+}
+
+//Parses events from JSON lines of flat objects, with numbers either bare or quoted
+pub fn parse_events_jsonl(input: &str) -> Result<Vec<PoolEvent>, UniswapV3MathError> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let line_number = index + 1;
+            let body = line
+                .trim()
+                .strip_prefix('{')
+                .and_then(|line| line.strip_suffix('}'))
+                .ok_or_else(|| {
+                    UniswapV3MathError::InvalidEvent(line_number, "not an object".to_string())
+                })?;
+
+            let record = body
+                .split(',')
+                .map(|entry| {
+                    let (key, value) = entry.split_once(':').ok_or_else(|| {
+                        UniswapV3MathError::InvalidEvent(
+                            line_number,
+                            format!("invalid entry {entry}"),
+                        )
+                    })?;
+                    Ok((key.trim().trim_matches('"'), value.trim().trim_matches('"')))
+                })
+                .collect::<Result<_, UniswapV3MathError>>()?;
+            PoolEvent::from_record(line_number, &record)
+        })
+        .collect()
+}
+
+//Reads events from a .csv or .jsonl file
+pub fn load_events(path: &Path) -> Result<Vec<PoolEvent>, UniswapV3MathError> {
+    let input = std::fs::read_to_string(path)
+        .map_err(|err| UniswapV3MathError::EventReadError(err.to_string()))?;
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("csv") => parse_events_csv(&input),
+        Some("jsonl") => parse_events_jsonl(&input),
+        _ => Err(UniswapV3MathError::EventReadError(format!(
+            "unsupported file {}",
+            path.display()
+        ))),
+    }
+}
+
+//A liquidity change decided by a strategy, applied to the strategy's own positions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrategyAction {
+    Mint {
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    },
+    //Burns liquidity and collects everything owed to the position
+    Burn {
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    },
+}
+
+pub trait Strategy {
+    //Called before each event is replayed, with the positions of the strategy readable from the pool under owner
+    fn on_event(&mut self, pool: &Pool, owner: Address, event: &PoolEvent) -> Vec<StrategyAction>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BacktestReport {
+    pub events_replayed: usize,
+    //Historical events that could not be applied to the simulated pool, e.g. burns of positions minted before the
+    //replay started. A skipped event leaves the pool unchanged.
+    pub events_skipped: usize,
+    //Gas count proxies, the number of transactions the strategy sent
+    pub mints: usize,
+    pub burns: usize,
+    //Fees collected by the strategy, including the fees of its open positions at the end
+    pub fees_earned_0: U256,
+    pub fees_earned_1: U256,
+    //The strategy's token deltas if its open positions were closed at the end, positive when received
+    pub amount_0: I256,
+    pub amount_1: I256,
+    //The tokens held in open positions at the end, including uncollected fees
+    pub open_amount_0: U256,
+    pub open_amount_1: U256,
+    pub final_sqrt_price_x_96: U256,
+    //amount_0 and amount_1 valued in token1 at the final price, the profit against holding the deposited tokens
+    pub pnl: I256,
+    //The part of pnl made of fees, and the rest which is the impermanent loss of the strategy
+    pub fee_value: U256,
+    pub impermanent_loss: I256,
+}
+
+//Returns the value in token1 of signed amounts of token0 and token1 at a sqrt price
+fn signed_value_in_token_1(
+    sqrt_price_x_96: U256,
+    amount_0: I256,
+    amount_1: I256,
+) -> Result<I256, UniswapV3MathError> {
+    let value_0 = I256::from_raw(mul_div(
+        mul_div(amount_0.unsigned_abs(), sqrt_price_x_96, Q96)?,
+        sqrt_price_x_96,
+        Q96,
+    )?);
+    Ok(if amount_0.is_negative() {
+        amount_1 - value_0
+    } else {
+        amount_1 + value_0
+    })
+}
+
+//Replays events on the pool, letting the strategy act before each of them. Swaps are replayed as unbounded exact
+//input swaps limited to the logged price, so that liquidity added by the strategy changes how much is swapped but
+//not where the price ends.
+pub fn run_backtest<S: Strategy>(
+    pool: &mut Pool,
+    strategy: &mut S,
+    owner: Address,
+    events: &[PoolEvent],
+) -> Result<BacktestReport, UniswapV3MathError> {
+    let mut report = BacktestReport::default();
+
+    for event in events {
+        for action in strategy.on_event(pool, owner, event) {
+            let action = match action {
+                StrategyAction::Mint {
+                    tick_lower,
+                    tick_upper,
+                    amount,
+                } => {
+                    report.mints += 1;
+                    Action::Mint {
+                        owner,
+                        tick_lower,
+                        tick_upper,
+                        amount,
+                    }
+                }
+                StrategyAction::Burn {
+                    tick_lower,
+                    tick_upper,
+                    amount,
+                } => {
+                    report.burns += 1;
+                    Action::Burn {
+                        owner,
+                        tick_lower,
+                        tick_upper,
+                        amount,
+                    }
+                }
+            };
+            let deltas = apply_action(pool, &action)?;
+            report.amount_0 += deltas.amount_0;
+            report.amount_1 += deltas.amount_1;
+            report.fees_earned_0 += deltas.fees_earned_0;
+            report.fees_earned_1 += deltas.fees_earned_1;
+        }
+
+        let result = match *event {
+            PoolEvent::Swap {
+                sqrt_price_x_96, ..
+            } => {
+                // the logged amounts only moved the price this far with the historical liquidity
+                if sqrt_price_x_96 == pool.slot0.sqrt_price_x_96 {
+                    Ok(())
+                } else {
+                    let zero_for_one = sqrt_price_x_96 < pool.slot0.sqrt_price_x_96;
+                    pool.swap(zero_for_one, I256::MAX, sqrt_price_x_96)
+                        .map(|_| ())
+                }
+            }
+            PoolEvent::Mint {
+                owner,
+                tick_lower,
+                tick_upper,
+                amount,
+                ..
+            } => pool.mint(owner, tick_lower, tick_upper, amount).map(|_| ()),
+            PoolEvent::Burn {
+                owner,
+                tick_lower,
+                tick_upper,
+                amount,
+                ..
+            } => pool.burn(owner, tick_lower, tick_upper, amount).map(|_| ()),
+        };
+
+        if result.is_ok() {
+            report.events_replayed += 1;
+        } else {
+            report.events_skipped += 1;
+        }
+    }
+
+    // close the open positions on a copy of the pool to value them
+    let mut closed = pool.clone();
+    let open_positions = pool
+        .positions
+        .iter()
+        .filter(|(key, position)| {
+            key.owner == owner
+                && (position.liquidity > 0
+                    || position.tokens_owed_0 > 0
+                    || position.tokens_owed_1 > 0)
+        })
+        .map(|(key, position)| (key.tick_lower, key.tick_upper, position.liquidity))
+        .collect::<Vec<_>>();
+    for (tick_lower, tick_upper, liquidity) in open_positions {
+        let deltas = apply_action(
+            &mut closed,
+            &Action::Burn {
+                owner,
+                tick_lower,
+                tick_upper,
+                amount: liquidity,
+            },
+        )?;
+        report.open_amount_0 += deltas.amount_0.into_raw();
+        report.open_amount_1 += deltas.amount_1.into_raw();
+        report.amount_0 += deltas.amount_0;
+        report.amount_1 += deltas.amount_1;
+        report.fees_earned_0 += deltas.fees_earned_0;
+        report.fees_earned_1 += deltas.fees_earned_1;
+    }
+
+    report.final_sqrt_price_x_96 = pool.slot0.sqrt_price_x_96;
+    report.pnl = signed_value_in_token_1(
+        report.final_sqrt_price_x_96,
+        report.amount_0,
+        report.amount_1,
+    )?;
+    report.fee_value = signed_value_in_token_1(
+        report.final_sqrt_price_x_96,
+        I256::from_raw(report.fees_earned_0),
+        I256::from_raw(report.fees_earned_1),
+    )?
+    .into_raw();
+    report.impermanent_loss = report.pnl - I256::from_raw(report.fee_value);
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test_utils::{self, SQRT_PRICE_1_1},
+        tick_math::get_sqrt_ratio_at_tick,
+    };
+
+    const LP: Address = Address::repeat_byte(1);
+    const STRATEGY: Address = Address::repeat_byte(2);
+
+    fn new_pool() -> Pool {
+        test_utils::new_pool(LP, &[(-6000, 6000, 1e18 as u128)])
+    }
+
+    // swaps that walk the price up to about tick 500 and back, as logged by a pool
+    fn history() -> Vec<PoolEvent> {
+        let mut pool = new_pool();
+        let mut events = vec![];
+        for (timestamp, tick) in [(1, 100), (2, 300), (3, 500), (4, 200), (5, -100), (6, 50)] {
+            let zero_for_one = tick < pool.slot0.tick;
+            let sqrt_price_x_96 = get_sqrt_ratio_at_tick(tick).unwrap();
+            let result = pool.swap(zero_for_one, I256::MAX, sqrt_price_x_96).unwrap();
+            events.push(PoolEvent::Swap {
+                timestamp,
+                amount_0: result.amount_0,
+                amount_1: result.amount_1,
+                sqrt_price_x_96,
+            });
+        }
+        events
+    }
+
+    fn to_csv(events: &[PoolEvent]) -> String {
+        let mut csv = "event,timestamp,amount0,amount1,sqrtPriceX96\n".to_string();
+        for event in events {
+            if let PoolEvent::Swap {
+                timestamp,
+                amount_0,
+                amount_1,
+                sqrt_price_x_96,
+            } = event
+            {
+                csv += &format!("swap,{timestamp},{amount_0},{amount_1},{sqrt_price_x_96}\n");
+            }
+        }
+        csv
+    }
+
+    // keeps a position of 200 ticks around the price, moving it whenever the price leaves it
+    struct Recenter {
+        range: Option<(i32, i32)>,
+    }
+
+    impl Strategy for Recenter {
+        fn on_event(&mut self, pool: &Pool, _: Address, _: &PoolEvent) -> Vec<StrategyAction> {
+            let tick = pool.slot0.tick;
+            let mut actions = vec![];
+            if let Some((tick_lower, tick_upper)) = self.range {
+                if (tick_lower..tick_upper).contains(&tick) {
+                    return actions;
+                }
+                actions.push(StrategyAction::Burn {
+                    tick_lower,
+                    tick_upper,
+                    amount: 1e17 as u128,
+                });
+            }
+
+            let tick_lower = tick.div_euclid(60) * 60 - 120;
+            let tick_upper = tick_lower + 240;
+            self.range = Some((tick_lower, tick_upper));
+            actions.push(StrategyAction::Mint {
+                tick_lower,
+                tick_upper,
+                amount: 1e17 as u128,
+            });
+            actions
+        }
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let events = history();
+        assert_eq!(parse_events_csv(&to_csv(&events)).unwrap(), events);
+
+        let jsonl = r#"
+            {"event": "mint", "timestamp": 1, "owner": "0x0101010101010101010101010101010101010101", "tickLower": -60, "tickUpper": 60, "amount": "1000"}
+            {"event":"swap","timestamp":2,"amount0":"-5","amount1":"6","sqrtPriceX96":"79228162514264337593543950336"}
+        "#;
+        assert_eq!(
+            parse_events_jsonl(jsonl).unwrap(),
+            vec![
+                PoolEvent::Mint {
+                    timestamp: 1,
+                    owner: LP,
+                    tick_lower: -60,
+                    tick_upper: 60,
+                    amount: 1000,
+                },
+                PoolEvent::Swap {
+                    timestamp: 2,
+                    amount_0: I256::try_from(-5).unwrap(),
+                    amount_1: I256::try_from(6).unwrap(),
+                    sqrt_price_x_96: SQRT_PRICE_1_1,
+                },
+            ]
+        );
+
+        let result = parse_events_jsonl(r#"{"event":"swap","timestamp":2}"#);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Invalid event on line 1: missing amount0"
+        );
+        let result = parse_events_csv("event,timestamp\nflash,1");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Invalid event on line 2: unknown event"
+        );
+    }
+
+    #[test]
+    fn test_load_events() {
+        let events = history();
+        let path = std::env::temp_dir().join(format!("backtest-{}.csv", std::process::id()));
+        std::fs::write(&path, to_csv(&events)).unwrap();
+        assert_eq!(load_events(&path).unwrap(), events);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(load_events(&path).is_err());
+    }
+
+    struct Hold;
+
+    impl Strategy for Hold {
+        fn on_event(&mut self, _: &Pool, _: Address, _: &PoolEvent) -> Vec<StrategyAction> {
+            vec![]
+        }
+    }
+
+    #[test]
+    fn test_run_backtest_skipped_burn() {
+        let mint = PoolEvent::Mint {
+            timestamp: 1,
+            owner: LP,
+            tick_lower: -600,
+            tick_upper: 600,
+            amount: 1e18 as u128,
+        };
+        // a position on the same ticks minted before the replay started
+        let burn = PoolEvent::Burn {
+            timestamp: 2,
+            owner: STRATEGY,
+            tick_lower: -600,
+            tick_upper: 600,
+            amount: 4e17 as u128,
+        };
+
+        let mut expected = new_pool();
+        run_backtest(&mut expected, &mut Hold, STRATEGY, &[mint]).unwrap();
+        let mut pool = new_pool();
+        let report = run_backtest(&mut pool, &mut Hold, STRATEGY, &[mint, burn]).unwrap();
+
+        assert_eq!(report.events_replayed, 1);
+        assert_eq!(report.events_skipped, 1);
+        assert_eq!(pool.ticks, expected.ticks);
+        assert_eq!(pool.tick_bitmap, expected.tick_bitmap);
+        assert_eq!(pool.positions, expected.positions);
+        assert_eq!(pool.liquidity, expected.liquidity);
+    }
+
+    // mints a wide position before the first event, recording the price before every event
+    struct WideRange {
+        prices: Vec<U256>,
+    }
+
+    impl Strategy for WideRange {
+        fn on_event(&mut self, pool: &Pool, _: Address, _: &PoolEvent) -> Vec<StrategyAction> {
+            self.prices.push(pool.slot0.sqrt_price_x_96);
+            if self.prices.len() > 1 {
+                return vec![];
+            }
+            vec![StrategyAction::Mint {
+                tick_lower: -6000,
+                tick_upper: 6000,
+                amount: 1e18 as u128,
+            }]
+        }
+    }
+
+    #[test]
+    fn test_run_backtest_follows_logged_prices() {
+        let events = history();
+        let mut pool = new_pool();
+        let mut strategy = WideRange { prices: vec![] };
+        let report = run_backtest(&mut pool, &mut strategy, STRATEGY, &events).unwrap();
+        assert_eq!(report.events_replayed, events.len());
+
+        // the strategy doubled the liquidity in range, yet every swap ends at the logged price
+        let replayed_prices = strategy.prices[1..]
+            .iter()
+            .chain([&report.final_sqrt_price_x_96]);
+        for (event, price) in events.iter().zip(replayed_prices) {
+            let PoolEvent::Swap {
+                sqrt_price_x_96, ..
+            } = event
+            else {
+                unreachable!()
+            };
+            assert_eq!(price, sqrt_price_x_96);
+        }
+    }
+
+    #[test]
+    fn test_run_backtest() {
+        let events = history();
+        let mut pool = new_pool();
+        let mut strategy = Recenter { range: None };
+        let report = run_backtest(&mut pool, &mut strategy, STRATEGY, &events).unwrap();
+
+        // the price ends where history ended
+        assert_eq!(report.events_replayed, events.len());
+        assert_eq!(report.events_skipped, 0);
+        assert_eq!(
+            report.final_sqrt_price_x_96,
+            get_sqrt_ratio_at_tick(50).unwrap()
+        );
+
+        // the strategy followed the price out of its range four times
+        assert_eq!(report.mints, 5);
+        assert_eq!(report.burns, 4);
+        assert!(!report.fees_earned_0.is_zero() && !report.fees_earned_1.is_zero());
+
+        // the report closed the last position without touching the pool
+        assert!(!report.open_amount_0.is_zero() || !report.open_amount_1.is_zero());
+        assert!(pool
+            .positions
+            .iter()
+            .any(|(key, position)| key.owner == STRATEGY && position.liquidity > 0));
+
+        // pnl splits into fees and impermanent loss, which a recentering strategy suffers from
+        assert_eq!(
+            report.pnl,
+            I256::from_raw(report.fee_value) + report.impermanent_loss
+        );
+        assert!(report.impermanent_loss.is_negative());
+    }
+}
@@ -0,0 +1,296 @@
+use alloy_primitives::U256;
+
+use crate::{
+    error::UniswapV3MathError,
+    liquidity_math,
+    pool::Pool,
+    swap_math::{compute_swap_step_with_kind, SwapKind, SwapStep},
+    tick_bitmap::next_initialized_tick_within_one_word,
+    tick_math::{get_sqrt_ratio_at_tick, MAX_SQRT_RATIO, MAX_TICK, MIN_SQRT_RATIO, MIN_TICK},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchQuote {
+    //The input used, less than the amount quoted when the swap stops at the price limit
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub sqrt_price_x_96: U256,
+}
+
+//A step of the swap loop from the start of a range of constant liquidity to its end or the price limit
+struct Segment {
+    sqrt_price_x_96: U256,
+    sqrt_price_target_x_96: U256,
+    sqrt_price_next_x_96: U256,
+    tick_next: i32,
+    initialized: bool,
+    liquidity: u128,
+}
+
+//Walks the ranges of a swap in one direction, keeping the amounts swapped through the ranges already passed
+struct Walk<'a> {
+    pool: &'a Pool,
+    zero_for_one: bool,
+    sqrt_price_limit_x_96: U256,
+    sqrt_price_x_96: U256,
+    tick: i32,
+    liquidity: u128,
+    amount_in: U256,
+    amount_out: U256,
+}
+
+impl Walk<'_> {
+    fn segment(&self) -> Result<Segment, UniswapV3MathError> {
+        let (tick_next, initialized) = next_initialized_tick_within_one_word(
+            &self.pool.tick_bitmap,
+            self.tick,
+            self.pool.config.tick_spacing,
+            self.zero_for_one,
+        )?;
+        let tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+        let sqrt_price_next_x_96 = get_sqrt_ratio_at_tick(tick_next)?;
+        let sqrt_price_target_x_96 = if self.zero_for_one {
+            sqrt_price_next_x_96.max(self.sqrt_price_limit_x_96)
+        } else {
+            sqrt_price_next_x_96.min(self.sqrt_price_limit_x_96)
+        };
+
+        Ok(Segment {
+            sqrt_price_x_96: self.sqrt_price_x_96,
+            sqrt_price_target_x_96,
+            sqrt_price_next_x_96,
+            tick_next,
+            initialized,
+            liquidity: self.liquidity,
+        })
+    }
+
+    // moves past a segment the swap went through entirely, as the swap loop does when it reaches the step target
+    fn advance(&mut self, segment: &Segment, step: &SwapStep) -> Result<(), UniswapV3MathError> {
+        self.amount_in += step.amount_in + step.fee_amount;
+        self.amount_out += step.amount_out;
+        self.sqrt_price_x_96 = segment.sqrt_price_target_x_96;
+
+        if segment.sqrt_price_target_x_96 == segment.sqrt_price_next_x_96 {
+            if segment.initialized {
+                let mut liquidity_net = self
+                    .pool
+                    .ticks
+                    .get(&segment.tick_next)
+                    .map(|tick| tick.liquidity_net)
+                    .unwrap_or_default();
+                if self.zero_for_one {
+                    liquidity_net = -liquidity_net;
+                }
+                self.liquidity = liquidity_math::add_delta(self.liquidity, liquidity_net)?;
+            }
+
+            self.tick = if self.zero_for_one {
+                segment.tick_next - 1
+            } else {
+                segment.tick_next
+            };
+        }
+
+        Ok(())
+    }
+}
+
+//Quotes exact input swaps of each of the amounts, sorted in ascending order, in a single walk of the pool's ticks. Each
+//amount continues the walk from the range the previous one ended in, so ranges are only crossed once and each amount
+//costs a single partial step, with results identical to swapping each amount on its own.
+pub fn quote_exact_input_batch(
+    pool: &Pool,
+    zero_for_one: bool,
+    amounts_in: &[U256],
+    sqrt_price_limit_x_96: U256,
+) -> Result<Vec<BatchQuote>, UniswapV3MathError> {
+    if amounts_in.windows(2).any(|pair| pair[0] > pair[1]) {
+        return Err(UniswapV3MathError::AmountsNotSorted);
+    }
+
+    let sqrt_price_x_96 = pool.slot0.sqrt_price_x_96;
+    if zero_for_one {
+        if sqrt_price_limit_x_96 >= sqrt_price_x_96 {
+            return Err(UniswapV3MathError::PriceLimitAlreadyExceeded(
+                sqrt_price_x_96,
+                sqrt_price_limit_x_96,
+            ));
+        }
+        if sqrt_price_limit_x_96 <= MIN_SQRT_RATIO {
+            return Err(UniswapV3MathError::PriceLimitOutOfBounds(
+                sqrt_price_limit_x_96,
+            ));
+        }
+    } else {
+        if sqrt_price_limit_x_96 <= sqrt_price_x_96 {
+            return Err(UniswapV3MathError::PriceLimitAlreadyExceeded(
+                sqrt_price_x_96,
+                sqrt_price_limit_x_96,
+            ));
+        }
+        if sqrt_price_limit_x_96 >= MAX_SQRT_RATIO {
+            return Err(UniswapV3MathError::PriceLimitOutOfBounds(
+                sqrt_price_limit_x_96,
+            ));
+        }
+    }
+
+    let mut walk = Walk {
+        pool,
+        zero_for_one,
+        sqrt_price_limit_x_96,
+        sqrt_price_x_96,
+        tick: pool.slot0.tick,
+        liquidity: pool.liquidity,
+        amount_in: U256::ZERO,
+        amount_out: U256::ZERO,
+    };
+    let mut segment = walk.segment()?;
+    let mut quotes = Vec::with_capacity(amounts_in.len());
+
+    for &amount in amounts_in {
+        let quote = loop {
+            let amount_remaining = amount - walk.amount_in;
+            if amount_remaining.is_zero() || walk.sqrt_price_x_96 == sqrt_price_limit_x_96 {
+                break BatchQuote {
+                    amount_in: walk.amount_in,
+                    amount_out: walk.amount_out,
+                    sqrt_price_x_96: walk.sqrt_price_x_96,
+                };
+            }
+
+            let step = compute_swap_step_with_kind(
+                segment.sqrt_price_x_96,
+                segment.sqrt_price_target_x_96,
+                segment.liquidity,
+                amount_remaining,
+                SwapKind::ExactIn,
+                pool.config.fee,
+            )?;
+
+            // the step stops inside the segment, later amounts start again from the segment
+            if step.sqrt_ratio_next_x_96 != segment.sqrt_price_target_x_96 {
+                break BatchQuote {
+                    amount_in: amount,
+                    amount_out: walk.amount_out + step.amount_out,
+                    sqrt_price_x_96: step.sqrt_ratio_next_x_96,
+                };
+            }
+
+            walk.advance(&segment, &step)?;
+            if walk.sqrt_price_x_96 != sqrt_price_limit_x_96 {
+                segment = walk.segment()?;
+            }
+        };
+        quotes.push(quote);
+    }
+
+    Ok(quotes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_utils, tick_math::get_sqrt_ratio_at_tick, U256_1};
+    use alloy_primitives::{Address, I256};
+    #[cfg(not(target_arch = "wasm32"))]
+    use proptest::prelude::*;
+
+    fn new_pool() -> Pool {
+        test_utils::new_pool(
+            Address::ZERO,
+            &[
+                (-600, 600, 1e18 as u128),
+                (-120, 60, 5e18 as u128),
+                (-6000, 6000, 1e17 as u128),
+            ],
+        )
+    }
+
+    fn swap(pool: &Pool, zero_for_one: bool, amount: U256, limit: U256) -> BatchQuote {
+        let mut pool = pool.clone();
+        let result = pool
+            .swap(zero_for_one, I256::from_raw(amount), limit)
+            .unwrap();
+        let (amount_in, amount_out) = if zero_for_one {
+            (result.amount_0, result.amount_1)
+        } else {
+            (result.amount_1, result.amount_0)
+        };
+        BatchQuote {
+            amount_in: amount_in.into_raw(),
+            amount_out: (-amount_out).into_raw(),
+            sqrt_price_x_96: pool.slot0.sqrt_price_x_96,
+        }
+    }
+
+    fn limit(zero_for_one: bool) -> U256 {
+        if zero_for_one {
+            MIN_SQRT_RATIO + U256_1
+        } else {
+            MAX_SQRT_RATIO - U256_1
+        }
+    }
+
+    #[test]
+    fn test_quote_exact_input_batch() {
+        let pool = new_pool();
+        for zero_for_one in [true, false] {
+            // amounts ending inside ranges, on their boundaries and past all the liquidity
+            let mut amounts = (1..=200)
+                .map(|i| U256::from(i as u64 * 1e15 as u64))
+                .collect::<Vec<_>>();
+            amounts.insert(0, U256::from(1));
+            amounts.push(amounts[200]);
+            amounts.push(U256::from(1e20 as u128));
+
+            let limit = limit(zero_for_one);
+            let quotes = quote_exact_input_batch(&pool, zero_for_one, &amounts, limit).unwrap();
+            for (amount, quote) in amounts.iter().zip(&quotes) {
+                assert_eq!(*quote, swap(&pool, zero_for_one, *amount, limit));
+            }
+        }
+    }
+
+    #[test]
+    fn test_quote_exact_input_batch_price_limit() {
+        let pool = new_pool();
+        let limit = get_sqrt_ratio_at_tick(-300).unwrap();
+        let amounts = [U256::from(1e16 as u64), U256::from(1e18 as u64)];
+        let quotes = quote_exact_input_batch(&pool, true, &amounts, limit).unwrap();
+
+        // the larger amount stops at the limit without using all of its input
+        assert_eq!(quotes[0], swap(&pool, true, amounts[0], limit));
+        assert_eq!(quotes[1], swap(&pool, true, amounts[1], limit));
+        assert_eq!(quotes[1].sqrt_price_x_96, limit);
+        assert!(quotes[1].amount_in < amounts[1]);
+
+        let result = quote_exact_input_batch(&pool, true, &[amounts[1], amounts[0]], limit);
+        assert!(matches!(result, Err(UniswapV3MathError::AmountsNotSorted)));
+        let result = quote_exact_input_batch(&pool, false, &amounts, limit);
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::PriceLimitAlreadyExceeded(_, _))
+        ));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    proptest! {
+        #[test]
+        fn test_quote_exact_input_batch_matches_swap(
+            zero_for_one: bool,
+            mut amounts in prop::collection::vec(1u128..1e19 as u128, 1..20),
+        ) {
+            let pool = new_pool();
+            amounts.sort();
+            let amounts = amounts.into_iter().map(U256::from).collect::<Vec<_>>();
+
+            let limit = limit(zero_for_one);
+            let quotes = quote_exact_input_batch(&pool, zero_for_one, &amounts, limit).unwrap();
+            for (amount, quote) in amounts.iter().zip(&quotes) {
+                prop_assert_eq!(*quote, swap(&pool, zero_for_one, *amount, limit));
+            }
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use alloy_primitives::U256;
+
+use crate::{error::UniswapV3MathError, full_math::mul_div_rounding_up};
+
+//PancakeSwap v3 packs the protocol fee of each token into 16 bits of a uint32, denominated in basis points of the
+//swap fee
+pub const PANCAKE_PROTOCOL_FEE_SP: u32 = 65536;
+pub const PANCAKE_PROTOCOL_FEE_DENOMINATOR: u32 = 10000;
+
+//Slipstream charges a fee on the swap fees of unstaked liquidity, in pips
+pub const SLIPSTREAM_DEFAULT_UNSTAKED_FEE: u32 = 100_000;
+pub const SLIPSTREAM_MAX_UNSTAKED_FEE: u32 = 500_000;
+
+//A Uniswap v3 fork that shares the core math but differs in its fee tiers and fee accounting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    //The protocol takes 1/n of the swap fee, with n packed in 4 bits per token of a uint8 feeProtocol
+    #[default]
+    UniswapV3,
+    //Same pool contract and fee accounting as UniswapV3
+    SushiSwapV3,
+    //The protocol takes n/10000 of the swap fee, with n packed in 16 bits per token of a uint32 feeProtocol
+    PancakeSwapV3,
+    //Aerodrome/Velodrome Slipstream. There is no protocol fee, instead the fees of gauge-staked liquidity and a
+    //cut of the fees of unstaked liquidity are paid to the gauge.
+    Slipstream,
+}
+
+impl Dialect {
+    //The (fee, tick spacing) pairs enabled by the factory at deployment
+    pub fn fee_tiers(self) -> &'static [(u32, i32)] {
+        match self {
+            Dialect::UniswapV3 | Dialect::SushiSwapV3 => {
+                &[(100, 1), (500, 10), (3000, 60), (10000, 200)]
+            }
+            Dialect::PancakeSwapV3 => &[(100, 1), (500, 10), (2500, 50), (10000, 200)],
+            Dialect::Slipstream => &[(100, 1), (500, 50), (500, 100), (3000, 200), (10000, 2000)],
+        }
+    }
+
+    //Returns the tick spacing of a fee tier. Slipstream pools are keyed by tick spacing, so the first tier with a
+    //matching fee is returned.
+    pub fn tick_spacing(self, fee: u32) -> Option<i32> {
+        self.fee_tiers()
+            .iter()
+            .find(|(tier_fee, _)| *tier_fee == fee)
+            .map(|(_, tick_spacing)| *tick_spacing)
+    }
+
+    //Returns the default fee for a tick spacing
+    pub fn fee(self, tick_spacing: i32) -> Option<u32> {
+        self.fee_tiers()
+            .iter()
+            .find(|(_, tier_tick_spacing)| *tier_tick_spacing == tick_spacing)
+            .map(|(fee, _)| *fee)
+    }
+
+    //Returns the protocol fee setting of the input token from the packed slot0 feeProtocol
+    pub fn fee_protocol(self, fee_protocol: u32, zero_for_one: bool) -> u32 {
+        match self {
+            Dialect::UniswapV3 | Dialect::SushiSwapV3 => {
+                if zero_for_one {
+                    fee_protocol % 16
+                } else {
+                    (fee_protocol >> 4) % 16
+                }
+            }
+            Dialect::PancakeSwapV3 => {
+                if zero_for_one {
+                    fee_protocol % PANCAKE_PROTOCOL_FEE_SP
+                } else {
+                    fee_protocol >> 16
+                }
+            }
+            Dialect::Slipstream => 0,
+        }
+    }
+
+    //Validates a packed feeProtocol as setFeeProtocol would
+    pub fn validate_fee_protocol(self, fee_protocol: u32) -> Result<(), UniswapV3MathError> {
+        let valid = match self {
+            Dialect::UniswapV3 | Dialect::SushiSwapV3 => {
+                let valid_n = |n: u32| n == 0 || (4..=10).contains(&n);
+                fee_protocol <= u8::MAX as u32
+                    && valid_n(self.fee_protocol(fee_protocol, true))
+                    && valid_n(self.fee_protocol(fee_protocol, false))
+            }
+            Dialect::PancakeSwapV3 => {
+                let valid_n = |n: u32| n == 0 || (1000..=4000).contains(&n);
+                valid_n(self.fee_protocol(fee_protocol, true))
+                    && valid_n(self.fee_protocol(fee_protocol, false))
+            }
+            Dialect::Slipstream => fee_protocol == 0,
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(UniswapV3MathError::InvalidFeeProtocol(fee_protocol))
+        }
+    }
+
+    //Returns the part of a step's fee amount owed to the protocol, given the fee protocol of the input token
+    pub fn protocol_fee_amount(self, fee_amount: U256, fee_protocol: u32) -> U256 {
+        if fee_protocol == 0 {
+            return U256::ZERO;
+        }
+
+        match self {
+            Dialect::UniswapV3 | Dialect::SushiSwapV3 => fee_amount / U256::from(fee_protocol),
+            Dialect::PancakeSwapV3 => {
+                fee_amount * U256::from(fee_protocol) / U256::from(PANCAKE_PROTOCOL_FEE_DENOMINATOR)
+            }
+            Dialect::Slipstream => U256::ZERO,
+        }
+    }
+}
+
+//The parameters of a single pool of a dialect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PoolConfig {
+    pub dialect: Dialect,
+    pub fee: u32,
+    pub tick_spacing: i32,
+    //The fee taken by the gauge on the swap fees of unstaked liquidity in pips, only used by Slipstream
+    pub unstaked_fee: u32,
+}
+
+impl PoolConfig {
+    //Returns the config of a factory fee tier, or None if the dialect has no such tier
+    pub fn new(dialect: Dialect, fee: u32) -> Option<PoolConfig> {
+        Some(PoolConfig {
+            dialect,
+            fee,
+            tick_spacing: dialect.tick_spacing(fee)?,
+            unstaked_fee: if dialect == Dialect::Slipstream {
+                SLIPSTREAM_DEFAULT_UNSTAKED_FEE
+            } else {
+                0
+            },
+        })
+    }
+
+    //Returns the config of a Slipstream pool with the default fee for its tick spacing
+    pub fn slipstream(tick_spacing: i32) -> Option<PoolConfig> {
+        PoolConfig::new(Dialect::Slipstream, Dialect::Slipstream.fee(tick_spacing)?).map(|config| {
+            PoolConfig {
+                tick_spacing,
+                ..config
+            }
+        })
+    }
+
+    //Splits a Slipstream step fee between unstaked liquidity providers and the gauge. Staked liquidity earns no
+    //fees in the pool, its share and the unstaked fee on the rest are paid to the gauge.
+    // returns (uint256 unstakedFeeAmount, uint256 gaugeFeeAmount)
+    pub fn split_gauge_fee(
+        &self,
+        fee_amount: U256,
+        liquidity: u128,
+        staked_liquidity: u128,
+    ) -> Result<(U256, U256), UniswapV3MathError> {
+        if self.dialect != Dialect::Slipstream || liquidity == 0 {
+            return Ok((fee_amount, U256::ZERO));
+        }
+
+        let staked_fee_amount = mul_div_rounding_up(
+            fee_amount,
+            U256::from(staked_liquidity),
+            U256::from(liquidity),
+        )?;
+        let unstaked_fee_amount = fee_amount
+            .checked_sub(staked_fee_amount)
+            .ok_or(UniswapV3MathError::StakedLiquidityExceedsLiquidity)?;
+        let unstaked_gauge_fee = mul_div_rounding_up(
+            unstaked_fee_amount,
+            U256::from(self.unstaked_fee),
+            U256::from(1_000_000),
+        )?;
+
+        Ok((
+            unstaked_fee_amount - unstaked_gauge_fee,
+            staked_fee_amount + unstaked_gauge_fee,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fee_tiers() {
+        assert_eq!(Dialect::UniswapV3.tick_spacing(3000), Some(60));
+        assert_eq!(Dialect::SushiSwapV3.tick_spacing(3000), Some(60));
+        assert_eq!(Dialect::PancakeSwapV3.tick_spacing(2500), Some(50));
+        assert_eq!(Dialect::PancakeSwapV3.tick_spacing(3000), None);
+        assert_eq!(Dialect::Slipstream.fee(100), Some(500));
+
+        let config = PoolConfig::slipstream(200).unwrap();
+        assert_eq!(config.fee, 3000);
+        assert_eq!(config.unstaked_fee, SLIPSTREAM_DEFAULT_UNSTAKED_FEE);
+        assert!(PoolConfig::slipstream(60).is_none());
+    }
+
+    #[test]
+    fn test_fee_protocol() {
+        // 1/4 of token0 fees and 1/10 of token1 fees
+        let fee_protocol = 4 | (10 << 4);
+        assert_eq!(Dialect::UniswapV3.fee_protocol(fee_protocol, true), 4);
+        assert_eq!(Dialect::UniswapV3.fee_protocol(fee_protocol, false), 10);
+        assert!(Dialect::UniswapV3
+            .validate_fee_protocol(fee_protocol)
+            .is_ok());
+        assert!(Dialect::UniswapV3.validate_fee_protocol(3).is_err());
+        assert_eq!(
+            Dialect::UniswapV3.protocol_fee_amount(U256::from(1000), 4),
+            U256::from(250)
+        );
+
+        // 33% of token0 fees and 25% of token1 fees
+        let fee_protocol = 3300 | (2500 << 16);
+        assert_eq!(
+            Dialect::PancakeSwapV3.fee_protocol(fee_protocol, true),
+            3300
+        );
+        assert_eq!(
+            Dialect::PancakeSwapV3.fee_protocol(fee_protocol, false),
+            2500
+        );
+        assert!(Dialect::PancakeSwapV3
+            .validate_fee_protocol(fee_protocol)
+            .is_ok());
+        assert!(Dialect::PancakeSwapV3.validate_fee_protocol(4001).is_err());
+        assert_eq!(
+            Dialect::PancakeSwapV3.protocol_fee_amount(U256::from(1000), 3300),
+            U256::from(330)
+        );
+
+        assert!(Dialect::Slipstream.validate_fee_protocol(1).is_err());
+    }
+
+    #[test]
+    fn test_split_gauge_fee() {
+        let config = PoolConfig::slipstream(100).unwrap();
+
+        // nothing staked, the gauge only takes the unstaked fee
+        let (unstaked, gauge) = config.split_gauge_fee(U256::from(1000), 100, 0).unwrap();
+        assert_eq!((unstaked, gauge), (U256::from(900), U256::from(100)));
+
+        // a quarter staked
+        let (unstaked, gauge) = config.split_gauge_fee(U256::from(1000), 100, 25).unwrap();
+        assert_eq!((unstaked, gauge), (U256::from(675), U256::from(325)));
+
+        // more staked than in range liquidity
+        assert!(matches!(
+            config.split_gauge_fee(U256::from(1000), 100, 101),
+            Err(UniswapV3MathError::StakedLiquidityExceedsLiquidity)
+        ));
+
+        // other dialects pay all fees to liquidity providers
+        let config = PoolConfig::new(Dialect::UniswapV3, 3000).unwrap();
+        let (unstaked, gauge) = config.split_gauge_fee(U256::from(1000), 100, 25).unwrap();
+        assert_eq!((unstaked, gauge), (U256::from(1000), U256::ZERO));
+    }
+}
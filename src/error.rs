@@ -1,4 +1,4 @@
-use alloy_primitives::ruint::ParseError;
+use alloy_primitives::{ruint::ParseError, U256};
 use thiserror::Error;
 
 // TODO: make these errors better, some errors in univ3 libs are just require(condition) without a message.
@@ -33,8 +33,86 @@ pub enum UniswapV3MathError {
         "Second inequality must be < because the price can never reach the price at the max tick"
     )]
     R,
+    #[error("Liquidity exceeds the maximum liquidity per tick")]
+    LO,
     #[error("Overflow when casting to U160")]
     SafeCastToU160Overflow,
+    #[error("Overflow when casting to U128")]
+    SafeCastToU128Overflow,
+    #[error("Overflow when casting to I128")]
+    SafeCastToI128Overflow,
+    #[error("Overflow when casting to I256")]
+    SafeCastToI256Overflow,
+    #[error("Addition overflow")]
+    LowGasSafeMathAddOverflow,
+    #[error("Subtraction underflow")]
+    LowGasSafeMathSubUnderflow,
+    #[error("Multiplication overflow")]
+    LowGasSafeMathMulOverflow,
+    #[error("LP fee {0} is larger than 100%")]
+    LPFeeTooLarge(u32),
+    #[error("Protocol fee {0} is larger than the maximum")]
+    ProtocolFeeTooLarge(u32),
+    #[error("Tick lower {0} is not less than tick upper {1}")]
+    TicksMisordered(i32, i32),
+    #[error("Tick lower {0} is less than the minimum tick")]
+    TickLowerOutOfBounds(i32),
+    #[error("Tick upper {0} is greater than the maximum tick")]
+    TickUpperOutOfBounds(i32),
+    #[error("Price limit {1} is already exceeded by the current price {0}")]
+    PriceLimitAlreadyExceeded(U256, U256),
+    #[error("Price limit {0} is out of bounds")]
+    PriceLimitOutOfBounds(U256),
+    #[error("Exact output swaps are not possible with a 100% swap fee")]
+    InvalidFeeForExactOut,
+    #[error("Cannot update an empty position")]
+    CannotUpdateEmptyPosition,
+    #[error("Pool is not initialized")]
+    PoolNotInitialized,
+    #[error("Pool is already initialized")]
+    PoolAlreadyInitialized,
+    #[error("No liquidity to receive fees")]
+    NoLiquidityToReceiveFees,
+    #[error("Hook delta exceeds the swap amount")]
+    HookDeltaExceedsSwapAmount,
+    #[error("Swap amount cannot be zero")]
+    SwapAmountCannotBeZero,
+    #[error("Tick spacing {0} is out of bounds")]
+    TickSpacingOutOfBounds(i32),
+    #[error("Only dynamic fee pools can update their LP fee")]
+    UnauthorizedDynamicLPFeeUpdate,
+    #[error("Invalid fee protocol {0}")]
+    InvalidFeeProtocol(u32),
+    #[error("Liquidity staking is only supported by Slipstream pools")]
+    StakingNotSupported,
+    #[error("Staked liquidity exceeds the liquidity")]
+    StakedLiquidityExceedsLiquidity,
+    #[error("Max fee exceeded")]
+    MaxFeeExceeded,
+    #[error("Gammas must be > 0")]
+    GammasMustBeNonZero,
+    #[error("Oracle is not initialized")]
+    OracleNotInitialized,
+    #[error("Target {0} is older than the oldest timepoint")]
+    TargetIsTooOld(u32),
+    #[error("Seconds ago {0} is greater than the time {1}")]
+    SecondsAgoExceedsTime(u32, u32),
+    #[error("Time {0} is earlier than the last timepoint at {1}")]
+    TimeBeforeLastTimepoint(u32, u32),
+    #[error("Price slippage check")]
+    PriceSlippageCheck,
+    #[error("Invalid token ID {0}")]
+    InvalidTokenId(u64),
+    #[error("Not cleared")]
+    PositionNotCleared,
+    #[error("Collect amounts are zero")]
+    CollectAmountsAreZero,
+    #[error("Could not read events: {0}")]
+    EventReadError(String),
+    #[error("Invalid event on line {0}: {1}")]
+    InvalidEvent(usize, String),
+    #[error("Amounts are not sorted")]
+    AmountsNotSorted,
     #[error("Tick spacing error")]
     TickSpacingError,
     #[error("Middleware error when getting next_initialized_tick_within_one_word")]
@@ -0,0 +1,386 @@
+use alloy_primitives::U256;
+
+use crate::{
+    error::UniswapV3MathError,
+    tick_math::{
+        get_sqrt_ratio_at_tick, get_tick_at_sqrt_ratio, MAX_SQRT_RATIO, MAX_TICK, MIN_SQRT_RATIO,
+        MIN_TICK,
+    },
+};
+
+//Floating point versions of the tick, amount and swap step math for estimates that do not need exact integers, such as
+//pruning routes. Sqrt prices are plain numbers, sqrt(token1 / token0) rather than Q64.96, and amounts and liquidity
+//are in token units.
+//
+//Error bounds against the exact U256 path, for inputs converted from the exact values:
+//  - get_sqrt_ratio_at_tick_f64 is within a relative SQRT_RATIO_RELATIVE_ERROR of getSqrtRatioAtTick, plus 2^-96 from
+//    the rounding of the Q64.96 result
+//  - the amount deltas are within amount_0_error_bound and amount_1_error_bound, relative to the whole amount of the
+//    range below the upper price for token1 and above the lower price for token0, as the subtraction of close prices
+//    cancels the leading digits
+//  - swap step amounts are within the same bounds over the swept range, also counting the 2^-96 rounding of the
+//    exact next price, the paid amount, fee included, scaled by the fee
+//
+//Only the tick has a refinement into the exact value, as getTickAtSqrtRatio is the one exact function searching for
+//its result. The exact amount deltas, next prices and swap steps are a fixed handful of mul_div calls that an
+//approximation cannot shorten, so they are recomputed from the same inputs with sqrt_price_math and swap_math.
+
+const Q96: f64 = 79228162514264337593543950336.0;
+const PIPS_DENOMINATOR: f64 = 1e6;
+
+//The relative error of the f64 sqrt ratio, from the error of tick * ln(1.0001) / 2 carried through exp
+pub const SQRT_RATIO_RELATIVE_ERROR: f64 = 1e-14;
+//The relative error of a handful of f64 operations, with a wide margin
+pub const RELATIVE_ERROR: f64 = 1e-13;
+
+pub fn sqrt_price_to_f64(sqrt_price_x_96: U256) -> f64 {
+    f64::from(sqrt_price_x_96) / Q96
+}
+
+//Converts a sqrt price to Q64.96, rounded down
+pub fn sqrt_price_from_f64(sqrt_price: f64) -> Result<U256, UniswapV3MathError> {
+    let sqrt_price_x_96 =
+        U256::try_from(sqrt_price * Q96).map_err(|_| UniswapV3MathError::SqrtPriceIsZero)?;
+    if sqrt_price_x_96.is_zero() {
+        return Err(UniswapV3MathError::SqrtPriceIsZero);
+    }
+    Ok(sqrt_price_x_96)
+}
+
+pub fn get_sqrt_ratio_at_tick_f64(tick: i32) -> f64 {
+    (tick as f64 * 0.0001f64.ln_1p() / 2.0).exp()
+}
+
+//Returns the tick of a sqrt price, which may be one off the exact tick when the price is within the error bound of a
+//tick's price, see refine_tick_at_sqrt_ratio
+pub fn get_tick_at_sqrt_ratio_f64(sqrt_price: f64) -> i32 {
+    let tick = (2.0 * sqrt_price.ln() / 0.0001f64.ln_1p()).floor();
+    tick.clamp(MIN_TICK as f64, (MAX_TICK - 1) as f64) as i32
+}
+
+//Returns the exact tick of a sqrt price starting from an approximate tick, at most three evaluations of
+//getSqrtRatioAtTick instead of the full logarithm when the approximate tick is within one of the exact tick, as
+//get_tick_at_sqrt_ratio_f64 is. Approximate ticks further off fall back to getTickAtSqrtRatio.
+pub fn refine_tick_at_sqrt_ratio(
+    sqrt_price_x_96: U256,
+    approximate_tick: i32,
+) -> Result<i32, UniswapV3MathError> {
+    if !(sqrt_price_x_96 >= MIN_SQRT_RATIO && sqrt_price_x_96 < MAX_SQRT_RATIO) {
+        return Err(UniswapV3MathError::R);
+    }
+
+    // the greatest tick whose price is at most the sqrt price. The price of MIN_TICK is at most any valid sqrt price
+    // and the price of MAX_TICK above it, so the ticks checked stay within bounds.
+    let tick = approximate_tick.clamp(MIN_TICK, MAX_TICK - 1);
+    if get_sqrt_ratio_at_tick(tick)? > sqrt_price_x_96 {
+        if get_sqrt_ratio_at_tick(tick - 1)? <= sqrt_price_x_96 {
+            return Ok(tick - 1);
+        }
+    } else if get_sqrt_ratio_at_tick(tick + 1)? > sqrt_price_x_96 {
+        return Ok(tick);
+    } else if get_sqrt_ratio_at_tick(tick + 2)? > sqrt_price_x_96 {
+        return Ok(tick + 1);
+    }
+
+    get_tick_at_sqrt_ratio(sqrt_price_x_96)
+}
+
+fn sort(sqrt_price_a: f64, sqrt_price_b: f64) -> (f64, f64) {
+    if sqrt_price_a > sqrt_price_b {
+        (sqrt_price_b, sqrt_price_a)
+    } else {
+        (sqrt_price_a, sqrt_price_b)
+    }
+}
+
+//Calculates liquidity / sqrt(lower) - liquidity / sqrt(upper)
+pub fn get_amount_0_delta_f64(sqrt_price_a: f64, sqrt_price_b: f64, liquidity: f64) -> f64 {
+    let (sqrt_price_a, sqrt_price_b) = sort(sqrt_price_a, sqrt_price_b);
+    liquidity * (sqrt_price_b - sqrt_price_a) / sqrt_price_b / sqrt_price_a
+}
+
+//Calculates liquidity * (sqrt(upper) - sqrt(lower))
+pub fn get_amount_1_delta_f64(sqrt_price_a: f64, sqrt_price_b: f64, liquidity: f64) -> f64 {
+    let (sqrt_price_a, sqrt_price_b) = sort(sqrt_price_a, sqrt_price_b);
+    liquidity * (sqrt_price_b - sqrt_price_a)
+}
+
+//Returns the largest difference between get_amount_0_delta_f64 and the exact getAmount0Delta, rounded either way
+pub fn amount_0_error_bound(sqrt_price_a: f64, sqrt_price_b: f64, liquidity: f64) -> f64 {
+    let (sqrt_price_a, _) = sort(sqrt_price_a, sqrt_price_b);
+    RELATIVE_ERROR * liquidity / sqrt_price_a + 1.0
+}
+
+//Returns the largest difference between get_amount_1_delta_f64 and the exact getAmount1Delta, rounded either way
+pub fn amount_1_error_bound(sqrt_price_a: f64, sqrt_price_b: f64, liquidity: f64) -> f64 {
+    let (_, sqrt_price_b) = sort(sqrt_price_a, sqrt_price_b);
+    RELATIVE_ERROR * liquidity * sqrt_price_b + 1.0
+}
+
+pub fn get_next_sqrt_price_from_input_f64(
+    sqrt_price: f64,
+    liquidity: f64,
+    amount_in: f64,
+    zero_for_one: bool,
+) -> f64 {
+    if zero_for_one {
+        liquidity * sqrt_price / (liquidity + amount_in * sqrt_price)
+    } else {
+        sqrt_price + amount_in / liquidity
+    }
+}
+
+//Returns NaN when the output exceeds the token0 reserves of the liquidity, where the exact path reverts
+pub fn get_next_sqrt_price_from_output_f64(
+    sqrt_price: f64,
+    liquidity: f64,
+    amount_out: f64,
+    zero_for_one: bool,
+) -> f64 {
+    if zero_for_one {
+        sqrt_price - amount_out / liquidity
+    } else {
+        let denominator = liquidity - amount_out * sqrt_price;
+        if denominator <= 0.0 {
+            return f64::NAN;
+        }
+        liquidity * sqrt_price / denominator
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SwapStepF64 {
+    pub sqrt_ratio_next: f64,
+    pub amount_in: f64,
+    pub amount_out: f64,
+    pub fee_amount: f64,
+    //The largest differences from the exact step of amount_in + fee_amount and of amount_out
+    pub amount_paid_error_bound: f64,
+    pub amount_out_error_bound: f64,
+}
+
+//computeSwapStep in f64, with a positive amount remaining as exact input and a negative one as exact output
+pub fn compute_swap_step_f64(
+    sqrt_ratio_current: f64,
+    sqrt_ratio_target: f64,
+    liquidity: f64,
+    amount_remaining: f64,
+    fee_pips: u32,
+) -> SwapStepF64 {
+    let zero_for_one = sqrt_ratio_current >= sqrt_ratio_target;
+    let exact_in = amount_remaining >= 0.0;
+    let fee = fee_pips as f64;
+
+    let amount_in_delta = |a: f64, b: f64| {
+        if zero_for_one {
+            get_amount_0_delta_f64(a, b, liquidity)
+        } else {
+            get_amount_1_delta_f64(a, b, liquidity)
+        }
+    };
+    let amount_out_delta = |a: f64, b: f64| {
+        if zero_for_one {
+            get_amount_1_delta_f64(a, b, liquidity)
+        } else {
+            get_amount_0_delta_f64(a, b, liquidity)
+        }
+    };
+
+    let sqrt_ratio_next = if exact_in {
+        let amount_remaining_less_fee =
+            amount_remaining * (PIPS_DENOMINATOR - fee) / PIPS_DENOMINATOR;
+        if amount_remaining_less_fee >= amount_in_delta(sqrt_ratio_target, sqrt_ratio_current) {
+            sqrt_ratio_target
+        } else {
+            get_next_sqrt_price_from_input_f64(
+                sqrt_ratio_current,
+                liquidity,
+                amount_remaining_less_fee,
+                zero_for_one,
+            )
+        }
+    } else if -amount_remaining >= amount_out_delta(sqrt_ratio_target, sqrt_ratio_current) {
+        sqrt_ratio_target
+    } else {
+        get_next_sqrt_price_from_output_f64(
+            sqrt_ratio_current,
+            liquidity,
+            -amount_remaining,
+            zero_for_one,
+        )
+    };
+
+    let amount_in = amount_in_delta(sqrt_ratio_next, sqrt_ratio_current);
+    let mut amount_out = amount_out_delta(sqrt_ratio_next, sqrt_ratio_current);
+    if !exact_in {
+        amount_out = amount_out.min(-amount_remaining);
+    }
+    let fee_amount = if exact_in && sqrt_ratio_next != sqrt_ratio_target {
+        amount_remaining - amount_in
+    } else {
+        amount_in * fee / (PIPS_DENOMINATOR - fee)
+    };
+
+    // the exact next price is rounded to a Q64.96 unit, which moves the amounts by liquidity / 2^96 in token1 and
+    // liquidity / 2^96 / sqrt(lower)^2 in token0
+    let (lower, upper) = sort(sqrt_ratio_current, sqrt_ratio_target);
+    let bound_0 = amount_0_error_bound(lower, upper, liquidity) + liquidity / Q96 / lower / lower;
+    let bound_1 = amount_1_error_bound(lower, upper, liquidity) + liquidity / Q96;
+    let (bound_in, bound_out) = if zero_for_one {
+        (bound_0, bound_1)
+    } else {
+        (bound_1, bound_0)
+    };
+
+    SwapStepF64 {
+        sqrt_ratio_next,
+        amount_in,
+        amount_out,
+        fee_amount,
+        amount_paid_error_bound: bound_in * PIPS_DENOMINATOR / (PIPS_DENOMINATOR - fee) + 1.0,
+        amount_out_error_bound: bound_out,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(not(target_arch = "wasm32"))]
+    use crate::{
+        sqrt_price_math::{_get_amount_0_delta, _get_amount_1_delta},
+        swap_math::compute_swap_step,
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    use alloy_primitives::I256;
+    #[cfg(not(target_arch = "wasm32"))]
+    use proptest::prelude::*;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn exact_f64(value: U256) -> f64 {
+        f64::from(value)
+    }
+
+    #[test]
+    fn test_sqrt_ratio_at_tick_f64() {
+        for tick in [MIN_TICK, -500_000, -1, 0, 1, 60, 500_000, MAX_TICK] {
+            let exact = sqrt_price_to_f64(get_sqrt_ratio_at_tick(tick).unwrap());
+            let approximate = get_sqrt_ratio_at_tick_f64(tick);
+            assert!(
+                (approximate - exact).abs() <= SQRT_RATIO_RELATIVE_ERROR * exact + 1.0 / Q96,
+                "tick {tick}: {approximate} != {exact}"
+            );
+        }
+
+        assert_eq!(
+            sqrt_price_from_f64(1.0).unwrap(),
+            U256::from_limbs([0, 4294967296, 0, 0])
+        );
+        assert!(sqrt_price_from_f64(0.0).is_err());
+        assert!(sqrt_price_from_f64(-1.0).is_err());
+    }
+
+    #[test]
+    fn test_refine_tick_at_sqrt_ratio() {
+        // prices exactly on a tick and one unit below it are where the float tick can be off by one
+        for tick in [MIN_TICK, -100_000, -1, 0, 1, 100_000, MAX_TICK - 1] {
+            let sqrt_price_x_96 = get_sqrt_ratio_at_tick(tick).unwrap();
+            for sqrt_price_x_96 in [sqrt_price_x_96, sqrt_price_x_96 - U256::from(1)] {
+                if sqrt_price_x_96 < MIN_SQRT_RATIO {
+                    continue;
+                }
+                let exact = get_tick_at_sqrt_ratio(sqrt_price_x_96).unwrap();
+                let approximate = get_tick_at_sqrt_ratio_f64(sqrt_price_to_f64(sqrt_price_x_96));
+                assert!((approximate - exact).abs() <= 1);
+                // starts further off than the float error fall back to the full logarithm
+                for start in [approximate, approximate - 5, approximate + 5, -tick, 0] {
+                    assert_eq!(
+                        refine_tick_at_sqrt_ratio(sqrt_price_x_96, start).unwrap(),
+                        exact
+                    );
+                }
+            }
+        }
+
+        assert_eq!(
+            refine_tick_at_sqrt_ratio(MIN_SQRT_RATIO, MAX_TICK).unwrap(),
+            MIN_TICK
+        );
+        assert_eq!(
+            refine_tick_at_sqrt_ratio(MAX_SQRT_RATIO - U256::from(1), MIN_TICK).unwrap(),
+            MAX_TICK - 1
+        );
+        assert!(refine_tick_at_sqrt_ratio(MAX_SQRT_RATIO, MAX_TICK).is_err());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    proptest! {
+        #[test]
+        fn test_amount_deltas_f64(
+            tick_a in MIN_TICK..MAX_TICK,
+            tick_b in MIN_TICK..MAX_TICK,
+            liquidity in 1u128..u128::MAX,
+        ) {
+            let sqrt_price_a_x_96 = get_sqrt_ratio_at_tick(tick_a).unwrap();
+            let sqrt_price_b_x_96 = get_sqrt_ratio_at_tick(tick_b).unwrap();
+            let (a, b, l) = (
+                sqrt_price_to_f64(sqrt_price_a_x_96),
+                sqrt_price_to_f64(sqrt_price_b_x_96),
+                liquidity as f64,
+            );
+
+            for round_up in [false, true] {
+                let exact = exact_f64(
+                    _get_amount_0_delta(sqrt_price_a_x_96, sqrt_price_b_x_96, liquidity, round_up).unwrap()
+                );
+                let error = (get_amount_0_delta_f64(a, b, l) - exact).abs();
+                prop_assert!(error <= amount_0_error_bound(a, b, l), "{error}");
+
+                let exact = exact_f64(
+                    _get_amount_1_delta(sqrt_price_a_x_96, sqrt_price_b_x_96, liquidity, round_up).unwrap()
+                );
+                let error = (get_amount_1_delta_f64(a, b, l) - exact).abs();
+                prop_assert!(error <= amount_1_error_bound(a, b, l), "{error}");
+            }
+        }
+
+        #[test]
+        fn test_compute_swap_step_f64(
+            tick_current in -400_000i32..400_000,
+            ticks in -20_000i32..20_000,
+            liquidity in 1e6 as u128..1e30 as u128,
+            amount_remaining in -1e30 as i128..1e30 as i128,
+            fee_pips in prop::sample::select(vec![0u32, 100, 500, 3000, 10000, 100000]),
+        ) {
+            prop_assume!(ticks != 0 && amount_remaining != 0);
+            let sqrt_ratio_current_x_96 = get_sqrt_ratio_at_tick(tick_current).unwrap();
+            let sqrt_ratio_target_x_96 = get_sqrt_ratio_at_tick(tick_current + ticks).unwrap();
+
+            // exact output swaps draining token0 reserves revert on both paths
+            let exact = compute_swap_step(
+                sqrt_ratio_current_x_96,
+                sqrt_ratio_target_x_96,
+                liquidity,
+                I256::try_from(amount_remaining).unwrap(),
+                fee_pips,
+            );
+            let Ok((sqrt_ratio_next_x_96, amount_in, amount_out, fee_amount)) = exact else {
+                return Ok(());
+            };
+
+            let step = compute_swap_step_f64(
+                sqrt_price_to_f64(sqrt_ratio_current_x_96),
+                sqrt_price_to_f64(sqrt_ratio_target_x_96),
+                liquidity as f64,
+                amount_remaining as f64,
+                fee_pips,
+            );
+            let paid_error = (step.amount_in + step.fee_amount - exact_f64(amount_in + fee_amount)).abs();
+            prop_assert!(paid_error <= step.amount_paid_error_bound, "{paid_error} > {}", step.amount_paid_error_bound);
+            let out_error = (step.amount_out - exact_f64(amount_out)).abs();
+            prop_assert!(out_error <= step.amount_out_error_bound, "{out_error} > {}", step.amount_out_error_bound);
+
+            let next = sqrt_price_to_f64(sqrt_ratio_next_x_96);
+            prop_assert!((step.sqrt_ratio_next - next).abs() <= RELATIVE_ERROR * next + 2.0 / Q96);
+        }
+    }
+}
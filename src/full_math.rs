@@ -151,9 +151,13 @@ mod tests {
 mod test {
     use crate::U256_1;
     use alloy_primitives::U256;
+    #[cfg(not(target_arch = "wasm32"))]
+    use proptest::prelude::*;
     use std::ops::{Div, Mul, Sub};
 
     use super::mul_div;
+    #[cfg(not(target_arch = "wasm32"))]
+    use super::mul_div_rounding_up;
 
     const Q128: U256 = U256::from_limbs([0, 0, 1, 0]);
 
@@ -207,4 +211,31 @@ mod test {
         let result = mul_div(Q128, U256::from(1000).mul(Q128), U256::from(3000).mul(Q128));
         assert_eq!(result.unwrap(), Q128.div(U256::from(3)));
     }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    proptest! {
+        #[test]
+        fn prop_mul_div_rounding_up_is_at_most_one_above_mul_div(
+            a in any::<[u64; 4]>(),
+            b in any::<[u64; 4]>(),
+            denominator in any::<[u64; 4]>(),
+        ) {
+            let (a, b, denominator) = (
+                U256::from_limbs(a),
+                U256::from_limbs(b),
+                U256::from_limbs(denominator),
+            );
+
+            match (mul_div(a, b, denominator), mul_div_rounding_up(a, b, denominator)) {
+                (Ok(down), Ok(up)) => {
+                    prop_assert!(up >= down);
+                    prop_assert!(up - down <= U256_1);
+                    prop_assert_eq!(up == down, a.mul_mod(b, denominator).is_zero());
+                }
+                // rounding up can only additionally fail when the rounded down result is U256::MAX
+                (Ok(down), Err(_)) => prop_assert_eq!(down, U256::MAX),
+                (Err(_), up) => prop_assert!(up.is_err()),
+            }
+        }
+    }
 }
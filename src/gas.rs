@@ -0,0 +1,243 @@
+use std::collections::HashSet;
+
+use crate::swap_trace::SwapTrace;
+
+//The storage work of a simulated swap that drives its gas cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapGasUsage {
+    //Iterations of the swap loop, each running computeSwapStep
+    pub steps: u64,
+    //Tick bitmap words loaded for the first time in the swap, cold under EIP-2929, and loaded again
+    pub cold_words_loaded: u64,
+    pub warm_words_loaded: u64,
+    //Initialized ticks crossed, each flipping the fee growth outside of the tick in storage
+    pub initialized_ticks_crossed: u64,
+}
+
+impl SwapGasUsage {
+    pub fn from_trace(trace: &SwapTrace) -> SwapGasUsage {
+        let mut words = HashSet::new();
+        let mut usage = SwapGasUsage::default();
+
+        for step in &trace.steps {
+            usage.steps += 1;
+            if words.insert(step.word_position) {
+                usage.cold_words_loaded += 1;
+            } else {
+                usage.warm_words_loaded += 1;
+            }
+            if step.tick_crossed.is_some() {
+                usage.initialized_ticks_crossed += 1;
+            }
+        }
+
+        usage
+    }
+}
+
+//EIP-2929 storage costs: the first load of a slot in a transaction, a load once it is warm, and a write of a nonzero
+//slot to another nonzero value after it was loaded (SSTORE_RESET_GAS less the cold load already paid)
+const COLD_SLOAD_COST: u64 = 2_100;
+const WARM_STORAGE_READ_COST: u64 = 100;
+const SSTORE_RESET_COST: u64 = 2_900;
+
+//Tick.cross loads the four slots of a Tick.Info and writes the three holding its outside accumulators
+const TICK_SLOTS_LOADED: u64 = 4;
+const TICK_SLOTS_WRITTEN: u64 = 3;
+
+//A linear gas model of UniswapV3Pool.swap as measured by QuoterV2's gasEstimate. The storage constants follow the
+//EIP-2929 schedule, the rest is compared against the v3-core pool bytecode by differential/tests/gas.rs. Chains with
+//different opcode pricing or a swap overhead of their own should calibrate the constants against observed estimates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasModel {
+    //The cost of a swap that runs no step: the call, the slot0 and liquidity reads and writes and the callback
+    pub base: u64,
+    pub per_step: u64,
+    pub per_cold_word: u64,
+    pub per_warm_word: u64,
+    //The storage of a crossed tick, per slot loaded and per slot written
+    pub per_tick_slot_loaded: u64,
+    pub per_tick_slot_written: u64,
+    //The rest of a crossing: the oracle observation computed on the first crossing and the liquidity update
+    pub per_initialized_tick_crossed: u64,
+}
+
+impl Default for GasModel {
+    fn default() -> Self {
+        GasModel {
+            base: 76_000,
+            per_step: 4_000,
+            per_cold_word: COLD_SLOAD_COST,
+            per_warm_word: WARM_STORAGE_READ_COST,
+            per_tick_slot_loaded: COLD_SLOAD_COST,
+            per_tick_slot_written: SSTORE_RESET_COST,
+            per_initialized_tick_crossed: 6_900,
+        }
+    }
+}
+
+impl GasModel {
+    //The storage cost of crossing a tick
+    fn tick_storage(&self) -> u64 {
+        self.per_tick_slot_loaded * TICK_SLOTS_LOADED
+            + self.per_tick_slot_written * TICK_SLOTS_WRITTEN
+    }
+
+    pub fn estimate(&self, usage: &SwapGasUsage) -> u64 {
+        self.base
+            + self.per_step * usage.steps
+            + self.per_cold_word * usage.cold_words_loaded
+            + self.per_warm_word * usage.warm_words_loaded
+            + (self.tick_storage() + self.per_initialized_tick_crossed)
+                * usage.initialized_ticks_crossed
+    }
+
+    pub fn estimate_trace(&self, trace: &SwapTrace) -> u64 {
+        self.estimate(&SwapGasUsage::from_trace(trace))
+    }
+
+    //Fits base and per_initialized_tick_crossed to observed gas estimates by least squares, keeping the per step and
+    //storage constants. The cost per tick is kept when every sample crosses the same number of ticks.
+    pub fn calibrate(&self, samples: &[(SwapGasUsage, u64)]) -> GasModel {
+        if samples.is_empty() {
+            return *self;
+        }
+
+        // the gas left once the fixed constants are accounted for, against the ticks crossed
+        let points = samples
+            .iter()
+            .map(|(usage, gas)| {
+                let fixed = self.per_step * usage.steps
+                    + self.per_cold_word * usage.cold_words_loaded
+                    + self.per_warm_word * usage.warm_words_loaded
+                    + self.tick_storage() * usage.initialized_ticks_crossed;
+                (
+                    usage.initialized_ticks_crossed as f64,
+                    *gas as f64 - fixed as f64,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let variance = points
+            .iter()
+            .map(|(x, _)| (x - mean_x).powi(2))
+            .sum::<f64>();
+
+        let per_tick = if variance > 0.0 {
+            points
+                .iter()
+                .map(|(x, y)| (x - mean_x) * (y - mean_y))
+                .sum::<f64>()
+                / variance
+        } else {
+            self.per_initialized_tick_crossed as f64
+        };
+        let base = mean_y - per_tick * mean_x;
+
+        GasModel {
+            base: base.round().max(0.0) as u64,
+            per_initialized_tick_crossed: per_tick.round().max(0.0) as u64,
+            ..*self
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test_utils::new_pool,
+        tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO},
+    };
+    use alloy_primitives::{Address, I256, U256};
+
+    fn trace(zero_for_one: bool, amount: i128) -> SwapTrace {
+        let mut pool = new_pool(
+            Address::ZERO,
+            &[(-120, 120, 1e18 as u128), (-60000, 60000, 1e18 as u128)],
+        );
+        let limit = if zero_for_one {
+            MIN_SQRT_RATIO + U256::from(1)
+        } else {
+            MAX_SQRT_RATIO - U256::from(1)
+        };
+        let (_, trace) = pool
+            .swap_with_trace(zero_for_one, I256::try_from(amount).unwrap(), limit)
+            .unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_from_trace() {
+        // staying inside the narrow range, a single word is searched
+        let usage = SwapGasUsage::from_trace(&trace(false, 1e15 as i128));
+        assert_eq!(
+            usage,
+            SwapGasUsage {
+                steps: 1,
+                cold_words_loaded: 1,
+                warm_words_loaded: 0,
+                initialized_ticks_crossed: 0,
+            }
+        );
+
+        // leaving it crosses its upper tick, a large swap then walks across empty words of the bitmap
+        let usage = SwapGasUsage::from_trace(&trace(false, 5e18 as i128));
+        assert_eq!(usage.initialized_ticks_crossed, 1);
+        assert!(usage.cold_words_loaded > 1);
+        assert_eq!(
+            usage.steps,
+            usage.cold_words_loaded + usage.warm_words_loaded
+        );
+
+        // going left from tick 0 searches word -1 after the empty step at the boundary of word 0
+        let usage = SwapGasUsage::from_trace(&trace(true, 1e15 as i128));
+        assert_eq!(usage.steps, 2);
+        assert_eq!(usage.cold_words_loaded, 2);
+    }
+
+    #[test]
+    fn test_estimate() {
+        let model = GasModel::default();
+        let small = model.estimate_trace(&trace(false, 1e15 as i128));
+        assert_eq!(small, 76_000 + 4_000 + 2_100);
+
+        // crossing a tick costs more than the steps it adds, mostly in the storage of the tick
+        let large = model.estimate_trace(&trace(false, 5e18 as i128));
+        assert_eq!(model.tick_storage(), 4 * 2_100 + 3 * 2_900);
+        assert!(large > small + model.tick_storage() + model.per_initialized_tick_crossed);
+    }
+
+    #[test]
+    fn test_calibrate() {
+        let model = GasModel::default();
+        let chain = GasModel {
+            base: 50_000,
+            per_initialized_tick_crossed: 18_000,
+            ..model
+        };
+
+        // samples measured on a chain with a cheaper swap are fitted exactly
+        let samples = [
+            (false, 1e15 as i128),
+            (false, 5e18 as i128),
+            (true, 1e15 as i128),
+        ]
+        .into_iter()
+        .map(|(zero_for_one, amount)| {
+            let usage = SwapGasUsage::from_trace(&trace(zero_for_one, amount));
+            (usage, chain.estimate(&usage))
+        })
+        .collect::<Vec<_>>();
+        assert_eq!(model.calibrate(&samples), chain);
+
+        // without samples crossing different numbers of ticks only the base is fitted
+        let calibrated = model.calibrate(&samples[..1]);
+        assert_eq!(calibrated.base, 50_000);
+        assert_eq!(calibrated.per_initialized_tick_crossed, 6_900);
+        assert_eq!(model.calibrate(&[]), model);
+    }
+}
@@ -1,15 +1,41 @@
 use alloy_primitives::U256;
 
+pub mod algebra;
+pub mod arbitrage;
+pub mod backtest;
+pub mod batch_quote;
 pub mod bit_math;
+pub mod dialect;
 pub mod error;
+pub mod fast_math;
 pub mod full_math;
+pub mod gas;
+pub mod liquidity_amounts;
+pub mod liquidity_curve;
+pub mod liquidity_distribution;
 pub mod liquidity_math;
+pub mod low_gas_safe_math;
+pub mod pool;
+pub mod position_manager;
+pub mod position_value;
+pub mod price_impact;
+pub mod range_order;
+pub mod replay;
+pub mod safe_cast;
+pub mod scenario;
 pub mod sqrt_price_math;
 pub mod swap_math;
+pub mod swap_trace;
+#[cfg(test)]
+pub(crate) mod test_utils;
 pub mod tick;
 pub mod tick_bitmap;
 pub mod tick_math;
 pub mod unsafe_math;
+pub mod v4;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod zap;
 
 const U256_1: U256 = U256::from_limbs([1, 0, 0, 0]);
 const U256_2: U256 = U256::from_limbs([2, 0, 0, 0]);
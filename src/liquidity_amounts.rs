@@ -0,0 +1,202 @@
+use alloy_primitives::U256;
+
+use crate::{error::UniswapV3MathError, full_math::mul_div, safe_cast::to_uint128};
+
+const Q96: U256 = U256::from_limbs([0, 4294967296, 0, 0]);
+
+fn sort(sqrt_ratio_a_x_96: U256, sqrt_ratio_b_x_96: U256) -> (U256, U256) {
+    if sqrt_ratio_a_x_96 > sqrt_ratio_b_x_96 {
+        (sqrt_ratio_b_x_96, sqrt_ratio_a_x_96)
+    } else {
+        (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96)
+    }
+}
+
+//Computes the amount of liquidity received for a given amount of token0 and price range
+//Calculates amount0 * (sqrt(upper) * sqrt(lower)) / (sqrt(upper) - sqrt(lower))
+// returns (uint128 liquidity)
+pub fn get_liquidity_for_amount_0(
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    amount_0: U256,
+) -> Result<u128, UniswapV3MathError> {
+    let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = sort(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96);
+    let intermediate = mul_div(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, Q96)?;
+    to_uint128(mul_div(
+        amount_0,
+        intermediate,
+        sqrt_ratio_b_x_96 - sqrt_ratio_a_x_96,
+    )?)
+}
+
+//Computes the amount of liquidity received for a given amount of token1 and price range
+//Calculates amount1 / (sqrt(upper) - sqrt(lower))
+// returns (uint128 liquidity)
+pub fn get_liquidity_for_amount_1(
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    amount_1: U256,
+) -> Result<u128, UniswapV3MathError> {
+    let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = sort(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96);
+    to_uint128(mul_div(
+        amount_1,
+        Q96,
+        sqrt_ratio_b_x_96 - sqrt_ratio_a_x_96,
+    )?)
+}
+
+//Computes the maximum amount of liquidity received for a given amount of token0, token1, the current pool prices and
+//the prices at the tick boundaries
+// returns (uint128 liquidity)
+pub fn get_liquidity_for_amounts(
+    sqrt_ratio_x_96: U256,
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    amount_0: U256,
+    amount_1: U256,
+) -> Result<u128, UniswapV3MathError> {
+    let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = sort(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96);
+
+    if sqrt_ratio_x_96 <= sqrt_ratio_a_x_96 {
+        get_liquidity_for_amount_0(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, amount_0)
+    } else if sqrt_ratio_x_96 < sqrt_ratio_b_x_96 {
+        let liquidity_0 = get_liquidity_for_amount_0(sqrt_ratio_x_96, sqrt_ratio_b_x_96, amount_0)?;
+        let liquidity_1 = get_liquidity_for_amount_1(sqrt_ratio_a_x_96, sqrt_ratio_x_96, amount_1)?;
+        Ok(liquidity_0.min(liquidity_1))
+    } else {
+        get_liquidity_for_amount_1(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, amount_1)
+    }
+}
+
+//Computes the amount of token0 for a given amount of liquidity and a price range
+// returns (uint256 amount0)
+pub fn get_amount_0_for_liquidity(
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    liquidity: u128,
+) -> Result<U256, UniswapV3MathError> {
+    let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = sort(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96);
+    Ok(mul_div(
+        U256::from(liquidity) << 96,
+        sqrt_ratio_b_x_96 - sqrt_ratio_a_x_96,
+        sqrt_ratio_b_x_96,
+    )? / sqrt_ratio_a_x_96)
+}
+
+//Computes the amount of token1 for a given amount of liquidity and a price range
+// returns (uint256 amount1)
+pub fn get_amount_1_for_liquidity(
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    liquidity: u128,
+) -> Result<U256, UniswapV3MathError> {
+    let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = sort(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96);
+    mul_div(
+        U256::from(liquidity),
+        sqrt_ratio_b_x_96 - sqrt_ratio_a_x_96,
+        Q96,
+    )
+}
+
+//Computes the token0 and token1 value for a given amount of liquidity, the current pool prices and the prices at the
+//tick boundaries
+// returns (uint256 amount0, uint256 amount1)
+pub fn get_amounts_for_liquidity(
+    sqrt_ratio_x_96: U256,
+    sqrt_ratio_a_x_96: U256,
+    sqrt_ratio_b_x_96: U256,
+    liquidity: u128,
+) -> Result<(U256, U256), UniswapV3MathError> {
+    let (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = sort(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96);
+
+    if sqrt_ratio_x_96 <= sqrt_ratio_a_x_96 {
+        Ok((
+            get_amount_0_for_liquidity(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity)?,
+            U256::ZERO,
+        ))
+    } else if sqrt_ratio_x_96 < sqrt_ratio_b_x_96 {
+        Ok((
+            get_amount_0_for_liquidity(sqrt_ratio_x_96, sqrt_ratio_b_x_96, liquidity)?,
+            get_amount_1_for_liquidity(sqrt_ratio_a_x_96, sqrt_ratio_x_96, liquidity)?,
+        ))
+    } else {
+        Ok((
+            U256::ZERO,
+            get_amount_1_for_liquidity(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // encodePriceSqrt(reserve1, reserve0)
+    fn encode_price_sqrt(reserve_1: u64, reserve_0: u64) -> U256 {
+        let ratio_x_192: U256 = (U256::from(reserve_1) << 192) / U256::from(reserve_0);
+        ratio_x_192.root(2)
+    }
+
+    #[test]
+    fn test_get_liquidity_for_amounts() {
+        let sqrt_price_a = encode_price_sqrt(100, 110);
+        let sqrt_price_b = encode_price_sqrt(110, 100);
+
+        for (sqrt_price, liquidity) in [
+            // price inside
+            (encode_price_sqrt(1, 1), 2148),
+            // price below
+            (encode_price_sqrt(99, 110), 1048),
+            // price above
+            (encode_price_sqrt(111, 100), 2097),
+            // price on the boundaries
+            (sqrt_price_a, 1048),
+            (sqrt_price_b, 2097),
+        ] {
+            assert_eq!(
+                get_liquidity_for_amounts(
+                    sqrt_price,
+                    sqrt_price_a,
+                    sqrt_price_b,
+                    U256::from(100),
+                    U256::from(200)
+                )
+                .unwrap(),
+                liquidity
+            );
+        }
+
+        let result = get_liquidity_for_amount_1(sqrt_price_a, sqrt_price_b, U256::from(u128::MAX));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Overflow when casting to U128"
+        );
+    }
+
+    #[test]
+    fn test_get_amounts_for_liquidity() {
+        let sqrt_price_a = encode_price_sqrt(100, 110);
+        let sqrt_price_b = encode_price_sqrt(110, 100);
+
+        for (sqrt_price, liquidity, amount_0, amount_1) in [
+            (encode_price_sqrt(1, 1), 2148, 99, 99),
+            (encode_price_sqrt(99, 110), 1048, 99, 0),
+            (encode_price_sqrt(111, 100), 2097, 0, 199),
+            (sqrt_price_a, 1048, 99, 0),
+            (sqrt_price_b, 2097, 0, 199),
+        ] {
+            assert_eq!(
+                get_amounts_for_liquidity(sqrt_price, sqrt_price_a, sqrt_price_b, liquidity)
+                    .unwrap(),
+                (U256::from(amount_0), U256::from(amount_1))
+            );
+        }
+
+        // the price range may be given in any order
+        assert_eq!(
+            get_amounts_for_liquidity(encode_price_sqrt(1, 1), sqrt_price_b, sqrt_price_a, 2148)
+                .unwrap(),
+            (U256::from(99), U256::from(99))
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use alloy_primitives::U256;
+
+use crate::{
+    error::UniswapV3MathError,
+    full_math::mul_div,
+    liquidity_distribution::liquidity_distribution,
+    pool::Pool,
+    swap_math::{compute_swap_step_with_kind, SwapKind},
+    tick_math::get_sqrt_ratio_at_tick,
+};
+
+const Q96: U256 = U256::from_limbs([0, 4294967296, 0, 0]);
+
+//A range of constant liquidity, on which the pool trades as the constant product x * y = L^2 of its virtual reserves
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CurveSegment {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    //The exact sqrt prices of the ticks
+    pub sqrt_price_lower_x_96: U256,
+    pub sqrt_price_upper_x_96: U256,
+    pub liquidity: u128,
+    //L / sqrt(P) and L * sqrt(P) at the current price clamped to the segment, rounded down. The real reserves are the
+    //virtual ones less L / sqrt(upper) of token0 and L * sqrt(lower) of token1.
+    pub virtual_reserve_0: U256,
+    pub virtual_reserve_1: U256,
+    pub fee_pips: u32,
+}
+
+//The pool's liquidity as segments of constant liquidity in ascending price order, leaving out the ranges without
+//liquidity
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LiquidityCurve {
+    pub sqrt_price_x_96: U256,
+    pub segments: Vec<CurveSegment>,
+}
+
+pub fn liquidity_curve(pool: &Pool) -> Result<LiquidityCurve, UniswapV3MathError> {
+    let sqrt_price_x_96 = pool.slot0.sqrt_price_x_96;
+
+    let segments = liquidity_distribution(pool)?
+        .into_iter()
+        .filter(|range| range.liquidity > 0)
+        .map(|range| {
+            let sqrt_price_lower_x_96 = get_sqrt_ratio_at_tick(range.tick_lower)?;
+            let sqrt_price_upper_x_96 = get_sqrt_ratio_at_tick(range.tick_upper)?;
+            let sqrt_price_clamped_x_96 =
+                sqrt_price_x_96.clamp(sqrt_price_lower_x_96, sqrt_price_upper_x_96);
+            let liquidity = U256::from(range.liquidity);
+
+            Ok(CurveSegment {
+                tick_lower: range.tick_lower,
+                tick_upper: range.tick_upper,
+                sqrt_price_lower_x_96,
+                sqrt_price_upper_x_96,
+                liquidity: range.liquidity,
+                virtual_reserve_0: mul_div(liquidity, Q96, sqrt_price_clamped_x_96)?,
+                virtual_reserve_1: mul_div(liquidity, sqrt_price_clamped_x_96, Q96)?,
+                fee_pips: pool.config.fee,
+            })
+        })
+        .collect::<Result<_, UniswapV3MathError>>()?;
+
+    Ok(LiquidityCurve {
+        sqrt_price_x_96,
+        segments,
+    })
+}
+
+impl LiquidityCurve {
+    //Evaluates an exact input swap across the segments, one swap step per segment. The pool's swap also steps at
+    //tick bitmap word boundaries, where its rounding can differ from the curve's by a unit of each token. The input
+    //used is less than amount_in when the curve runs out of liquidity.
+    // returns (uint256 amountIn, uint256 amountOut)
+    pub fn amount_out(
+        &self,
+        zero_for_one: bool,
+        amount_in: U256,
+    ) -> Result<(U256, U256), UniswapV3MathError> {
+        let mut sqrt_price_x_96 = self.sqrt_price_x_96;
+        let mut amount_remaining = amount_in;
+        let mut amount_out = U256::ZERO;
+
+        let segments: Box<dyn Iterator<Item = &CurveSegment>> = if zero_for_one {
+            Box::new(self.segments.iter().rev())
+        } else {
+            Box::new(self.segments.iter())
+        };
+        for segment in segments {
+            if amount_remaining.is_zero() {
+                break;
+            }
+
+            // the segments the price has already passed in the swap direction
+            let (start, target) = if zero_for_one {
+                if segment.sqrt_price_lower_x_96 >= sqrt_price_x_96 {
+                    continue;
+                }
+                (
+                    sqrt_price_x_96.min(segment.sqrt_price_upper_x_96),
+                    segment.sqrt_price_lower_x_96,
+                )
+            } else {
+                if segment.sqrt_price_upper_x_96 <= sqrt_price_x_96 {
+                    continue;
+                }
+                (
+                    sqrt_price_x_96.max(segment.sqrt_price_lower_x_96),
+                    segment.sqrt_price_upper_x_96,
+                )
+            };
+
+            let step = compute_swap_step_with_kind(
+                start,
+                target,
+                segment.liquidity,
+                amount_remaining,
+                SwapKind::ExactIn,
+                segment.fee_pips,
+            )?;
+            amount_remaining -= step.amount_in + step.fee_amount;
+            amount_out += step.amount_out;
+            sqrt_price_x_96 = step.sqrt_ratio_next_x_96;
+        }
+
+        Ok((amount_in - amount_remaining, amount_out))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test_utils::{new_pool, SQRT_PRICE_1_1},
+        tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO},
+        U256_1,
+    };
+    use alloy_primitives::{Address, I256};
+
+    // returns (uint256 amountIn, uint256 amountOut)
+    fn swap(pool: &Pool, zero_for_one: bool, amount_in: U256) -> (U256, U256) {
+        let mut pool = pool.clone();
+        let limit = if zero_for_one {
+            MIN_SQRT_RATIO + U256_1
+        } else {
+            MAX_SQRT_RATIO - U256_1
+        };
+        let result = pool
+            .swap(zero_for_one, I256::from_raw(amount_in), limit)
+            .unwrap();
+        let (paid, received) = if zero_for_one {
+            (result.amount_0, result.amount_1)
+        } else {
+            (result.amount_1, result.amount_0)
+        };
+        (paid.into_raw(), (-received).into_raw())
+    }
+
+    #[test]
+    fn test_liquidity_curve() {
+        // a gap without liquidity between 600 and 1200
+        let pool = new_pool(
+            Address::ZERO,
+            &[
+                (-600, 600, 1e18 as u128),
+                (-120, 60, 5e18 as u128),
+                (1200, 1800, 1e18 as u128),
+            ],
+        );
+        let curve = liquidity_curve(&pool).unwrap();
+        assert_eq!(curve.sqrt_price_x_96, SQRT_PRICE_1_1);
+
+        let ticks = curve
+            .segments
+            .iter()
+            .map(|segment| (segment.tick_lower, segment.tick_upper, segment.liquidity))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ticks,
+            vec![
+                (-600, -120, 1e18 as u128),
+                (-120, 60, 6e18 as u128),
+                (60, 600, 1e18 as u128),
+                (1200, 1800, 1e18 as u128),
+            ]
+        );
+
+        for segment in &curve.segments {
+            assert_eq!(segment.fee_pips, 3000);
+            assert_eq!(
+                segment.sqrt_price_lower_x_96,
+                get_sqrt_ratio_at_tick(segment.tick_lower).unwrap()
+            );
+
+            // the virtual reserves multiply to L^2, up to their rounding down
+            let product = segment.virtual_reserve_0 * segment.virtual_reserve_1;
+            let liquidity_squared = U256::from(segment.liquidity).pow(U256::from(2));
+            assert!(product <= liquidity_squared);
+            assert!(
+                liquidity_squared - product
+                    <= segment.virtual_reserve_0 + segment.virtual_reserve_1
+            );
+        }
+
+        // the active segment is at the current price, L / 1 and L * 1
+        let active = curve.segments[1];
+        assert_eq!(active.virtual_reserve_0, U256::from(6e18 as u64));
+        assert_eq!(active.virtual_reserve_1, U256::from(6e18 as u64));
+    }
+
+    #[test]
+    fn test_amount_out() {
+        let pool = new_pool(
+            Address::ZERO,
+            &[
+                (-600, 600, 1e18 as u128),
+                (-120, 60, 5e18 as u128),
+                (1200, 1800, 1e18 as u128),
+            ],
+        );
+        let curve = liquidity_curve(&pool).unwrap();
+
+        // within a single bitmap word the curve matches the swap exactly, across the gap and past all the liquidity
+        for zero_for_one in [true, false] {
+            for amount_in in [
+                1e3 as u128,
+                1e16 as u128,
+                5e16 as u128,
+                1e17 as u128,
+                1e20 as u128,
+            ] {
+                let amount_in = U256::from(amount_in);
+                assert_eq!(
+                    curve.amount_out(zero_for_one, amount_in).unwrap(),
+                    swap(&pool, zero_for_one, amount_in)
+                );
+            }
+        }
+
+        let (used, _) = curve.amount_out(false, U256::from(1e20 as u128)).unwrap();
+        assert!(used < U256::from(1e20 as u128));
+    }
+
+    #[test]
+    fn test_amount_out_across_words() {
+        // a wide range spanning several bitmap words, where the swap takes extra steps
+        let pool = new_pool(Address::ZERO, &[(-60000, 60000, 1e18 as u128)]);
+        let curve = liquidity_curve(&pool).unwrap();
+
+        for zero_for_one in [true, false] {
+            let amount_in = U256::from(5e18 as u64);
+            let (curve_in, curve_out) = curve.amount_out(zero_for_one, amount_in).unwrap();
+            let (swap_in, swap_out) = swap(&pool, zero_for_one, amount_in);
+            assert_eq!(curve_in, swap_in);
+            assert!(curve_out.abs_diff(swap_out) <= U256::from(4));
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use alloy_primitives::U256;
+
+use crate::{
+    error::UniswapV3MathError,
+    liquidity_math,
+    pool::Pool,
+    price_impact::sqrt_price_for_price_impact,
+    sqrt_price_math::{_get_amount_0_delta, _get_amount_1_delta},
+    tick_math::{get_sqrt_ratio_at_tick, MAX_TICK, MIN_TICK},
+};
+
+//The active liquidity between two consecutive initialized ticks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LiquidityRange {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+}
+
+//The tokens held by the pool's liquidity within a tick range at the current price
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LiquidityBucket {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub amount_0: U256,
+    pub amount_1: U256,
+}
+
+//Returns the active liquidity curve, one range per pair of consecutive initialized ticks, found by accumulating
+//liquidity_net from the lowest initialized tick upwards
+pub fn liquidity_distribution(pool: &Pool) -> Result<Vec<LiquidityRange>, UniswapV3MathError> {
+    let mut ticks = pool
+        .ticks
+        .iter()
+        .filter(|(_, tick)| tick.liquidity_gross > 0)
+        .map(|(tick, info)| (*tick, info.liquidity_net))
+        .collect::<Vec<_>>();
+    ticks.sort_unstable_by_key(|(tick, _)| *tick);
+
+    let mut ranges = Vec::with_capacity(ticks.len().saturating_sub(1));
+    let mut liquidity = 0;
+    for window in ticks.windows(2) {
+        let (tick_lower, liquidity_net) = window[0];
+        liquidity = liquidity_math::add_delta(liquidity, liquidity_net)?;
+        ranges.push(LiquidityRange {
+            tick_lower,
+            tick_upper: window[1].0,
+            liquidity,
+        });
+    }
+
+    Ok(ranges)
+}
+
+//Returns the token amounts held by the liquidity of the ranges between two sqrt prices, token0 above the current
+//price and token1 below it. Amounts are rounded down.
+// returns (uint256 amount0, uint256 amount1)
+pub fn amounts_in_sqrt_price_band(
+    ranges: &[LiquidityRange],
+    sqrt_price_x_96: U256,
+    sqrt_price_a_x_96: U256,
+    sqrt_price_b_x_96: U256,
+) -> Result<(U256, U256), UniswapV3MathError> {
+    let (band_lower, band_upper) = if sqrt_price_a_x_96 <= sqrt_price_b_x_96 {
+        (sqrt_price_a_x_96, sqrt_price_b_x_96)
+    } else {
+        (sqrt_price_b_x_96, sqrt_price_a_x_96)
+    };
+
+    let mut amount_0 = U256::ZERO;
+    let mut amount_1 = U256::ZERO;
+    for range in ranges.iter().filter(|range| range.liquidity > 0) {
+        let lower = get_sqrt_ratio_at_tick(range.tick_lower)?.max(band_lower);
+        let upper = get_sqrt_ratio_at_tick(range.tick_upper)?.min(band_upper);
+        if lower >= upper {
+            continue;
+        }
+
+        if sqrt_price_x_96 < upper {
+            amount_0 +=
+                _get_amount_0_delta(lower.max(sqrt_price_x_96), upper, range.liquidity, false)?;
+        }
+        if sqrt_price_x_96 > lower {
+            amount_1 +=
+                _get_amount_1_delta(lower, upper.min(sqrt_price_x_96), range.liquidity, false)?;
+        }
+    }
+
+    Ok((amount_0, amount_1))
+}
+
+//Returns the depth within band_bps basis points of the current price: the token0 available to buyers up to
+//price * (1 + band) and the token1 available to sellers down to price * (1 - band)
+// returns (uint256 amount0, uint256 amount1)
+pub fn depth(pool: &Pool, band_bps: u32) -> Result<(U256, U256), UniswapV3MathError> {
+    let sqrt_price_x_96 = pool.slot0.sqrt_price_x_96;
+    amounts_in_sqrt_price_band(
+        &liquidity_distribution(pool)?,
+        sqrt_price_x_96,
+        sqrt_price_for_price_impact(sqrt_price_x_96, true, band_bps)?,
+        sqrt_price_for_price_impact(sqrt_price_x_96, false, band_bps)?,
+    )
+}
+
+//Splits the initialized liquidity into buckets of bucket_ticks ticks aligned to multiples of bucket_ticks, and returns
+//the token amounts held in each
+pub fn liquidity_buckets(
+    pool: &Pool,
+    bucket_ticks: i32,
+) -> Result<Vec<LiquidityBucket>, UniswapV3MathError> {
+    if bucket_ticks <= 0 {
+        return Err(UniswapV3MathError::TickSpacingOutOfBounds(bucket_ticks));
+    }
+
+    let ranges = liquidity_distribution(pool)?;
+    let (Some(first), Some(last)) = (ranges.first(), ranges.last()) else {
+        return Ok(vec![]);
+    };
+
+    let mut buckets = vec![];
+    let mut tick_lower = first.tick_lower.div_euclid(bucket_ticks) * bucket_ticks;
+    while tick_lower < last.tick_upper {
+        let tick_upper = tick_lower + bucket_ticks;
+        let (amount_0, amount_1) = amounts_in_sqrt_price_band(
+            &ranges,
+            pool.slot0.sqrt_price_x_96,
+            get_sqrt_ratio_at_tick(tick_lower.max(MIN_TICK))?,
+            get_sqrt_ratio_at_tick(tick_upper.min(MAX_TICK))?,
+        )?;
+        buckets.push(LiquidityBucket {
+            tick_lower,
+            tick_upper,
+            amount_0,
+            amount_1,
+        });
+        tick_lower = tick_upper;
+    }
+
+    Ok(buckets)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{self, SQRT_PRICE_1_1};
+    use alloy_primitives::Address;
+
+    fn new_pool() -> Pool {
+        test_utils::new_pool(
+            Address::ZERO,
+            &[
+                (-600, 600, 1e18 as u128),
+                (-120, 60, 5e18 as u128),
+                (1200, 2400, 2e18 as u128),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_liquidity_distribution() {
+        let pool = new_pool();
+        let ranges = liquidity_distribution(&pool).unwrap();
+        let expected = [
+            (-600, -120, 1e18),
+            (-120, 60, 6e18),
+            (60, 600, 1e18),
+            (600, 1200, 0.0),
+            (1200, 2400, 2e18),
+        ];
+        assert_eq!(ranges.len(), expected.len());
+        for (range, (tick_lower, tick_upper, liquidity)) in ranges.iter().zip(expected) {
+            assert_eq!(
+                *range,
+                LiquidityRange {
+                    tick_lower,
+                    tick_upper,
+                    liquidity: liquidity as u128,
+                }
+            );
+        }
+
+        // the range around the current tick holds the active liquidity
+        let active = ranges
+            .iter()
+            .find(|range| (range.tick_lower..range.tick_upper).contains(&pool.slot0.tick))
+            .unwrap();
+        assert_eq!(active.liquidity, pool.liquidity);
+    }
+
+    #[test]
+    fn test_amounts_match_minted_amounts() {
+        let pool = new_pool();
+        let ranges = liquidity_distribution(&pool).unwrap();
+
+        // the whole curve holds what was minted, up to rounding
+        let (amount_0, amount_1) = amounts_in_sqrt_price_band(
+            &ranges,
+            pool.slot0.sqrt_price_x_96,
+            get_sqrt_ratio_at_tick(MIN_TICK).unwrap(),
+            get_sqrt_ratio_at_tick(MAX_TICK).unwrap(),
+        )
+        .unwrap();
+        let mut minted = pool.clone();
+        let (burned_0, burned_1) = [(-600, 600, 1e18), (-120, 60, 5e18), (1200, 2400, 2e18)]
+            .into_iter()
+            .fold(
+                (U256::ZERO, U256::ZERO),
+                |(total_0, total_1), (lower, upper, amount)| {
+                    let (amount_0, amount_1) = minted
+                        .burn(Address::ZERO, lower, upper, amount as u128)
+                        .unwrap();
+                    (total_0 + amount_0, total_1 + amount_1)
+                },
+            );
+        assert!(burned_0.abs_diff(amount_0) <= U256::from(ranges.len()));
+        assert!(burned_1.abs_diff(amount_1) <= U256::from(ranges.len()));
+
+        // buckets partition the curve
+        let buckets = liquidity_buckets(&pool, 200).unwrap();
+        assert_eq!(buckets.first().unwrap().tick_lower, -600);
+        assert_eq!(buckets.last().unwrap().tick_upper, 2400);
+        let bucket_total_0: U256 = buckets.iter().map(|bucket| bucket.amount_0).sum();
+        let bucket_total_1: U256 = buckets.iter().map(|bucket| bucket.amount_1).sum();
+        assert!(amount_0.abs_diff(bucket_total_0) <= U256::from(buckets.len()));
+        assert!(amount_1.abs_diff(bucket_total_1) <= U256::from(buckets.len()));
+
+        // buckets below the price only hold token1, above it only token0, and the gap holds nothing
+        for bucket in &buckets {
+            if bucket.tick_upper <= 0 {
+                assert!(bucket.amount_0.is_zero() && !bucket.amount_1.is_zero());
+            } else if bucket.tick_lower >= 600 && bucket.tick_upper <= 1200 {
+                assert!(bucket.amount_0.is_zero() && bucket.amount_1.is_zero());
+            } else if bucket.tick_lower >= 0 {
+                assert!(!bucket.amount_0.is_zero() && bucket.amount_1.is_zero());
+            }
+        }
+
+        assert!(liquidity_buckets(&pool, 0).is_err());
+    }
+
+    #[test]
+    fn test_depth() {
+        let pool = new_pool();
+
+        // within 1% (~100 ticks) only the two inner ranges are reached on each side
+        let (amount_0, amount_1) = depth(&pool, 100).unwrap();
+        let sqrt_price_upper = sqrt_price_for_price_impact(SQRT_PRICE_1_1, false, 100).unwrap();
+        let sqrt_price_lower = sqrt_price_for_price_impact(SQRT_PRICE_1_1, true, 100).unwrap();
+        let sqrt_price_60 = get_sqrt_ratio_at_tick(60).unwrap();
+        assert_eq!(
+            amount_0,
+            _get_amount_0_delta(SQRT_PRICE_1_1, sqrt_price_60, 6e18 as u128, false).unwrap()
+                + _get_amount_0_delta(sqrt_price_60, sqrt_price_upper, 1e18 as u128, false)
+                    .unwrap()
+        );
+        assert_eq!(
+            amount_1,
+            _get_amount_1_delta(sqrt_price_lower, SQRT_PRICE_1_1, 6e18 as u128, false).unwrap()
+        );
+
+        // depth grows with the band
+        let (wide_0, wide_1) = depth(&pool, 2000).unwrap();
+        assert!(wide_0 > amount_0 && wide_1 > amount_1);
+    }
+}
@@ -3,7 +3,7 @@ use crate::error::UniswapV3MathError;
 // returns (uint128 z)
 pub fn add_delta(x: u128, y: i128) -> Result<u128, UniswapV3MathError> {
     if y < 0 {
-        let z = x.overflowing_sub(-y as u128);
+        let z = x.overflowing_sub(y.unsigned_abs());
 
         if z.1 {
             Err(UniswapV3MathError::LiquiditySub)
@@ -24,6 +24,8 @@ pub fn add_delta(x: u128, y: i128) -> Result<u128, UniswapV3MathError> {
 mod test {
 
     use crate::liquidity_math::add_delta;
+    #[cfg(not(target_arch = "wasm32"))]
+    use proptest::prelude::*;
 
     #[test]
     fn test_add_delta() {
@@ -50,5 +52,29 @@ mod test {
         // 3 + -4 underflows
         let result = add_delta(3, -4);
         assert_eq!(result.err().unwrap().to_string(), "Liquidity Sub");
+
+        // 2**128-1 + -2**127
+        let result = add_delta(u128::MAX, i128::MIN);
+        assert_eq!(result.unwrap(), u128::MAX >> 1);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    proptest! {
+        #[test]
+        fn prop_add_delta_is_reversible(x in any::<u128>(), y in (i128::MIN + 1)..=i128::MAX) {
+            if let Ok(z) = add_delta(x, y) {
+                prop_assert_eq!(add_delta(z, -y).unwrap(), x);
+            }
+        }
+
+        #[test]
+        fn prop_add_delta_matches_checked_arithmetic(x in any::<u128>(), y in any::<i128>()) {
+            let expected = if y < 0 {
+                x.checked_sub(y.unsigned_abs())
+            } else {
+                x.checked_add(y as u128)
+            };
+            prop_assert_eq!(add_delta(x, y).ok(), expected);
+        }
     }
 }
@@ -0,0 +1,82 @@
+use alloy_primitives::{I256, U256};
+
+use crate::error::UniswapV3MathError;
+
+// returns (uint256 z)
+pub fn add(x: U256, y: U256) -> Result<U256, UniswapV3MathError> {
+    x.checked_add(y)
+        .ok_or(UniswapV3MathError::LowGasSafeMathAddOverflow)
+}
+
+// returns (uint256 z)
+pub fn sub(x: U256, y: U256) -> Result<U256, UniswapV3MathError> {
+    x.checked_sub(y)
+        .ok_or(UniswapV3MathError::LowGasSafeMathSubUnderflow)
+}
+
+// returns (uint256 z)
+pub fn mul(x: U256, y: U256) -> Result<U256, UniswapV3MathError> {
+    x.checked_mul(y)
+        .ok_or(UniswapV3MathError::LowGasSafeMathMulOverflow)
+}
+
+// returns (int256 z)
+pub fn add_signed(x: I256, y: I256) -> Result<I256, UniswapV3MathError> {
+    x.checked_add(y)
+        .ok_or(UniswapV3MathError::LowGasSafeMathAddOverflow)
+}
+
+// returns (int256 z)
+pub fn sub_signed(x: I256, y: I256) -> Result<I256, UniswapV3MathError> {
+    x.checked_sub(y)
+        .ok_or(UniswapV3MathError::LowGasSafeMathSubUnderflow)
+}
+
+#[cfg(test)]
+mod test {
+    use alloy_primitives::{I256, U256};
+
+    use super::{add, add_signed, mul, sub, sub_signed};
+    use crate::{U256_1, U256_2};
+
+    #[test]
+    fn test_unsigned() {
+        assert_eq!(add(U256_1, U256_1).unwrap(), U256_2);
+        assert_eq!(
+            add(U256::MAX, U256_1).unwrap_err().to_string(),
+            "Addition overflow"
+        );
+
+        assert_eq!(sub(U256_2, U256_1).unwrap(), U256_1);
+        assert_eq!(
+            sub(U256_1, U256_2).unwrap_err().to_string(),
+            "Subtraction underflow"
+        );
+
+        assert_eq!(mul(U256::ZERO, U256::MAX).unwrap(), U256::ZERO);
+        assert_eq!(mul(U256::MAX, U256_1).unwrap(), U256::MAX);
+        assert_eq!(
+            mul(U256::MAX, U256_2).unwrap_err().to_string(),
+            "Multiplication overflow"
+        );
+    }
+
+    #[test]
+    fn test_signed() {
+        assert_eq!(
+            add_signed(I256::MAX, I256::MINUS_ONE).unwrap(),
+            I256::MAX - I256::ONE
+        );
+        assert_eq!(add_signed(I256::MIN, I256::MAX).unwrap(), I256::MINUS_ONE);
+        assert!(add_signed(I256::MAX, I256::ONE).is_err());
+        assert!(add_signed(I256::MIN, I256::MINUS_ONE).is_err());
+
+        assert_eq!(
+            sub_signed(I256::MIN, I256::MINUS_ONE).unwrap(),
+            I256::MIN + I256::ONE
+        );
+        assert!(sub_signed(I256::MIN, I256::ONE).is_err());
+        assert!(sub_signed(I256::MAX, I256::MINUS_ONE).is_err());
+        assert!(sub_signed(I256::ZERO, I256::MIN).is_err());
+    }
+}
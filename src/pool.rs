@@ -0,0 +1,1265 @@
+use std::collections::HashMap;
+
+use alloy_primitives::{Address, I256, U256};
+
+use crate::{
+    dialect::{Dialect, PoolConfig},
+    error::UniswapV3MathError,
+    full_math::mul_div,
+    liquidity_math,
+    sqrt_price_math::{get_amount_0_delta, get_amount_1_delta},
+    swap_math::{compute_swap_step_with_kind, SwapKind},
+    swap_trace::{SwapStepTrace, SwapTrace},
+    tick::{self, check_ticks, Tick},
+    tick_bitmap::{flip_tick, next_initialized_tick_within_one_word, position},
+    tick_math::{
+        get_sqrt_ratio_at_tick, get_tick_at_sqrt_ratio, MAX_SQRT_RATIO, MAX_TICK, MIN_SQRT_RATIO,
+        MIN_TICK,
+    },
+};
+
+const Q128: U256 = U256::from_limbs([0, 0, 1, 0]);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Slot0 {
+    pub sqrt_price_x_96: U256,
+    pub tick: i32,
+    //The packed protocol fee of both tokens, decoded by the pool's dialect
+    pub fee_protocol: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PositionKey {
+    pub owner: Address,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    pub liquidity: u128,
+    pub fee_growth_inside_0_last_x_128: U256,
+    pub fee_growth_inside_1_last_x_128: U256,
+    pub tokens_owed_0: u128,
+    pub tokens_owed_1: u128,
+}
+
+impl Position {
+    //Credits accumulated fees to a user's position
+    pub fn update(
+        &mut self,
+        liquidity_delta: i128,
+        fee_growth_inside_0_x_128: U256,
+        fee_growth_inside_1_x_128: U256,
+    ) -> Result<(), UniswapV3MathError> {
+        if liquidity_delta == 0 {
+            // disallow pokes for 0 liquidity positions
+            if self.liquidity == 0 {
+                return Err(UniswapV3MathError::CannotUpdateEmptyPosition);
+            }
+        }
+
+        // calculate accumulated fees, overflow in the subtraction of fee growth is expected
+        let tokens_owed_0: u128 = mul_div(
+            fee_growth_inside_0_x_128.wrapping_sub(self.fee_growth_inside_0_last_x_128),
+            U256::from(self.liquidity),
+            Q128,
+        )?
+        .wrapping_to();
+        let tokens_owed_1: u128 = mul_div(
+            fee_growth_inside_1_x_128.wrapping_sub(self.fee_growth_inside_1_last_x_128),
+            U256::from(self.liquidity),
+            Q128,
+        )?
+        .wrapping_to();
+
+        // update the position
+        if liquidity_delta != 0 {
+            self.liquidity = liquidity_math::add_delta(self.liquidity, liquidity_delta)?;
+        }
+        self.fee_growth_inside_0_last_x_128 = fee_growth_inside_0_x_128;
+        self.fee_growth_inside_1_last_x_128 = fee_growth_inside_1_x_128;
+        if tokens_owed_0 > 0 || tokens_owed_1 > 0 {
+            // overflow is acceptable, have to withdraw before you hit type(uint128).max fees
+            self.tokens_owed_0 = self.tokens_owed_0.wrapping_add(tokens_owed_0);
+            self.tokens_owed_1 = self.tokens_owed_1.wrapping_add(tokens_owed_1);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapResult {
+    //The delta of the balance of token0 of the pool, exact when negative, minimum when positive
+    pub amount_0: I256,
+    //The delta of the balance of token1 of the pool, exact when negative, minimum when positive
+    pub amount_1: I256,
+    //How the swap fee was split between liquidity providers, the protocol and the gauge
+    pub fees: SwapFees,
+}
+
+//The swap fee of a swap split per recipient and token. Fees are only charged in the input token, so the amounts of
+//the output token are zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapFees {
+    //The fee earned by in range liquidity providers
+    pub lp_fee_0: U256,
+    pub lp_fee_1: U256,
+    //The fee owed to the protocol, as set by feeProtocol
+    pub protocol_fee_0: U256,
+    pub protocol_fee_1: U256,
+    //The fee owed to the gauge, only charged by Slipstream pools
+    pub gauge_fee_0: U256,
+    pub gauge_fee_1: U256,
+    //The increase of feeGrowthGlobalX128. Rounding down in the fee growth leaves dust of the lp fee in the pool.
+    pub fee_growth_global_0_delta_x_128: U256,
+    pub fee_growth_global_1_delta_x_128: U256,
+}
+
+impl SwapFees {
+    //Returns the total fee charged in token0
+    pub fn total_fee_0(&self) -> U256 {
+        self.lp_fee_0 + self.protocol_fee_0 + self.gauge_fee_0
+    }
+
+    //Returns the total fee charged in token1
+    pub fn total_fee_1(&self) -> U256 {
+        self.lp_fee_1 + self.protocol_fee_1 + self.gauge_fee_1
+    }
+}
+
+//The entries of a pool a change to a position can write, kept to undo a change that fails part way
+struct PositionSnapshot {
+    position: (PositionKey, Option<Position>),
+    ticks: [(i32, Option<Tick>); 2],
+    words: Vec<(i16, Option<U256>)>,
+    liquidity: u128,
+}
+
+impl PositionSnapshot {
+    fn take(pool: &Pool, owner: Address, tick_lower: i32, tick_upper: i32) -> PositionSnapshot {
+        let key = PositionKey {
+            owner,
+            tick_lower,
+            tick_upper,
+        };
+        let words = [tick_lower, tick_upper]
+            .into_iter()
+            .filter_map(|tick| tick.checked_div(pool.config.tick_spacing))
+            .map(|compressed| {
+                let (word_pos, _) = position(compressed);
+                (word_pos, pool.tick_bitmap.get(&word_pos).copied())
+            })
+            .collect();
+
+        PositionSnapshot {
+            position: (key, pool.positions.get(&key).copied()),
+            ticks: [tick_lower, tick_upper].map(|tick| (tick, pool.ticks.get(&tick).copied())),
+            words,
+            liquidity: pool.liquidity,
+        }
+    }
+
+    fn restore(self, pool: &mut Pool) {
+        restore_entry(&mut pool.positions, self.position);
+        for entry in self.ticks {
+            restore_entry(&mut pool.ticks, entry);
+        }
+        for entry in self.words {
+            restore_entry(&mut pool.tick_bitmap, entry);
+        }
+        pool.liquidity = self.liquidity;
+    }
+}
+
+pub(crate) fn restore_entry<K: std::hash::Hash + Eq, V>(
+    map: &mut HashMap<K, V>,
+    (key, value): (K, Option<V>),
+) {
+    match value {
+        Some(value) => map.insert(key, value),
+        None => map.remove(&key),
+    };
+}
+
+//The state of a single UniswapV3Pool, or one of its forks as described by the pool's config. Observations are not
+//tracked, so the oracle values of ticks are left at zero.
+#[derive(Debug, Clone)]
+pub struct Pool {
+    pub config: PoolConfig,
+    pub slot0: Slot0,
+    pub fee_growth_global_0_x_128: U256,
+    pub fee_growth_global_1_x_128: U256,
+    pub protocol_fees_0: U256,
+    pub protocol_fees_1: U256,
+    pub liquidity: u128,
+    pub ticks: HashMap<i32, Tick>,
+    pub tick_bitmap: HashMap<i16, U256>,
+    pub positions: HashMap<PositionKey, Position>,
+    //Slipstream only, liquidity staked in the gauge which earns no fees in the pool
+    pub staked_liquidity: u128,
+    pub staked_liquidity_net: HashMap<i32, i128>,
+    //The liquidity staked on each range, at most the liquidity of the positions on the range
+    pub staked_range_liquidity: HashMap<(i32, i32), u128>,
+    pub gauge_fees_0: U256,
+    pub gauge_fees_1: U256,
+}
+
+impl Pool {
+    pub fn initialize(
+        config: PoolConfig,
+        sqrt_price_x_96: U256,
+    ) -> Result<Pool, UniswapV3MathError> {
+        let tick = get_tick_at_sqrt_ratio(sqrt_price_x_96)?;
+
+        Ok(Pool {
+            config,
+            slot0: Slot0 {
+                sqrt_price_x_96,
+                tick,
+                fee_protocol: 0,
+            },
+            fee_growth_global_0_x_128: U256::ZERO,
+            fee_growth_global_1_x_128: U256::ZERO,
+            protocol_fees_0: U256::ZERO,
+            protocol_fees_1: U256::ZERO,
+            liquidity: 0,
+            ticks: HashMap::new(),
+            tick_bitmap: HashMap::new(),
+            positions: HashMap::new(),
+            staked_liquidity: 0,
+            staked_liquidity_net: HashMap::new(),
+            staked_range_liquidity: HashMap::new(),
+            gauge_fees_0: U256::ZERO,
+            gauge_fees_1: U256::ZERO,
+        })
+    }
+
+    //Sets the packed protocol fee of both tokens, in the encoding of the pool's dialect
+    pub fn set_fee_protocol(&mut self, fee_protocol: u32) -> Result<(), UniswapV3MathError> {
+        self.config.dialect.validate_fee_protocol(fee_protocol)?;
+        self.slot0.fee_protocol = fee_protocol;
+        Ok(())
+    }
+
+    // returns (uint256 feeGrowthInside0X128, uint256 feeGrowthInside1X128)
+    pub fn get_fee_growth_inside(&self, tick_lower: i32, tick_upper: i32) -> (U256, U256) {
+        tick::get_fee_growth_inside(
+            &self.ticks,
+            tick_lower,
+            tick_upper,
+            self.slot0.tick,
+            self.fee_growth_global_0_x_128,
+            self.fee_growth_global_1_x_128,
+        )
+    }
+
+    //Adds liquidity to a position, returning the amounts of token0 and token1 owed to the pool
+    // returns (uint256 amount0, uint256 amount1)
+    pub fn mint(
+        &mut self,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    ) -> Result<(U256, U256), UniswapV3MathError> {
+        if amount == 0 {
+            return Err(UniswapV3MathError::LiquidityIsZero);
+        }
+        let liquidity_delta =
+            i128::try_from(amount).map_err(|_| UniswapV3MathError::SafeCastToI128Overflow)?;
+
+        let (amount_0, amount_1) =
+            self.modify_position(owner, tick_lower, tick_upper, liquidity_delta)?;
+
+        Ok((amount_0.into_raw(), amount_1.into_raw()))
+    }
+
+    //Removes liquidity from a position and credits the amounts to its tokens owed
+    // returns (uint256 amount0, uint256 amount1)
+    pub fn burn(
+        &mut self,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    ) -> Result<(U256, U256), UniswapV3MathError> {
+        let liquidity_delta =
+            i128::try_from(amount).map_err(|_| UniswapV3MathError::SafeCastToI128Overflow)?;
+
+        let (amount_0, amount_1) =
+            self.modify_position(owner, tick_lower, tick_upper, -liquidity_delta)?;
+        let (amount_0, amount_1) = (amount_0.unsigned_abs(), amount_1.unsigned_abs());
+
+        if !amount_0.is_zero() || !amount_1.is_zero() {
+            let position = self
+                .positions
+                .get_mut(&PositionKey {
+                    owner,
+                    tick_lower,
+                    tick_upper,
+                })
+                .expect("position was updated");
+            position.tokens_owed_0 = position.tokens_owed_0.wrapping_add(amount_0.wrapping_to());
+            position.tokens_owed_1 = position.tokens_owed_1.wrapping_add(amount_1.wrapping_to());
+        }
+
+        Ok((amount_0, amount_1))
+    }
+
+    //Collects up to the requested amounts of tokens owed to a position
+    // returns (uint128 amount0, uint128 amount1)
+    pub fn collect(
+        &mut self,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount_0_requested: u128,
+        amount_1_requested: u128,
+    ) -> (u128, u128) {
+        let Some(position) = self.positions.get_mut(&PositionKey {
+            owner,
+            tick_lower,
+            tick_upper,
+        }) else {
+            return (0, 0);
+        };
+
+        let amount_0 = amount_0_requested.min(position.tokens_owed_0);
+        let amount_1 = amount_1_requested.min(position.tokens_owed_1);
+        position.tokens_owed_0 -= amount_0;
+        position.tokens_owed_1 -= amount_1;
+
+        (amount_0, amount_1)
+    }
+
+    //Effect some changes to a position, returning the signed amounts of token0 and token1 owed to the pool
+    // returns (int256 amount0, int256 amount1)
+    pub fn modify_position(
+        &mut self,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity_delta: i128,
+    ) -> Result<(I256, I256), UniswapV3MathError> {
+        check_ticks(tick_lower, tick_upper)?;
+
+        // staked liquidity has to be unstaked before it can be burned
+        if liquidity_delta < 0 {
+            if let Some(&staked) = self.staked_range_liquidity.get(&(tick_lower, tick_upper)) {
+                let remaining = self
+                    .range_liquidity(tick_lower, tick_upper)
+                    .saturating_sub(liquidity_delta.unsigned_abs());
+                if remaining < staked {
+                    return Err(UniswapV3MathError::StakedLiquidityExceedsLiquidity);
+                }
+            }
+        }
+
+        // a change failing part way leaves the pool as it was, as the contract's revert would
+        let snapshot = PositionSnapshot::take(self, owner, tick_lower, tick_upper);
+        let result = self.apply_position_change(owner, tick_lower, tick_upper, liquidity_delta);
+        if result.is_err() {
+            snapshot.restore(self);
+        }
+        result
+    }
+
+    // returns (int256 amount0, int256 amount1)
+    fn apply_position_change(
+        &mut self,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity_delta: i128,
+    ) -> Result<(I256, I256), UniswapV3MathError> {
+        self.update_position(owner, tick_lower, tick_upper, liquidity_delta)?;
+
+        let mut amount_0 = I256::ZERO;
+        let mut amount_1 = I256::ZERO;
+
+        if liquidity_delta != 0 {
+            let tick = self.slot0.tick;
+            let sqrt_price_x_96 = self.slot0.sqrt_price_x_96;
+            let sqrt_price_lower_x_96 = get_sqrt_ratio_at_tick(tick_lower)?;
+            let sqrt_price_upper_x_96 = get_sqrt_ratio_at_tick(tick_upper)?;
+
+            if tick < tick_lower {
+                // current tick is below the passed range; liquidity can only become in range by crossing from left to
+                // right, when we'll need _more_ token0 (it's becoming more valuable) so user must provide it
+                amount_0 = get_amount_0_delta(
+                    sqrt_price_lower_x_96,
+                    sqrt_price_upper_x_96,
+                    liquidity_delta,
+                )?;
+            } else if tick < tick_upper {
+                // current tick is inside the passed range
+                amount_0 =
+                    get_amount_0_delta(sqrt_price_x_96, sqrt_price_upper_x_96, liquidity_delta)?;
+                amount_1 =
+                    get_amount_1_delta(sqrt_price_lower_x_96, sqrt_price_x_96, liquidity_delta)?;
+
+                self.liquidity = liquidity_math::add_delta(self.liquidity, liquidity_delta)?;
+            } else {
+                // current tick is above the passed range; liquidity can only become in range by crossing from right to
+                // left, when we'll need _more_ token1 (it's becoming more valuable) so user must provide it
+                amount_1 = get_amount_1_delta(
+                    sqrt_price_lower_x_96,
+                    sqrt_price_upper_x_96,
+                    liquidity_delta,
+                )?;
+            }
+        }
+
+        Ok((amount_0, amount_1))
+    }
+
+    fn update_position(
+        &mut self,
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity_delta: i128,
+    ) -> Result<(), UniswapV3MathError> {
+        let tick_current = self.slot0.tick;
+        let tick_spacing = self.config.tick_spacing;
+
+        // if we need to update the ticks, do it
+        let mut flipped_lower = false;
+        let mut flipped_upper = false;
+        if liquidity_delta != 0 {
+            let max_liquidity_per_tick =
+                tick::tick_spacing_to_max_liquidity_per_tick(tick_spacing)?;
+
+            for (tick, upper) in [(tick_lower, false), (tick_upper, true)] {
+                let flipped = tick::update(
+                    &mut self.ticks,
+                    tick,
+                    tick_current,
+                    liquidity_delta,
+                    self.fee_growth_global_0_x_128,
+                    self.fee_growth_global_1_x_128,
+                    U256::ZERO,
+                    U256::ZERO,
+                    0,
+                    upper,
+                    max_liquidity_per_tick,
+                )?;
+
+                if flipped {
+                    flip_tick(&mut self.tick_bitmap, tick, tick_spacing)?;
+                }
+                if upper {
+                    flipped_upper = flipped;
+                } else {
+                    flipped_lower = flipped;
+                }
+            }
+        }
+
+        let (fee_growth_inside_0_x_128, fee_growth_inside_1_x_128) =
+            self.get_fee_growth_inside(tick_lower, tick_upper);
+
+        self.positions
+            .entry(PositionKey {
+                owner,
+                tick_lower,
+                tick_upper,
+            })
+            .or_default()
+            .update(
+                liquidity_delta,
+                fee_growth_inside_0_x_128,
+                fee_growth_inside_1_x_128,
+            )?;
+
+        // clear any tick data that is no longer needed
+        if liquidity_delta < 0 {
+            if flipped_lower {
+                tick::clear(&mut self.ticks, tick_lower);
+            }
+            if flipped_upper {
+                tick::clear(&mut self.ticks, tick_upper);
+            }
+        }
+
+        Ok(())
+    }
+
+    //Stakes (or unstakes, for a negative delta) liquidity of a range in the gauge of a Slipstream pool
+    pub fn stake(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        staked_liquidity_delta: i128,
+    ) -> Result<(), UniswapV3MathError> {
+        if self.config.dialect != Dialect::Slipstream {
+            return Err(UniswapV3MathError::StakingNotSupported);
+        }
+        check_ticks(tick_lower, tick_upper)?;
+
+        // only liquidity minted on the range can be staked
+        let range = (tick_lower, tick_upper);
+        let staked_range_liquidity = liquidity_math::add_delta(
+            self.staked_range_liquidity
+                .get(&range)
+                .copied()
+                .unwrap_or_default(),
+            staked_liquidity_delta,
+        )?;
+        if staked_range_liquidity > self.range_liquidity(tick_lower, tick_upper) {
+            return Err(UniswapV3MathError::StakedLiquidityExceedsLiquidity);
+        }
+
+        let tick = self.slot0.tick;
+        let staked_liquidity = if tick_lower <= tick && tick < tick_upper {
+            liquidity_math::add_delta(self.staked_liquidity, staked_liquidity_delta)?
+        } else {
+            self.staked_liquidity
+        };
+        let lower = self
+            .staked_liquidity_net
+            .get(&tick_lower)
+            .copied()
+            .unwrap_or_default()
+            .checked_add(staked_liquidity_delta)
+            .ok_or(UniswapV3MathError::SafeCastToI128Overflow)?;
+        let upper = self
+            .staked_liquidity_net
+            .get(&tick_upper)
+            .copied()
+            .unwrap_or_default()
+            .checked_sub(staked_liquidity_delta)
+            .ok_or(UniswapV3MathError::SafeCastToI128Overflow)?;
+
+        self.staked_liquidity = staked_liquidity;
+        self.staked_liquidity_net.insert(tick_lower, lower);
+        self.staked_liquidity_net.insert(tick_upper, upper);
+        if staked_range_liquidity == 0 {
+            self.staked_range_liquidity.remove(&range);
+        } else {
+            self.staked_range_liquidity
+                .insert(range, staked_range_liquidity);
+        }
+
+        Ok(())
+    }
+
+    //The liquidity of all positions on the range
+    fn range_liquidity(&self, tick_lower: i32, tick_upper: i32) -> u128 {
+        self.positions
+            .iter()
+            .filter(|(key, _)| key.tick_lower == tick_lower && key.tick_upper == tick_upper)
+            .fold(0u128, |liquidity, (_, position)| {
+                liquidity.saturating_add(position.liquidity)
+            })
+    }
+
+    //Swap token0 for token1, or token1 for token0. A positive amount specified is the exact input, a negative amount
+    //the exact output.
+    pub fn swap(
+        &mut self,
+        zero_for_one: bool,
+        amount_specified: I256,
+        sqrt_price_limit_x_96: U256,
+    ) -> Result<SwapResult, UniswapV3MathError> {
+        self.swap_inner(zero_for_one, amount_specified, sqrt_price_limit_x_96, None)
+    }
+
+    //Swaps like swap, also recording every step of the swap loop
+    // returns (SwapResult result, SwapTrace trace)
+    pub fn swap_with_trace(
+        &mut self,
+        zero_for_one: bool,
+        amount_specified: I256,
+        sqrt_price_limit_x_96: U256,
+    ) -> Result<(SwapResult, SwapTrace), UniswapV3MathError> {
+        let mut trace = SwapTrace::default();
+        let result = self.swap_inner(
+            zero_for_one,
+            amount_specified,
+            sqrt_price_limit_x_96,
+            Some(&mut trace),
+        )?;
+        Ok((result, trace))
+    }
+
+    fn swap_inner(
+        &mut self,
+        zero_for_one: bool,
+        amount_specified: I256,
+        sqrt_price_limit_x_96: U256,
+        trace: Option<&mut SwapTrace>,
+    ) -> Result<SwapResult, UniswapV3MathError> {
+        // the ticks crossed by a swap failing part way are restored, as the contract's revert would
+        let mut crossed_ticks = vec![];
+        let result = self.swap_loop(
+            zero_for_one,
+            amount_specified,
+            sqrt_price_limit_x_96,
+            trace,
+            &mut crossed_ticks,
+        );
+        if result.is_err() {
+            for entry in crossed_ticks {
+                restore_entry(&mut self.ticks, entry);
+            }
+        }
+        result
+    }
+
+    fn swap_loop(
+        &mut self,
+        zero_for_one: bool,
+        amount_specified: I256,
+        sqrt_price_limit_x_96: U256,
+        mut trace: Option<&mut SwapTrace>,
+        crossed_ticks: &mut Vec<(i32, Option<Tick>)>,
+    ) -> Result<SwapResult, UniswapV3MathError> {
+        if amount_specified.is_zero() {
+            return Err(UniswapV3MathError::SwapAmountCannotBeZero);
+        }
+
+        let slot0_start = self.slot0;
+        let dialect = self.config.dialect;
+
+        if zero_for_one {
+            if sqrt_price_limit_x_96 >= slot0_start.sqrt_price_x_96 {
+                return Err(UniswapV3MathError::PriceLimitAlreadyExceeded(
+                    slot0_start.sqrt_price_x_96,
+                    sqrt_price_limit_x_96,
+                ));
+            }
+            if sqrt_price_limit_x_96 <= MIN_SQRT_RATIO {
+                return Err(UniswapV3MathError::PriceLimitOutOfBounds(
+                    sqrt_price_limit_x_96,
+                ));
+            }
+        } else {
+            if sqrt_price_limit_x_96 <= slot0_start.sqrt_price_x_96 {
+                return Err(UniswapV3MathError::PriceLimitAlreadyExceeded(
+                    slot0_start.sqrt_price_x_96,
+                    sqrt_price_limit_x_96,
+                ));
+            }
+            if sqrt_price_limit_x_96 >= MAX_SQRT_RATIO {
+                return Err(UniswapV3MathError::PriceLimitOutOfBounds(
+                    sqrt_price_limit_x_96,
+                ));
+            }
+        }
+
+        let fee_protocol = dialect.fee_protocol(slot0_start.fee_protocol, zero_for_one);
+        let exact_input = amount_specified > I256::ZERO;
+
+        let mut amount_specified_remaining = amount_specified;
+        let mut amount_calculated = I256::ZERO;
+        let mut sqrt_price_x_96 = slot0_start.sqrt_price_x_96;
+        let mut tick = slot0_start.tick;
+        let mut liquidity = self.liquidity;
+        let mut staked_liquidity = self.staked_liquidity;
+        let fee_growth_global_start_x_128 = if zero_for_one {
+            self.fee_growth_global_0_x_128
+        } else {
+            self.fee_growth_global_1_x_128
+        };
+        let mut fee_growth_global_x_128 = fee_growth_global_start_x_128;
+        let mut lp_fee = U256::ZERO;
+        let mut protocol_fee = U256::ZERO;
+        let mut gauge_fee = U256::ZERO;
+
+        // continue swapping as long as we haven't used the entire input/output and haven't reached the price limit
+        while !amount_specified_remaining.is_zero() && sqrt_price_x_96 != sqrt_price_limit_x_96 {
+            let sqrt_price_start_x_96 = sqrt_price_x_96;
+            let tick_start = tick;
+
+            let (mut tick_next, initialized) = next_initialized_tick_within_one_word(
+                &self.tick_bitmap,
+                tick,
+                self.config.tick_spacing,
+                zero_for_one,
+            )?;
+
+            // ensure that we do not overshoot the min/max tick, as the tick bitmap is not aware of these bounds
+            tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+
+            // get the price for the next tick
+            let sqrt_price_next_x_96 = get_sqrt_ratio_at_tick(tick_next)?;
+
+            // compute values to swap to the target tick, price limit, or point where input/output amount is exhausted
+            let sqrt_price_target_x_96 = if zero_for_one {
+                sqrt_price_next_x_96.max(sqrt_price_limit_x_96)
+            } else {
+                sqrt_price_next_x_96.min(sqrt_price_limit_x_96)
+            };
+            let liquidity_start = liquidity;
+            let amount_specified_remaining_start = amount_specified_remaining;
+            let fee_growth_global_start_step_x_128 = fee_growth_global_x_128;
+            let (amount_remaining, kind) =
+                SwapKind::from_amount_specified(amount_specified_remaining);
+            let mut step = compute_swap_step_with_kind(
+                sqrt_price_x_96,
+                sqrt_price_target_x_96,
+                liquidity,
+                amount_remaining,
+                kind,
+                self.config.fee,
+            )?;
+            sqrt_price_x_96 = step.sqrt_ratio_next_x_96;
+            let step_fee_amount = step.fee_amount;
+
+            if exact_input {
+                amount_specified_remaining -= I256::from_raw(step.amount_in + step.fee_amount);
+                amount_calculated -= I256::from_raw(step.amount_out);
+            } else {
+                amount_specified_remaining += I256::from_raw(step.amount_out);
+                amount_calculated += I256::from_raw(step.amount_in + step.fee_amount);
+            }
+
+            // if the protocol fee is on, calculate how much is owed, decrement fee_amount, and increment protocol_fee
+            let delta = dialect.protocol_fee_amount(step.fee_amount, fee_protocol);
+            step.fee_amount -= delta;
+            protocol_fee += delta;
+
+            // staked liquidity earns no fees, its share is paid to the gauge instead
+            let (lp_fee_amount, gauge_fee_amount) =
+                self.config
+                    .split_gauge_fee(step.fee_amount, liquidity, staked_liquidity)?;
+            gauge_fee += gauge_fee_amount;
+            lp_fee += lp_fee_amount;
+
+            // update global fee tracker
+            let fee_liquidity = liquidity
+                .checked_sub(staked_liquidity)
+                .ok_or(UniswapV3MathError::StakedLiquidityExceedsLiquidity)?;
+            if fee_liquidity > 0 {
+                fee_growth_global_x_128 = fee_growth_global_x_128.wrapping_add(mul_div(
+                    lp_fee_amount,
+                    Q128,
+                    U256::from(fee_liquidity),
+                )?);
+            }
+
+            let mut liquidity_net_applied = 0;
+
+            // shift tick if we reached the next price
+            if sqrt_price_x_96 == sqrt_price_next_x_96 {
+                // if the tick is initialized, run the tick transition
+                if initialized {
+                    let (fee_growth_global_0_x_128, fee_growth_global_1_x_128) = if zero_for_one {
+                        (fee_growth_global_x_128, self.fee_growth_global_1_x_128)
+                    } else {
+                        (self.fee_growth_global_0_x_128, fee_growth_global_x_128)
+                    };
+
+                    crossed_ticks.push((tick_next, self.ticks.get(&tick_next).copied()));
+                    let mut liquidity_net = tick::cross(
+                        &mut self.ticks,
+                        tick_next,
+                        fee_growth_global_0_x_128,
+                        fee_growth_global_1_x_128,
+                        U256::ZERO,
+                        U256::ZERO,
+                        0,
+                    );
+                    let mut staked_liquidity_net = self
+                        .staked_liquidity_net
+                        .get(&tick_next)
+                        .copied()
+                        .unwrap_or_default();
+
+                    // if we're moving leftward, we interpret liquidity_net as the opposite sign
+                    if zero_for_one {
+                        liquidity_net = -liquidity_net;
+                        staked_liquidity_net = -staked_liquidity_net;
+                    }
+
+                    liquidity = liquidity_math::add_delta(liquidity, liquidity_net)?;
+                    liquidity_net_applied = liquidity_net;
+                    staked_liquidity =
+                        liquidity_math::add_delta(staked_liquidity, staked_liquidity_net)?;
+                }
+
+                tick = if zero_for_one {
+                    tick_next - 1
+                } else {
+                    tick_next
+                };
+            } else if sqrt_price_x_96 != sqrt_price_start_x_96 {
+                // recompute unless we're on a lower tick boundary (i.e. already transitioned ticks), and haven't moved
+                tick = get_tick_at_sqrt_ratio(sqrt_price_x_96)?;
+            }
+
+            if let Some(trace) = trace.as_deref_mut() {
+                // the word searched by nextInitializedTickWithinOneWord, which starts one tick to the right when
+                // searching rightward
+                let compressed = tick_start.div_euclid(self.config.tick_spacing);
+                let (word_position, _) = position(if zero_for_one {
+                    compressed
+                } else {
+                    compressed + 1
+                });
+                trace.steps.push(SwapStepTrace {
+                    word_position,
+                    tick_next,
+                    initialized,
+                    sqrt_price_start_x_96,
+                    sqrt_price_target_x_96,
+                    liquidity: liquidity_start,
+                    amount_remaining: amount_specified_remaining_start,
+                    sqrt_price_next_x_96: sqrt_price_x_96,
+                    amount_in: step.amount_in,
+                    amount_out: step.amount_out,
+                    fee_amount: step_fee_amount,
+                    protocol_fee: delta,
+                    gauge_fee: gauge_fee_amount,
+                    fee_growth_global_before_x_128: fee_growth_global_start_step_x_128,
+                    fee_growth_global_after_x_128: fee_growth_global_x_128,
+                    tick_crossed: (initialized && sqrt_price_x_96 == sqrt_price_next_x_96)
+                        .then_some(tick_next),
+                    liquidity_net: liquidity_net_applied,
+                    liquidity_after: liquidity,
+                    tick_after: tick,
+                });
+            }
+        }
+
+        self.slot0.sqrt_price_x_96 = sqrt_price_x_96;
+        self.slot0.tick = tick;
+        self.liquidity = liquidity;
+        self.staked_liquidity = staked_liquidity;
+
+        // update fee growth global and, if necessary, protocol and gauge fees
+        if zero_for_one {
+            self.fee_growth_global_0_x_128 = fee_growth_global_x_128;
+            self.protocol_fees_0 += protocol_fee;
+            self.gauge_fees_0 += gauge_fee;
+        } else {
+            self.fee_growth_global_1_x_128 = fee_growth_global_x_128;
+            self.protocol_fees_1 += protocol_fee;
+            self.gauge_fees_1 += gauge_fee;
+        }
+
+        let (amount_0, amount_1) = if zero_for_one == exact_input {
+            (
+                amount_specified - amount_specified_remaining,
+                amount_calculated,
+            )
+        } else {
+            (
+                amount_calculated,
+                amount_specified - amount_specified_remaining,
+            )
+        };
+
+        let fee_growth_global_delta_x_128 =
+            fee_growth_global_x_128.wrapping_sub(fee_growth_global_start_x_128);
+        let fees = if zero_for_one {
+            SwapFees {
+                lp_fee_0: lp_fee,
+                protocol_fee_0: protocol_fee,
+                gauge_fee_0: gauge_fee,
+                fee_growth_global_0_delta_x_128: fee_growth_global_delta_x_128,
+                ..Default::default()
+            }
+        } else {
+            SwapFees {
+                lp_fee_1: lp_fee,
+                protocol_fee_1: protocol_fee,
+                gauge_fee_1: gauge_fee,
+                fee_growth_global_1_delta_x_128: fee_growth_global_delta_x_128,
+                ..Default::default()
+            }
+        };
+
+        Ok(SwapResult {
+            amount_0,
+            amount_1,
+            fees,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_utils::SQRT_PRICE_1_1, v4};
+
+    fn new_pool(dialect: Dialect, fee: u32) -> Pool {
+        let config = PoolConfig::new(dialect, fee).unwrap();
+        let spacing = config.tick_spacing;
+        let mut pool = Pool::initialize(config, SQRT_PRICE_1_1).unwrap();
+        pool.mint(Address::ZERO, -2 * spacing, 2 * spacing, 1e18 as u128)
+            .unwrap();
+        pool
+    }
+
+    fn swap_exact_in(pool: &mut Pool, amount: i128, zero_for_one: bool) -> SwapResult {
+        let limit = if zero_for_one {
+            MIN_SQRT_RATIO + U256::from(1)
+        } else {
+            MAX_SQRT_RATIO - U256::from(1)
+        };
+        pool.swap(zero_for_one, I256::try_from(amount).unwrap(), limit)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_mint_and_burn() {
+        let mut pool = new_pool(Dialect::UniswapV3, 3000);
+        assert_eq!(pool.liquidity, 1e18 as u128);
+
+        let (amount_0, amount_1) = pool.burn(Address::ZERO, -120, 120, 1e18 as u128).unwrap();
+        assert_eq!(amount_0, U256::from(5981737760509662u64));
+        assert_eq!(amount_1, U256::from(5981737760509662u64));
+        assert_eq!(pool.liquidity, 0);
+        assert!(pool.ticks.is_empty());
+
+        let (collected_0, collected_1) =
+            pool.collect(Address::ZERO, -120, 120, u128::MAX, u128::MAX);
+        assert_eq!(collected_0, 5981737760509662);
+        assert_eq!(collected_1, 5981737760509662);
+
+        assert!(matches!(
+            pool.mint(Address::ZERO, -120, 120, 0),
+            Err(UniswapV3MathError::LiquidityIsZero)
+        ));
+
+        // failed changes leave the pool as it was
+        pool.mint(Address::ZERO, -600, 600, 1000).unwrap();
+        let state = |pool: &Pool| {
+            (
+                pool.ticks.clone(),
+                pool.tick_bitmap.clone(),
+                pool.positions.clone(),
+                pool.liquidity,
+            )
+        };
+        let before = state(&pool);
+
+        assert!(matches!(
+            pool.mint(Address::ZERO, -121, 120, 1),
+            Err(UniswapV3MathError::TickSpacingError)
+        ));
+        assert!(matches!(
+            pool.mint(Address::ZERO, -600, 121, 1),
+            Err(UniswapV3MathError::TickSpacingError)
+        ));
+        assert_eq!(state(&pool), before);
+
+        // burning a position of another owner on the same ticks
+        assert!(matches!(
+            pool.burn(Address::repeat_byte(1), -600, 600, 400),
+            Err(UniswapV3MathError::LiquiditySub)
+        ));
+        assert!(matches!(
+            pool.burn(Address::ZERO, -600, 600, 1001),
+            Err(UniswapV3MathError::LiquiditySub)
+        ));
+        assert_eq!(state(&pool), before);
+
+        // a tick spacing that is not positive is rejected rather than divided by
+        pool.config.tick_spacing = 0;
+        assert!(matches!(
+            pool.mint(Address::ZERO, -600, 600, 1),
+            Err(UniswapV3MathError::TickSpacingOutOfBounds(0))
+        ));
+        assert_eq!(state(&pool), before);
+    }
+
+    #[test]
+    fn test_swap_matches_v4_pool() {
+        let mut pool = new_pool(Dialect::UniswapV3, 3000);
+        let mut v4_pool = v4::pool::Pool::initialize(SQRT_PRICE_1_1, 3000).unwrap();
+        v4_pool
+            .modify_liquidity(&v4::pool::ModifyLiquidityParams {
+                tick_lower: -120,
+                tick_upper: 120,
+                liquidity_delta: 1e18 as i128,
+                tick_spacing: 60,
+                ..Default::default()
+            })
+            .unwrap();
+
+        for (amount, zero_for_one) in [
+            (1e15 as i128, true),
+            (3e15 as i128, false),
+            (1e18 as i128, true),
+        ] {
+            let result = swap_exact_in(&mut pool, amount, zero_for_one);
+            let v4_result = v4_pool
+                .swap(&v4::pool::SwapParams {
+                    amount_specified: I256::try_from(-amount).unwrap(),
+                    tick_spacing: 60,
+                    zero_for_one,
+                    sqrt_price_limit_x_96: if zero_for_one {
+                        MIN_SQRT_RATIO + U256::from(1)
+                    } else {
+                        MAX_SQRT_RATIO - U256::from(1)
+                    },
+                    lp_fee_override: 0,
+                })
+                .unwrap();
+
+            // v4 deltas are from the caller's perspective
+            assert_eq!(
+                result.amount_0,
+                -I256::try_from(v4_result.delta.amount_0).unwrap()
+            );
+            assert_eq!(
+                result.amount_1,
+                -I256::try_from(v4_result.delta.amount_1).unwrap()
+            );
+            assert_eq!(pool.slot0.sqrt_price_x_96, v4_pool.slot0.sqrt_price_x_96);
+            assert_eq!(pool.slot0.tick, v4_pool.slot0.tick);
+            assert_eq!(pool.liquidity, v4_pool.liquidity);
+        }
+    }
+
+    #[test]
+    fn test_swap_exact_output() {
+        let mut pool = new_pool(Dialect::SushiSwapV3, 3000);
+
+        let result = pool
+            .swap(
+                true,
+                I256::try_from(-1e15 as i128).unwrap(),
+                MIN_SQRT_RATIO + U256::from(1),
+            )
+            .unwrap();
+        assert_eq!(result.amount_1, I256::try_from(-1e15 as i128).unwrap());
+        assert!(result.amount_0 > I256::try_from(1e15 as i128).unwrap());
+
+        assert!(matches!(
+            pool.swap(false, I256::ZERO, MAX_SQRT_RATIO - U256::from(1)),
+            Err(UniswapV3MathError::SwapAmountCannotBeZero)
+        ));
+        assert!(matches!(
+            pool.swap(true, I256::ONE, SQRT_PRICE_1_1),
+            Err(UniswapV3MathError::PriceLimitAlreadyExceeded(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_protocol_fee() {
+        // Uniswap takes 1/4 of the token0 swap fee
+        let mut pool = new_pool(Dialect::UniswapV3, 3000);
+        pool.set_fee_protocol(4 | (4 << 4)).unwrap();
+        let result = swap_exact_in(&mut pool, 1e15 as i128, true);
+        assert_eq!(result.fees.protocol_fee_0, U256::from(750000000000u64));
+        assert_eq!(pool.protocol_fees_0, result.fees.protocol_fee_0);
+
+        // PancakeSwap takes 33% of the token1 swap fee
+        let mut pool = new_pool(Dialect::PancakeSwapV3, 2500);
+        pool.set_fee_protocol(3300 | (3300 << 16)).unwrap();
+        let result = swap_exact_in(&mut pool, 1e15 as i128, false);
+        assert_eq!(result.fees.protocol_fee_1, U256::from(825000000000u64));
+        assert_eq!(pool.protocol_fees_1, result.fees.protocol_fee_1);
+        assert_eq!(pool.protocol_fees_0, U256::ZERO);
+
+        assert!(pool.set_fee_protocol(4 | (4 << 4)).is_err());
+    }
+
+    #[test]
+    fn test_swap_fees() {
+        let mut pool = new_pool(Dialect::UniswapV3, 3000);
+        // 1/4 of token0 fees and 1/6 of token1 fees
+        pool.set_fee_protocol(4 | (6 << 4)).unwrap();
+
+        let result = swap_exact_in(&mut pool, 1e15 as i128, true);
+        let fees = result.fees;
+        assert_eq!(fees.total_fee_0(), U256::from(3e12 as u64));
+        assert_eq!(fees.protocol_fee_0, fees.total_fee_0() / U256::from(4));
+        assert_eq!(fees.lp_fee_0, U256::from(2.25e12 as u64));
+        assert_eq!(fees.total_fee_1(), U256::ZERO);
+        assert_eq!(
+            fees.fee_growth_global_0_delta_x_128,
+            pool.fee_growth_global_0_x_128
+        );
+        assert_eq!(fees.fee_growth_global_1_delta_x_128, U256::ZERO);
+
+        // liquidity providers earn the lp fee, less rounding dust
+        let growth_0 = mul_div(
+            fees.fee_growth_global_0_delta_x_128,
+            U256::from(pool.liquidity),
+            Q128,
+        )
+        .unwrap();
+        assert!(fees.lp_fee_0 - growth_0 <= U256::from(1));
+
+        let fee_growth_global_1_start_x_128 = pool.fee_growth_global_1_x_128;
+        let result = swap_exact_in(&mut pool, 1e15 as i128, false);
+        let fees = result.fees;
+        assert_eq!(fees.protocol_fee_1, fees.total_fee_1() / U256::from(6));
+        assert_eq!(fees.total_fee_0(), U256::ZERO);
+        assert_eq!(
+            fees.fee_growth_global_1_delta_x_128,
+            pool.fee_growth_global_1_x_128 - fee_growth_global_1_start_x_128
+        );
+        assert_eq!(pool.protocol_fees_1, fees.protocol_fee_1);
+    }
+
+    #[test]
+    fn test_staked_liquidity_excluded_from_fees() {
+        let config = PoolConfig::slipstream(100).unwrap();
+        let mut pool = Pool::initialize(config, SQRT_PRICE_1_1).unwrap();
+        pool.mint(Address::ZERO, -200, 200, 1e18 as u128).unwrap();
+        pool.mint(Address::ZERO, -100, 100, 1e18 as u128).unwrap();
+
+        // stake the narrower position, which leaves the range during the swap
+        pool.stake(-100, 100, 1e18 as i128).unwrap();
+        assert_eq!(pool.staked_liquidity, 1e18 as u128);
+
+        let result = swap_exact_in(&mut pool, 1e15 as i128, true);
+        // half of the 500 pip fee is staked, and the gauge takes 10% of the rest
+        assert_eq!(result.fees.gauge_fee_0, U256::from(275000000000u64));
+        assert_eq!(result.fees.protocol_fee_0, U256::ZERO);
+
+        // unstaked liquidity earns the remaining fees
+        pool.burn(Address::ZERO, -200, 200, 0).unwrap();
+        let (fees_0, _) = pool.collect(Address::ZERO, -200, 200, u128::MAX, u128::MAX);
+        assert!(fees_0.abs_diff(225000000000) <= 1);
+
+        // crossing out of the staked range removes the staked liquidity
+        swap_exact_in(&mut pool, 1e16 as i128, true);
+        assert!(pool.slot0.tick < -100);
+        assert_eq!(pool.staked_liquidity, 0);
+        assert_eq!(pool.liquidity, 1e18 as u128);
+
+        let mut pool = new_pool(Dialect::UniswapV3, 3000);
+        assert!(matches!(
+            pool.stake(-120, 120, 1),
+            Err(UniswapV3MathError::StakingNotSupported)
+        ));
+    }
+
+    #[test]
+    fn test_stake_limited_to_range_liquidity() {
+        let config = PoolConfig::slipstream(100).unwrap();
+        let mut pool = Pool::initialize(config, SQRT_PRICE_1_1).unwrap();
+        pool.mint(Address::ZERO, -600, 600, 1000).unwrap();
+        pool.mint(Address::repeat_byte(1), -600, 600, 500).unwrap();
+
+        // more than the positions on the range hold
+        assert!(matches!(
+            pool.stake(-600, 600, 5000),
+            Err(UniswapV3MathError::StakedLiquidityExceedsLiquidity)
+        ));
+        assert!(matches!(
+            pool.stake(-100, 100, 1),
+            Err(UniswapV3MathError::StakedLiquidityExceedsLiquidity)
+        ));
+        assert_eq!(pool.staked_liquidity, 0);
+        assert!(pool.staked_liquidity_net.is_empty());
+
+        pool.stake(-600, 600, 1200).unwrap();
+        assert_eq!(pool.staked_liquidity, 1200);
+        assert!(matches!(
+            pool.stake(-600, 600, 301),
+            Err(UniswapV3MathError::StakedLiquidityExceedsLiquidity)
+        ));
+        assert!(matches!(
+            pool.stake(-600, 600, -1201),
+            Err(UniswapV3MathError::LiquiditySub)
+        ));
+
+        // staked liquidity cannot be burned
+        assert!(matches!(
+            pool.burn(Address::ZERO, -600, 600, 400),
+            Err(UniswapV3MathError::StakedLiquidityExceedsLiquidity)
+        ));
+        pool.burn(Address::ZERO, -600, 600, 300).unwrap();
+
+        swap_exact_in(&mut pool, 1e3 as i128, true);
+
+        pool.stake(-600, 600, -1200).unwrap();
+        assert_eq!(pool.staked_liquidity, 0);
+        assert!(pool.staked_range_liquidity.is_empty());
+        pool.burn(Address::ZERO, -600, 600, 700).unwrap();
+    }
+
+    #[test]
+    fn test_failed_swap_restores_crossed_ticks() {
+        let mut pool = new_pool(Dialect::UniswapV3, 3000);
+        pool.mint(Address::ZERO, -6000, 6000, 1e18 as u128).unwrap();
+        // inconsistent staked liquidity makes the swap fail once it has crossed tick -120
+        pool.staked_liquidity_net.insert(-120, 1);
+        let ticks = pool.ticks.clone();
+        let slot0 = pool.slot0;
+
+        let result = pool.swap(true, I256::MAX, get_sqrt_ratio_at_tick(-600).unwrap());
+        assert!(matches!(result, Err(UniswapV3MathError::LiquiditySub)));
+        assert_eq!(pool.ticks, ticks);
+        assert_eq!(pool.slot0, slot0);
+    }
+
+    #[test]
+    fn test_swap_with_trace() {
+        for zero_for_one in [true, false] {
+            let mut pool = new_pool(Dialect::UniswapV3, 3000);
+            pool.mint(Address::ZERO, -600, 600, 1e18 as u128).unwrap();
+            pool.set_fee_protocol(4 + (4 << 4)).unwrap();
+            let mut traced = pool.clone();
+
+            // a swap leaving the narrow range crosses one of its ticks
+            let result = swap_exact_in(&mut pool, 2e16 as i128, zero_for_one);
+            let limit = if zero_for_one {
+                MIN_SQRT_RATIO + U256::from(1)
+            } else {
+                MAX_SQRT_RATIO - U256::from(1)
+            };
+            let (traced_result, trace) = traced
+                .swap_with_trace(zero_for_one, I256::try_from(2e16 as i128).unwrap(), limit)
+                .unwrap();
+            assert_eq!(traced_result, result);
+            assert_eq!(traced.slot0, pool.slot0);
+            assert_eq!(traced.liquidity, pool.liquidity);
+            assert_eq!(
+                traced.fee_growth_global_0_x_128,
+                pool.fee_growth_global_0_x_128
+            );
+            assert_eq!(
+                traced.fee_growth_global_1_x_128,
+                pool.fee_growth_global_1_x_128
+            );
+
+            let crossed = if zero_for_one { -120 } else { 120 };
+            assert_eq!(trace.ticks_crossed(), vec![crossed]);
+
+            // the steps chain into each other and add up to the swap
+            let first = trace.steps.first().unwrap();
+            let last = trace.steps.last().unwrap();
+            assert_eq!(first.sqrt_price_start_x_96, SQRT_PRICE_1_1);
+            assert_eq!(first.liquidity, 2e18 as u128);
+            assert_eq!(last.sqrt_price_next_x_96, pool.slot0.sqrt_price_x_96);
+            assert_eq!(last.liquidity_after, 1e18 as u128);
+            for pair in trace.steps.windows(2) {
+                assert_eq!(pair[0].sqrt_price_next_x_96, pair[1].sqrt_price_start_x_96);
+                assert_eq!(pair[0].liquidity_after, pair[1].liquidity);
+                assert_eq!(
+                    pair[0].fee_growth_global_after_x_128,
+                    pair[1].fee_growth_global_before_x_128
+                );
+            }
+
+            let sum = |field: fn(&SwapStepTrace) -> U256| -> U256 {
+                trace.steps.iter().map(field).fold(U256::ZERO, |a, b| a + b)
+            };
+            let (amount_in, amount_out) = if zero_for_one {
+                (result.amount_0, result.amount_1)
+            } else {
+                (result.amount_1, result.amount_0)
+            };
+            assert_eq!(
+                sum(|step| step.amount_in + step.fee_amount),
+                amount_in.into_raw()
+            );
+            assert_eq!(sum(|step| step.amount_out), (-amount_out).into_raw());
+            assert_eq!(
+                sum(|step| step.protocol_fee),
+                result.fees.protocol_fee_0 + result.fees.protocol_fee_1
+            );
+        }
+    }
+}
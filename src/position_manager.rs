@@ -0,0 +1,489 @@
+use std::collections::HashMap;
+
+use alloy_primitives::{Address, U256};
+
+use crate::{
+    error::UniswapV3MathError,
+    full_math::mul_div,
+    liquidity_amounts::get_liquidity_for_amounts,
+    pool::{Pool, PositionKey},
+    tick_math::get_sqrt_ratio_at_tick,
+};
+
+const Q128: U256 = U256::from_limbs([0, 0, 1, 0]);
+
+//A position NFT. Positions of the same range share a single pool position owned by the manager, the fees of each
+//token are tracked with its own fee growth snapshot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ManagedPosition {
+    pub owner: Address,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside_0_last_x_128: U256,
+    pub fee_growth_inside_1_last_x_128: U256,
+    pub tokens_owed_0: u128,
+    pub tokens_owed_1: u128,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MintParams {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub amount_0_desired: U256,
+    pub amount_1_desired: U256,
+    pub amount_0_min: U256,
+    pub amount_1_min: U256,
+    pub recipient: Address,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IncreaseLiquidityParams {
+    pub token_id: u64,
+    pub amount_0_desired: U256,
+    pub amount_1_desired: U256,
+    pub amount_0_min: U256,
+    pub amount_1_min: U256,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecreaseLiquidityParams {
+    pub token_id: u64,
+    pub liquidity: u128,
+    pub amount_0_min: U256,
+    pub amount_1_min: U256,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CollectParams {
+    pub token_id: u64,
+    pub amount_0_max: u128,
+    pub amount_1_max: u128,
+}
+
+//NonfungiblePositionManager over a single in-memory pool
+#[derive(Debug, Clone)]
+pub struct PositionManager {
+    //The owner of the pool positions
+    pub address: Address,
+    pub pool: Pool,
+    pub next_id: u64,
+    pub positions: HashMap<u64, ManagedPosition>,
+}
+
+impl PositionManager {
+    pub fn new(address: Address, pool: Pool) -> PositionManager {
+        PositionManager {
+            address,
+            pool,
+            next_id: 1,
+            positions: HashMap::new(),
+        }
+    }
+
+    fn position(&self, token_id: u64) -> Result<ManagedPosition, UniswapV3MathError> {
+        self.positions
+            .get(&token_id)
+            .copied()
+            .ok_or(UniswapV3MathError::InvalidTokenId(token_id))
+    }
+
+    fn pool_fee_growth_inside_last(&self, tick_lower: i32, tick_upper: i32) -> (U256, U256) {
+        let position = self
+            .pool
+            .positions
+            .get(&PositionKey {
+                owner: self.address,
+                tick_lower,
+                tick_upper,
+            })
+            .copied()
+            .unwrap_or_default();
+        (
+            position.fee_growth_inside_0_last_x_128,
+            position.fee_growth_inside_1_last_x_128,
+        )
+    }
+
+    //Credits the fees earned since the position's last snapshot to its tokens owed, from the pool position's fee
+    //growth which must have just been updated
+    fn accrue_fees(&self, position: &mut ManagedPosition) -> Result<(), UniswapV3MathError> {
+        let (fee_growth_inside_0_last_x_128, fee_growth_inside_1_last_x_128) =
+            self.pool_fee_growth_inside_last(position.tick_lower, position.tick_upper);
+
+        let tokens_owed_0: u128 = mul_div(
+            fee_growth_inside_0_last_x_128.wrapping_sub(position.fee_growth_inside_0_last_x_128),
+            U256::from(position.liquidity),
+            Q128,
+        )?
+        .wrapping_to();
+        let tokens_owed_1: u128 = mul_div(
+            fee_growth_inside_1_last_x_128.wrapping_sub(position.fee_growth_inside_1_last_x_128),
+            U256::from(position.liquidity),
+            Q128,
+        )?
+        .wrapping_to();
+
+        position.tokens_owed_0 = position.tokens_owed_0.wrapping_add(tokens_owed_0);
+        position.tokens_owed_1 = position.tokens_owed_1.wrapping_add(tokens_owed_1);
+        position.fee_growth_inside_0_last_x_128 = fee_growth_inside_0_last_x_128;
+        position.fee_growth_inside_1_last_x_128 = fee_growth_inside_1_last_x_128;
+
+        Ok(())
+    }
+
+    //Adds the most liquidity the desired amounts allow at the current price
+    // returns (uint128 liquidity, uint256 amount0, uint256 amount1)
+    fn add_liquidity(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount_0_desired: U256,
+        amount_1_desired: U256,
+        amount_0_min: U256,
+        amount_1_min: U256,
+    ) -> Result<(u128, U256, U256), UniswapV3MathError> {
+        let liquidity = get_liquidity_for_amounts(
+            self.pool.slot0.sqrt_price_x_96,
+            get_sqrt_ratio_at_tick(tick_lower)?,
+            get_sqrt_ratio_at_tick(tick_upper)?,
+            amount_0_desired,
+            amount_1_desired,
+        )?;
+
+        // mint on a copy of the pool so that a failed slippage check leaves it untouched, as a revert would
+        let mut pool = self.pool.clone();
+        let (amount_0, amount_1) = pool.mint(self.address, tick_lower, tick_upper, liquidity)?;
+
+        if amount_0 < amount_0_min || amount_1 < amount_1_min {
+            return Err(UniswapV3MathError::PriceSlippageCheck);
+        }
+        self.pool = pool;
+
+        Ok((liquidity, amount_0, amount_1))
+    }
+
+    //Creates a new position NFT
+    // returns (uint256 tokenId, uint128 liquidity, uint256 amount0, uint256 amount1)
+    pub fn mint(
+        &mut self,
+        params: MintParams,
+    ) -> Result<(u64, u128, U256, U256), UniswapV3MathError> {
+        let (liquidity, amount_0, amount_1) = self.add_liquidity(
+            params.tick_lower,
+            params.tick_upper,
+            params.amount_0_desired,
+            params.amount_1_desired,
+            params.amount_0_min,
+            params.amount_1_min,
+        )?;
+
+        let token_id = self.next_id;
+        self.next_id += 1;
+
+        let (fee_growth_inside_0_last_x_128, fee_growth_inside_1_last_x_128) =
+            self.pool_fee_growth_inside_last(params.tick_lower, params.tick_upper);
+        self.positions.insert(
+            token_id,
+            ManagedPosition {
+                owner: params.recipient,
+                tick_lower: params.tick_lower,
+                tick_upper: params.tick_upper,
+                liquidity,
+                fee_growth_inside_0_last_x_128,
+                fee_growth_inside_1_last_x_128,
+                tokens_owed_0: 0,
+                tokens_owed_1: 0,
+            },
+        );
+
+        Ok((token_id, liquidity, amount_0, amount_1))
+    }
+
+    //Increases the liquidity of a position, with the tokens paid by the caller
+    // returns (uint128 liquidity, uint256 amount0, uint256 amount1)
+    pub fn increase_liquidity(
+        &mut self,
+        params: IncreaseLiquidityParams,
+    ) -> Result<(u128, U256, U256), UniswapV3MathError> {
+        let mut position = self.position(params.token_id)?;
+
+        let (liquidity, amount_0, amount_1) = self.add_liquidity(
+            position.tick_lower,
+            position.tick_upper,
+            params.amount_0_desired,
+            params.amount_1_desired,
+            params.amount_0_min,
+            params.amount_1_min,
+        )?;
+
+        self.accrue_fees(&mut position)?;
+        position.liquidity += liquidity;
+        self.positions.insert(params.token_id, position);
+
+        Ok((liquidity, amount_0, amount_1))
+    }
+
+    //Decreases the liquidity of a position and credits the withdrawn tokens to its tokens owed
+    // returns (uint256 amount0, uint256 amount1)
+    pub fn decrease_liquidity(
+        &mut self,
+        params: DecreaseLiquidityParams,
+    ) -> Result<(U256, U256), UniswapV3MathError> {
+        if params.liquidity == 0 {
+            return Err(UniswapV3MathError::LiquidityIsZero);
+        }
+        let mut position = self.position(params.token_id)?;
+        if position.liquidity < params.liquidity {
+            return Err(UniswapV3MathError::LiquiditySub);
+        }
+
+        let mut pool = self.pool.clone();
+        let (amount_0, amount_1) = pool.burn(
+            self.address,
+            position.tick_lower,
+            position.tick_upper,
+            params.liquidity,
+        )?;
+
+        if amount_0 < params.amount_0_min || amount_1 < params.amount_1_min {
+            return Err(UniswapV3MathError::PriceSlippageCheck);
+        }
+        self.pool = pool;
+
+        self.accrue_fees(&mut position)?;
+        position.tokens_owed_0 = position.tokens_owed_0.wrapping_add(amount_0.wrapping_to());
+        position.tokens_owed_1 = position.tokens_owed_1.wrapping_add(amount_1.wrapping_to());
+        // subtraction is safe because we checked position.liquidity is gte params.liquidity
+        position.liquidity -= params.liquidity;
+        self.positions.insert(params.token_id, position);
+
+        Ok((amount_0, amount_1))
+    }
+
+    //Collects up to a maximum amount of fees and withdrawn tokens owed to a position
+    // returns (uint256 amount0, uint256 amount1)
+    pub fn collect(&mut self, params: CollectParams) -> Result<(u128, u128), UniswapV3MathError> {
+        if params.amount_0_max == 0 && params.amount_1_max == 0 {
+            return Err(UniswapV3MathError::CollectAmountsAreZero);
+        }
+        let mut position = self.position(params.token_id)?;
+
+        // trigger an update of the position fees owed and fee growth snapshots if it has any liquidity
+        if position.liquidity > 0 {
+            self.pool
+                .burn(self.address, position.tick_lower, position.tick_upper, 0)?;
+            self.accrue_fees(&mut position)?;
+        }
+
+        // compute the arguments to give to the pool#collect method
+        let amount_0_collect = params.amount_0_max.min(position.tokens_owed_0);
+        let amount_1_collect = params.amount_1_max.min(position.tokens_owed_1);
+
+        // the actual amounts collected are returned
+        let (amount_0, amount_1) = self.pool.collect(
+            self.address,
+            position.tick_lower,
+            position.tick_upper,
+            amount_0_collect,
+            amount_1_collect,
+        );
+
+        // sometimes there will be a few less wei than expected due to rounding down in core, but we just subtract the
+        // full amount expected
+        position.tokens_owed_0 -= amount_0_collect;
+        position.tokens_owed_1 -= amount_1_collect;
+        self.positions.insert(params.token_id, position);
+
+        Ok((amount_0, amount_1))
+    }
+
+    //Burns a position NFT, which must have no liquidity and nothing left to collect
+    pub fn burn(&mut self, token_id: u64) -> Result<(), UniswapV3MathError> {
+        let position = self.position(token_id)?;
+        if position.liquidity != 0 || position.tokens_owed_0 != 0 || position.tokens_owed_1 != 0 {
+            return Err(UniswapV3MathError::PositionNotCleared);
+        }
+
+        self.positions.remove(&token_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test_utils::new_pool,
+        tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO},
+    };
+    use alloy_primitives::I256;
+
+    const MANAGER: Address = Address::repeat_byte(0xAA);
+    const ALICE: Address = Address::repeat_byte(1);
+    const BOB: Address = Address::repeat_byte(2);
+
+    fn new_manager() -> PositionManager {
+        PositionManager::new(MANAGER, new_pool(Address::ZERO, &[]))
+    }
+
+    fn mint_params(recipient: Address, amount: u64) -> MintParams {
+        MintParams {
+            tick_lower: -120,
+            tick_upper: 120,
+            amount_0_desired: U256::from(amount),
+            amount_1_desired: U256::from(amount),
+            recipient,
+            ..Default::default()
+        }
+    }
+
+    fn swap_both_ways(manager: &mut PositionManager, amount: i128) {
+        manager
+            .pool
+            .swap(
+                true,
+                I256::try_from(amount).unwrap(),
+                MIN_SQRT_RATIO + U256::from(1),
+            )
+            .unwrap();
+        manager
+            .pool
+            .swap(
+                false,
+                I256::try_from(amount).unwrap(),
+                MAX_SQRT_RATIO - U256::from(1),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_mint() {
+        let mut manager = new_manager();
+        let (token_id, liquidity, amount_0, amount_1) =
+            manager.mint(mint_params(ALICE, 1e18 as u64)).unwrap();
+        assert_eq!(token_id, 1);
+        assert!(amount_0 <= U256::from(1e18 as u64) && amount_1 <= U256::from(1e18 as u64));
+        assert_eq!(manager.pool.liquidity, liquidity);
+        assert_eq!(manager.positions[&token_id].owner, ALICE);
+
+        // slippage is checked against the amounts actually paid
+        let result = manager.mint(MintParams {
+            amount_0_min: U256::from(1e18 as u64 + 1),
+            ..mint_params(BOB, 1e18 as u64)
+        });
+        assert_eq!(result.unwrap_err().to_string(), "Price slippage check");
+        assert_eq!(manager.next_id, 2);
+        assert_eq!(manager.pool.liquidity, liquidity);
+    }
+
+    #[test]
+    fn test_fees_are_split_between_positions_of_the_same_range() {
+        let mut manager = new_manager();
+        let (alice, ..) = manager.mint(mint_params(ALICE, 1e18 as u64)).unwrap();
+        let (bob, ..) = manager.mint(mint_params(BOB, 3e18 as u64)).unwrap();
+        assert_eq!(manager.pool.positions.len(), 1);
+
+        swap_both_ways(&mut manager, 1e17 as i128);
+
+        let collect_all = |token_id| CollectParams {
+            token_id,
+            amount_0_max: u128::MAX,
+            amount_1_max: u128::MAX,
+        };
+        let (alice_0, alice_1) = manager.collect(collect_all(alice)).unwrap();
+        let (bob_0, bob_1) = manager.collect(collect_all(bob)).unwrap();
+
+        // 0.3% of 1e17 of each token, a quarter to alice and three quarters to bob
+        assert!(alice_0.abs_diff(75e12 as u128) <= 1 && alice_1.abs_diff(75e12 as u128) <= 1);
+        assert!(bob_0.abs_diff(225e12 as u128) <= 1 && bob_1.abs_diff(225e12 as u128) <= 1);
+
+        // only the rounding dust of splitting the fees is left in the pool position
+        let pool_position = manager.pool.positions.values().next().unwrap();
+        assert!(pool_position.tokens_owed_0 <= 1 && pool_position.tokens_owed_1 <= 1);
+    }
+
+    #[test]
+    fn test_rebalance() {
+        let mut manager = new_manager();
+        let (token_id, liquidity, amount_0, amount_1) =
+            manager.mint(mint_params(ALICE, 1e18 as u64)).unwrap();
+
+        swap_both_ways(&mut manager, 1e17 as i128);
+
+        // fees accrued before an increase are kept
+        let (added, ..) = manager
+            .increase_liquidity(IncreaseLiquidityParams {
+                token_id,
+                amount_0_desired: amount_0,
+                amount_1_desired: amount_1,
+                ..Default::default()
+            })
+            .unwrap();
+        let position = manager.positions[&token_id];
+        assert_eq!(position.liquidity, liquidity + added);
+        assert!(position.tokens_owed_0 > 0 && position.tokens_owed_1 > 0);
+        let (fees_0, fees_1) = (position.tokens_owed_0, position.tokens_owed_1);
+
+        // withdraw everything into tokens owed
+        let result = manager.decrease_liquidity(DecreaseLiquidityParams {
+            token_id,
+            liquidity: position.liquidity + 1,
+            ..Default::default()
+        });
+        assert!(result.is_err());
+        let (withdrawn_0, withdrawn_1) = manager
+            .decrease_liquidity(DecreaseLiquidityParams {
+                token_id,
+                liquidity: position.liquidity,
+                ..Default::default()
+            })
+            .unwrap();
+        let position = manager.positions[&token_id];
+        assert_eq!(position.liquidity, 0);
+        assert_eq!(
+            U256::from(position.tokens_owed_0),
+            withdrawn_0 + U256::from(fees_0)
+        );
+        assert_eq!(
+            U256::from(position.tokens_owed_1),
+            withdrawn_1 + U256::from(fees_1)
+        );
+
+        assert_eq!(
+            manager.burn(token_id).unwrap_err().to_string(),
+            "Not cleared"
+        );
+
+        // collect in two steps
+        let (collected_0, _) = manager
+            .collect(CollectParams {
+                token_id,
+                amount_0_max: 1,
+                amount_1_max: 0,
+            })
+            .unwrap();
+        assert_eq!(collected_0, 1);
+        manager
+            .collect(CollectParams {
+                token_id,
+                amount_0_max: u128::MAX,
+                amount_1_max: u128::MAX,
+            })
+            .unwrap();
+
+        manager.burn(token_id).unwrap();
+        assert!(manager.positions.is_empty());
+        assert_eq!(
+            manager
+                .collect(CollectParams {
+                    token_id,
+                    amount_0_max: 1,
+                    amount_1_max: 1,
+                })
+                .unwrap_err()
+                .to_string(),
+            "Invalid token ID 1"
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use alloy_primitives::{I256, U256};
+
+use crate::{
+    error::UniswapV3MathError,
+    full_math::mul_div,
+    sqrt_price_math::{get_amount_0_delta, get_amount_1_delta},
+    tick::check_ticks,
+    tick_math::get_sqrt_ratio_at_tick,
+};
+
+const Q96: U256 = U256::from_limbs([0, 4294967296, 0, 0]);
+const WAD: U256 = U256::from_limbs([1_000_000_000_000_000_000, 0, 0, 0]);
+
+//The value of a position at two prices, compared to holding the tokens it held at the first price
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImpermanentLoss {
+    //The position's token amounts at the first and second price
+    pub amounts_a: (U256, U256),
+    pub amounts_b: (U256, U256),
+    //Values at the second price, in token1
+    pub hodl_value: U256,
+    pub lp_value: U256,
+    pub fee_value: U256,
+    //(lp_value + fee_value) / hodl_value - 1 as a WAD, negative for a loss
+    pub impermanent_loss_wad: I256,
+}
+
+//Returns the token amounts of a position at a sqrt price, rounded down as burning the position would
+// returns (uint256 amount0, uint256 amount1)
+pub fn position_amounts(
+    sqrt_price_x_96: U256,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+) -> Result<(U256, U256), UniswapV3MathError> {
+    check_ticks(tick_lower, tick_upper)?;
+
+    let liquidity_delta =
+        -i128::try_from(liquidity).map_err(|_| UniswapV3MathError::SafeCastToI128Overflow)?;
+    let sqrt_price_lower_x_96 = get_sqrt_ratio_at_tick(tick_lower)?;
+    let sqrt_price_upper_x_96 = get_sqrt_ratio_at_tick(tick_upper)?;
+    let sqrt_price_x_96 = sqrt_price_x_96.clamp(sqrt_price_lower_x_96, sqrt_price_upper_x_96);
+
+    let amount_0 = get_amount_0_delta(sqrt_price_x_96, sqrt_price_upper_x_96, liquidity_delta)?;
+    let amount_1 = get_amount_1_delta(sqrt_price_lower_x_96, sqrt_price_x_96, liquidity_delta)?;
+
+    Ok((amount_0.unsigned_abs(), amount_1.unsigned_abs()))
+}
+
+//Returns the value in token1 of amounts of token0 and token1 at a sqrt price, price = sqrtPriceX96^2 / 2^192
+pub fn value_in_token_1(
+    sqrt_price_x_96: U256,
+    amount_0: U256,
+    amount_1: U256,
+) -> Result<U256, UniswapV3MathError> {
+    let amount_0_in_token_1 = mul_div(
+        mul_div(amount_0, sqrt_price_x_96, Q96)?,
+        sqrt_price_x_96,
+        Q96,
+    )?;
+    Ok(amount_0_in_token_1 + amount_1)
+}
+
+//Returns the position value and impermanent loss when the price moves from sqrt_price_a to sqrt_price_b, optionally
+//counting the fees earned meanwhile as part of the LP value
+pub fn impermanent_loss(
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+    sqrt_price_a_x_96: U256,
+    sqrt_price_b_x_96: U256,
+    fees: Option<(U256, U256)>,
+) -> Result<ImpermanentLoss, UniswapV3MathError> {
+    let amounts_a = position_amounts(sqrt_price_a_x_96, tick_lower, tick_upper, liquidity)?;
+    let amounts_b = position_amounts(sqrt_price_b_x_96, tick_lower, tick_upper, liquidity)?;
+
+    let hodl_value = value_in_token_1(sqrt_price_b_x_96, amounts_a.0, amounts_a.1)?;
+    let lp_value = value_in_token_1(sqrt_price_b_x_96, amounts_b.0, amounts_b.1)?;
+    let fee_value = match fees {
+        Some((fees_0, fees_1)) => value_in_token_1(sqrt_price_b_x_96, fees_0, fees_1)?,
+        None => U256::ZERO,
+    };
+
+    let impermanent_loss_wad = if hodl_value.is_zero() {
+        I256::ZERO
+    } else {
+        let ratio_wad = mul_div(lp_value + fee_value, WAD, hodl_value)?;
+        I256::from_raw(ratio_wad) - I256::from_raw(WAD)
+    };
+
+    Ok(ImpermanentLoss {
+        amounts_a,
+        amounts_b,
+        hodl_value,
+        lp_value,
+        fee_value,
+        impermanent_loss_wad,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test_utils::SQRT_PRICE_1_1,
+        tick_math::{MAX_TICK, MIN_TICK},
+    };
+
+    #[test]
+    fn test_position_amounts() {
+        // the same amounts as burning the position
+        let (amount_0, amount_1) =
+            position_amounts(SQRT_PRICE_1_1, -120, 120, 1e18 as u128).unwrap();
+        assert_eq!(amount_0, U256::from(5981737760509662u64));
+        assert_eq!(amount_1, U256::from(5981737760509662u64));
+
+        // all token0 below the range and all token1 above it
+        let below = get_sqrt_ratio_at_tick(-240).unwrap();
+        let above = get_sqrt_ratio_at_tick(240).unwrap();
+        let (amount_0, amount_1) = position_amounts(below, -120, 120, 1e18 as u128).unwrap();
+        assert!(!amount_0.is_zero() && amount_1.is_zero());
+        let (amount_0, amount_1) = position_amounts(above, -120, 120, 1e18 as u128).unwrap();
+        assert!(amount_0.is_zero() && !amount_1.is_zero());
+
+        assert!(position_amounts(SQRT_PRICE_1_1, 120, -120, 1).is_err());
+    }
+
+    #[test]
+    fn test_full_range_impermanent_loss() {
+        // a full range position loses 2 * sqrt(r) / (1 + r) - 1 when the price moves by a factor r
+        let sqrt_price_b = get_sqrt_ratio_at_tick(13863).unwrap(); // ~4x
+        let il = impermanent_loss(
+            MIN_TICK,
+            MAX_TICK,
+            1e18 as u128,
+            SQRT_PRICE_1_1,
+            sqrt_price_b,
+            None,
+        )
+        .unwrap();
+
+        let r = (sqrt_price_b.to::<u128>() as f64 / 2f64.powi(96)).powi(2);
+        let expected = 2.0 * r.sqrt() / (1.0 + r) - 1.0;
+        let actual = il.impermanent_loss_wad.as_i64() as f64 / 1e18;
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+        assert!(il.lp_value < il.hodl_value);
+
+        // without a price move there is no loss
+        let il = impermanent_loss(
+            -120,
+            120,
+            1e18 as u128,
+            SQRT_PRICE_1_1,
+            SQRT_PRICE_1_1,
+            None,
+        )
+        .unwrap();
+        assert_eq!(il.amounts_a, il.amounts_b);
+        assert_eq!(il.impermanent_loss_wad, I256::ZERO);
+    }
+
+    #[test]
+    fn test_impermanent_loss_with_fees() {
+        let sqrt_price_b = get_sqrt_ratio_at_tick(-60).unwrap();
+        let without_fees =
+            impermanent_loss(-120, 120, 1e18 as u128, SQRT_PRICE_1_1, sqrt_price_b, None).unwrap();
+        assert!(without_fees.impermanent_loss_wad < I256::ZERO);
+
+        // fees worth the loss make up for it
+        let loss = without_fees.hodl_value - without_fees.lp_value;
+        let with_fees = impermanent_loss(
+            -120,
+            120,
+            1e18 as u128,
+            SQRT_PRICE_1_1,
+            sqrt_price_b,
+            Some((U256::ZERO, loss)),
+        )
+        .unwrap();
+        assert_eq!(with_fees.fee_value, loss);
+        assert_eq!(with_fees.impermanent_loss_wad, I256::ZERO);
+
+        // fees in token0 are valued at the second price
+        let with_fees = impermanent_loss(
+            -120,
+            120,
+            1e18 as u128,
+            SQRT_PRICE_1_1,
+            SQRT_PRICE_1_1,
+            Some((U256::from(1000), U256::from(1000))),
+        )
+        .unwrap();
+        assert_eq!(with_fees.fee_value, U256::from(2000));
+        assert!(with_fees.impermanent_loss_wad > I256::ZERO);
+    }
+}
@@ -0,0 +1,320 @@
+use alloy_primitives::{I256, U256};
+
+use crate::{
+    error::UniswapV3MathError,
+    full_math::{mul_div, mul_div_rounding_up},
+    liquidity_math,
+    pool::Pool,
+    sqrt_price_math::{_get_amount_0_delta, _get_amount_1_delta},
+    swap_math::{compute_swap_step_with_kind, SwapKind},
+    tick_bitmap::next_initialized_tick_within_one_word,
+    tick_math::{
+        get_sqrt_ratio_at_tick, get_tick_at_sqrt_ratio, MAX_SQRT_RATIO, MAX_TICK, MIN_SQRT_RATIO,
+        MIN_TICK,
+    },
+    U256_1,
+};
+
+const Q96: U256 = U256::from_limbs([0, 4294967296, 0, 0]);
+const BPS_DENOMINATOR: u32 = 10_000;
+
+//The amounts a swap has to trade to move a pool to a price
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PriceImpactQuote {
+    pub zero_for_one: bool,
+    //The exact input, including the fee
+    pub amount_in: U256,
+    //The output received when swapping exactly amount_in
+    pub amount_out: U256,
+    //The part of amount_in paid as swap fee, including protocol and gauge fees
+    pub fee_amount: U256,
+    pub sqrt_price_x_96: U256,
+    pub tick: i32,
+    pub ticks_crossed: u32,
+}
+
+//Returns the sqrt price at which the price of token0 in token1 has moved by price_impact_bps basis points, down when
+//selling token0 and up when selling token1. The sqrt price is rounded towards the current price so that a swap to it
+//never exceeds the impact, and is clamped to the swappable range.
+pub fn sqrt_price_for_price_impact(
+    sqrt_price_x_96: U256,
+    zero_for_one: bool,
+    price_impact_bps: u32,
+) -> Result<U256, UniswapV3MathError> {
+    if zero_for_one {
+        if price_impact_bps >= BPS_DENOMINATOR {
+            return Ok(MIN_SQRT_RATIO + U256_1);
+        }
+
+        let ratio_x_192: U256 =
+            (U256::from(BPS_DENOMINATOR - price_impact_bps) << 192) / U256::from(BPS_DENOMINATOR);
+        let mut factor_x_96 = ratio_x_192.root(2);
+        if factor_x_96 * factor_x_96 < ratio_x_192 {
+            factor_x_96 += U256_1;
+        }
+
+        Ok(mul_div_rounding_up(sqrt_price_x_96, factor_x_96, Q96)?.max(MIN_SQRT_RATIO + U256_1))
+    } else {
+        let ratio_x_192: U256 = (U256::from(BPS_DENOMINATOR as u64 + price_impact_bps as u64)
+            << 192)
+            / U256::from(BPS_DENOMINATOR);
+        let factor_x_96 = ratio_x_192.root(2);
+
+        Ok(mul_div(sqrt_price_x_96, factor_x_96, Q96)?.min(MAX_SQRT_RATIO - U256_1))
+    }
+}
+
+//Returns the amounts needed to move the pool to a target sqrt price, walking every initialized tick in between.
+//Swapping amount_in exactly with the target as price limit ends at the target price, unless the pool has no liquidity
+//left before the target, in which case the swap stops where its input runs out.
+pub fn amounts_to_sqrt_price(
+    pool: &Pool,
+    sqrt_price_target_x_96: U256,
+) -> Result<PriceImpactQuote, UniswapV3MathError> {
+    if sqrt_price_target_x_96 <= MIN_SQRT_RATIO || sqrt_price_target_x_96 >= MAX_SQRT_RATIO {
+        return Err(UniswapV3MathError::PriceLimitOutOfBounds(
+            sqrt_price_target_x_96,
+        ));
+    }
+
+    let zero_for_one = sqrt_price_target_x_96 < pool.slot0.sqrt_price_x_96;
+    let mut quote = PriceImpactQuote {
+        zero_for_one,
+        sqrt_price_x_96: pool.slot0.sqrt_price_x_96,
+        tick: pool.slot0.tick,
+        ..Default::default()
+    };
+    let mut liquidity = pool.liquidity;
+
+    while quote.sqrt_price_x_96 != sqrt_price_target_x_96 {
+        let (tick_next, initialized) = next_initialized_tick_within_one_word(
+            &pool.tick_bitmap,
+            quote.tick,
+            pool.config.tick_spacing,
+            zero_for_one,
+        )?;
+        let tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+        let sqrt_price_next_x_96 = get_sqrt_ratio_at_tick(tick_next)?;
+
+        let sqrt_price_step_x_96 = if zero_for_one {
+            sqrt_price_next_x_96.max(sqrt_price_target_x_96)
+        } else {
+            sqrt_price_next_x_96.min(sqrt_price_target_x_96)
+        };
+
+        // the amounts to cross the whole range, rounded as the swap rounds them
+        let (amount_in, amount_out) = if zero_for_one {
+            (
+                _get_amount_0_delta(sqrt_price_step_x_96, quote.sqrt_price_x_96, liquidity, true)?,
+                _get_amount_1_delta(
+                    sqrt_price_step_x_96,
+                    quote.sqrt_price_x_96,
+                    liquidity,
+                    false,
+                )?,
+            )
+        } else {
+            (
+                _get_amount_1_delta(quote.sqrt_price_x_96, sqrt_price_step_x_96, liquidity, true)?,
+                _get_amount_0_delta(
+                    quote.sqrt_price_x_96,
+                    sqrt_price_step_x_96,
+                    liquidity,
+                    false,
+                )?,
+            )
+        };
+
+        // an unbounded exact input reaches the step target, which gives the fee charged on amount_in
+        let step = compute_swap_step_with_kind(
+            quote.sqrt_price_x_96,
+            sqrt_price_step_x_96,
+            liquidity,
+            I256::MAX.into_raw(),
+            SwapKind::ExactIn,
+            pool.config.fee,
+        )?;
+        quote.amount_in += amount_in + step.fee_amount;
+        quote.amount_out += amount_out;
+        quote.fee_amount += step.fee_amount;
+        quote.sqrt_price_x_96 = sqrt_price_step_x_96;
+
+        if sqrt_price_step_x_96 == sqrt_price_next_x_96 {
+            if initialized {
+                let mut liquidity_net = pool
+                    .ticks
+                    .get(&tick_next)
+                    .map(|tick| tick.liquidity_net)
+                    .unwrap_or_default();
+                if zero_for_one {
+                    liquidity_net = -liquidity_net;
+                }
+                liquidity = liquidity_math::add_delta(liquidity, liquidity_net)?;
+                quote.ticks_crossed += 1;
+            }
+
+            quote.tick = if zero_for_one {
+                tick_next - 1
+            } else {
+                tick_next
+            };
+        } else {
+            quote.tick = get_tick_at_sqrt_ratio(quote.sqrt_price_x_96)?;
+        }
+    }
+
+    Ok(quote)
+}
+
+//Returns the largest swap in a direction that moves the price by at most price_impact_bps basis points
+pub fn amounts_for_price_impact(
+    pool: &Pool,
+    zero_for_one: bool,
+    price_impact_bps: u32,
+) -> Result<PriceImpactQuote, UniswapV3MathError> {
+    let sqrt_price_target_x_96 =
+        sqrt_price_for_price_impact(pool.slot0.sqrt_price_x_96, zero_for_one, price_impact_bps)?;
+    let quote = amounts_to_sqrt_price(pool, sqrt_price_target_x_96)?;
+
+    Ok(PriceImpactQuote {
+        zero_for_one,
+        ..quote
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{self, SQRT_PRICE_1_1};
+    use alloy_primitives::Address;
+
+    // liquidity over three overlapping ranges around the current price
+    fn new_pool() -> Pool {
+        test_utils::new_pool(
+            Address::ZERO,
+            &[
+                (-600, 600, 1e18 as u128),
+                (-120, 60, 5e18 as u128),
+                (-6000, 6000, 1e17 as u128),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_sqrt_price_for_price_impact() {
+        // 1% down in price is ~0.5013% down in sqrt price
+        let sqrt_price = sqrt_price_for_price_impact(SQRT_PRICE_1_1, true, 100).unwrap();
+        let price = (sqrt_price.to::<u128>() as f64 / 2f64.powi(96)).powi(2);
+        assert!((price - 0.99).abs() < 1e-12);
+        assert!(price >= 0.99);
+
+        let sqrt_price = sqrt_price_for_price_impact(SQRT_PRICE_1_1, false, 100).unwrap();
+        let price = (sqrt_price.to::<u128>() as f64 / 2f64.powi(96)).powi(2);
+        assert!((price - 1.01).abs() < 1e-12);
+
+        assert_eq!(
+            sqrt_price_for_price_impact(SQRT_PRICE_1_1, true, 0).unwrap(),
+            SQRT_PRICE_1_1
+        );
+        assert_eq!(
+            sqrt_price_for_price_impact(SQRT_PRICE_1_1, true, 10_000).unwrap(),
+            MIN_SQRT_RATIO + U256_1
+        );
+        assert_eq!(
+            sqrt_price_for_price_impact(MAX_SQRT_RATIO - U256_1, false, 1).unwrap(),
+            MAX_SQRT_RATIO - U256_1
+        );
+    }
+
+    #[test]
+    fn test_amounts_to_sqrt_price_matches_swap() {
+        for (zero_for_one, tick) in [(true, -300), (true, -1000), (false, 30), (false, 5000)] {
+            let pool = new_pool();
+            let target = get_sqrt_ratio_at_tick(tick).unwrap();
+            let quote = amounts_to_sqrt_price(&pool, target).unwrap();
+            assert_eq!(quote.zero_for_one, zero_for_one);
+            assert_eq!(quote.sqrt_price_x_96, target);
+
+            // swapping exactly amount_in lands on the target and pays out amount_out
+            let mut exact_in = pool.clone();
+            let result = exact_in
+                .swap(zero_for_one, I256::from_raw(quote.amount_in), target)
+                .unwrap();
+            let (amount_in, amount_out) = if zero_for_one {
+                (result.amount_0, -result.amount_1)
+            } else {
+                (result.amount_1, -result.amount_0)
+            };
+            assert_eq!(amount_in.into_raw(), quote.amount_in);
+            assert_eq!(amount_out.into_raw(), quote.amount_out);
+            assert_eq!(exact_in.slot0.sqrt_price_x_96, target);
+            assert_eq!(exact_in.slot0.tick, quote.tick);
+            assert_eq!(exact_in.liquidity, {
+                let mut pool = pool.clone();
+                pool.swap(zero_for_one, I256::MAX, target).unwrap();
+                pool.liquidity
+            });
+            if zero_for_one {
+                assert_eq!(quote.fee_amount, result.fees.total_fee_0());
+            } else {
+                assert_eq!(quote.fee_amount, result.fees.total_fee_1());
+            }
+
+            // asking for amount_out exactly costs at most amount_in
+            let mut exact_out = pool.clone();
+            let result = exact_out
+                .swap(zero_for_one, -I256::from_raw(quote.amount_out), target)
+                .unwrap();
+            let amount_in = if zero_for_one {
+                result.amount_0
+            } else {
+                result.amount_1
+            };
+            assert!(amount_in.into_raw() <= quote.amount_in);
+        }
+    }
+
+    #[test]
+    fn test_ticks_crossed() {
+        let pool = new_pool();
+
+        let quote = amounts_to_sqrt_price(&pool, get_sqrt_ratio_at_tick(-1000).unwrap()).unwrap();
+        assert_eq!(quote.ticks_crossed, 2);
+
+        let quote = amounts_to_sqrt_price(&pool, get_sqrt_ratio_at_tick(7000).unwrap()).unwrap();
+        assert_eq!(quote.ticks_crossed, 3);
+
+        let quote = amounts_to_sqrt_price(&pool, SQRT_PRICE_1_1).unwrap();
+        assert_eq!(
+            quote,
+            PriceImpactQuote {
+                sqrt_price_x_96: SQRT_PRICE_1_1,
+                ..Default::default()
+            }
+        );
+
+        assert!(amounts_to_sqrt_price(&pool, MIN_SQRT_RATIO).is_err());
+    }
+
+    #[test]
+    fn test_amounts_for_price_impact() {
+        let pool = new_pool();
+        for zero_for_one in [true, false] {
+            let quote = amounts_for_price_impact(&pool, zero_for_one, 250).unwrap();
+
+            let mut swapped = pool.clone();
+            let limit = if zero_for_one {
+                MIN_SQRT_RATIO + U256_1
+            } else {
+                MAX_SQRT_RATIO - U256_1
+            };
+            swapped
+                .swap(zero_for_one, I256::from_raw(quote.amount_in), limit)
+                .unwrap();
+
+            let price = |sqrt_price: U256| (sqrt_price.to::<u128>() as f64 / 2f64.powi(96)).powi(2);
+            let impact = (price(swapped.slot0.sqrt_price_x_96) - 1.0).abs();
+            assert!((impact - 0.025).abs() < 1e-9, "impact = {impact}");
+        }
+    }
+}
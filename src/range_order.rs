@@ -0,0 +1,279 @@
+use alloy_primitives::U256;
+
+use crate::{
+    error::UniswapV3MathError,
+    liquidity_amounts::{get_liquidity_for_amount_0, get_liquidity_for_amount_1},
+    position_value::position_amounts,
+    sqrt_price_math::{_get_amount_0_delta, _get_amount_1_delta},
+    tick::check_ticks,
+    tick_math::{get_sqrt_ratio_at_tick, get_tick_at_sqrt_ratio},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeOrderStatus {
+    //The price has not reached the range, the order still holds only the deposited token
+    Open,
+    //The price is inside the range, the order holds both tokens
+    PartiallyFilled,
+    //The price has crossed the range, the order holds only the bought token
+    Filled,
+}
+
+//A limit order as a position spanning a single tick spacing, entirely on one side of the price. Selling token0 the
+//range is above the price and converts to token1 as the price rises through it, selling token1 it is below the price
+//and converts to token0 as the price falls through it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeOrder {
+    //Whether token0 is sold for token1
+    pub zero_for_one: bool,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+}
+
+impl RangeOrder {
+    //Returns the range of an order filled at the limit price or better: selling token0 the range starts at the first
+    //usable tick at or above the limit price, selling token1 it ends at the last usable tick at or below it
+    // returns (int24 tickLower, int24 tickUpper)
+    pub fn ticks(
+        sqrt_price_limit_x_96: U256,
+        tick_spacing: i32,
+        zero_for_one: bool,
+    ) -> Result<(i32, i32), UniswapV3MathError> {
+        if tick_spacing <= 0 {
+            return Err(UniswapV3MathError::TickSpacingOutOfBounds(tick_spacing));
+        }
+
+        let tick = get_tick_at_sqrt_ratio(sqrt_price_limit_x_96)?;
+        let (tick_lower, tick_upper) = if zero_for_one {
+            // round up, unless the limit price is exactly on a usable tick
+            let mut tick_lower = tick.div_euclid(tick_spacing) * tick_spacing;
+            if tick_lower < tick || get_sqrt_ratio_at_tick(tick_lower)? < sqrt_price_limit_x_96 {
+                tick_lower += tick_spacing;
+            }
+            (tick_lower, tick_lower + tick_spacing)
+        } else {
+            let tick_upper = tick.div_euclid(tick_spacing) * tick_spacing;
+            (tick_upper - tick_spacing, tick_upper)
+        };
+
+        check_ticks(tick_lower, tick_upper)?;
+        Ok((tick_lower, tick_upper))
+    }
+
+    //Places an order selling amount_in at the limit price or better. The range must be entirely on one side of the
+    //current price so that the deposit is a single token.
+    pub fn new(
+        sqrt_price_limit_x_96: U256,
+        tick_spacing: i32,
+        zero_for_one: bool,
+        amount_in: U256,
+        sqrt_price_x_96: U256,
+    ) -> Result<RangeOrder, UniswapV3MathError> {
+        let (tick_lower, tick_upper) =
+            RangeOrder::ticks(sqrt_price_limit_x_96, tick_spacing, zero_for_one)?;
+        let sqrt_price_lower_x_96 = get_sqrt_ratio_at_tick(tick_lower)?;
+        let sqrt_price_upper_x_96 = get_sqrt_ratio_at_tick(tick_upper)?;
+
+        let liquidity = if zero_for_one {
+            if sqrt_price_x_96 > sqrt_price_lower_x_96 {
+                return Err(UniswapV3MathError::PriceLimitAlreadyExceeded(
+                    sqrt_price_x_96,
+                    sqrt_price_lower_x_96,
+                ));
+            }
+            get_liquidity_for_amount_0(sqrt_price_lower_x_96, sqrt_price_upper_x_96, amount_in)?
+        } else {
+            if sqrt_price_x_96 < sqrt_price_upper_x_96 {
+                return Err(UniswapV3MathError::PriceLimitAlreadyExceeded(
+                    sqrt_price_x_96,
+                    sqrt_price_upper_x_96,
+                ));
+            }
+            get_liquidity_for_amount_1(sqrt_price_lower_x_96, sqrt_price_upper_x_96, amount_in)?
+        };
+
+        Ok(RangeOrder {
+            zero_for_one,
+            tick_lower,
+            tick_upper,
+            liquidity,
+        })
+    }
+
+    //Returns the fill status at the current tick, a position is active when tick_lower <= tick < tick_upper
+    pub fn status(&self, tick: i32) -> RangeOrderStatus {
+        let (not_reached, crossed) = if self.zero_for_one {
+            (tick < self.tick_lower, tick >= self.tick_upper)
+        } else {
+            (tick >= self.tick_upper, tick < self.tick_lower)
+        };
+
+        if not_reached {
+            RangeOrderStatus::Open
+        } else if crossed {
+            RangeOrderStatus::Filled
+        } else {
+            RangeOrderStatus::PartiallyFilled
+        }
+    }
+
+    //Returns the amounts the order would withdraw at a sqrt price, rounded down as burning it would
+    // returns (uint256 amount0, uint256 amount1)
+    pub fn amounts(&self, sqrt_price_x_96: U256) -> Result<(U256, U256), UniswapV3MathError> {
+        position_amounts(
+            sqrt_price_x_96,
+            self.tick_lower,
+            self.tick_upper,
+            self.liquidity,
+        )
+    }
+
+    //Returns the amount of the bought token withdrawn once the order is fully converted
+    pub fn amount_out_when_filled(&self) -> Result<U256, UniswapV3MathError> {
+        let sqrt_price_lower_x_96 = get_sqrt_ratio_at_tick(self.tick_lower)?;
+        let sqrt_price_upper_x_96 = get_sqrt_ratio_at_tick(self.tick_upper)?;
+
+        if self.zero_for_one {
+            _get_amount_1_delta(
+                sqrt_price_lower_x_96,
+                sqrt_price_upper_x_96,
+                self.liquidity,
+                false,
+            )
+        } else {
+            _get_amount_0_delta(
+                sqrt_price_lower_x_96,
+                sqrt_price_upper_x_96,
+                self.liquidity,
+                false,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test_utils::{new_pool, SQRT_PRICE_1_1},
+        tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO},
+    };
+    use alloy_primitives::{Address, I256};
+
+    #[test]
+    fn test_ticks() {
+        let at = |tick| get_sqrt_ratio_at_tick(tick).unwrap();
+
+        // selling token0 rounds the range up, selling token1 rounds it down
+        assert_eq!(RangeOrder::ticks(at(100), 60, true).unwrap(), (120, 180));
+        assert_eq!(RangeOrder::ticks(at(100), 60, false).unwrap(), (0, 60));
+        assert_eq!(RangeOrder::ticks(at(-100), 60, true).unwrap(), (-60, 0));
+        assert_eq!(
+            RangeOrder::ticks(at(-100), 60, false).unwrap(),
+            (-180, -120)
+        );
+
+        // a limit price on a usable tick is used as is
+        assert_eq!(RangeOrder::ticks(at(120), 60, true).unwrap(), (120, 180));
+        assert_eq!(RangeOrder::ticks(at(120), 60, false).unwrap(), (60, 120));
+
+        // a limit price just above a usable tick is rounded up
+        assert_eq!(
+            RangeOrder::ticks(at(120) + U256::from(1), 60, true).unwrap(),
+            (180, 240)
+        );
+
+        assert!(RangeOrder::ticks(at(100), 0, true).is_err());
+    }
+
+    #[test]
+    fn test_new() {
+        let order = RangeOrder::new(
+            get_sqrt_ratio_at_tick(100).unwrap(),
+            60,
+            true,
+            U256::from(1e18 as u64),
+            SQRT_PRICE_1_1,
+        )
+        .unwrap();
+        assert_eq!(order.status(0), RangeOrderStatus::Open);
+        assert_eq!(order.status(120), RangeOrderStatus::PartiallyFilled);
+        assert_eq!(order.status(180), RangeOrderStatus::Filled);
+
+        // the deposit is a single token
+        let (amount_0, amount_1) = order.amounts(SQRT_PRICE_1_1).unwrap();
+        assert!(amount_0 <= U256::from(1e18 as u64));
+        assert!(U256::from(1e18 as u64) - amount_0 <= U256::from(1));
+        assert_eq!(amount_1, U256::ZERO);
+
+        // the range is already crossed
+        let result = RangeOrder::new(
+            get_sqrt_ratio_at_tick(-100).unwrap(),
+            60,
+            true,
+            U256::from(1e18 as u64),
+            SQRT_PRICE_1_1,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_fill() {
+        for zero_for_one in [true, false] {
+            let mut pool = new_pool(Address::ZERO, &[(-6000, 6000, 1e18 as u128)]);
+
+            let limit_tick = if zero_for_one { 300 } else { -300 };
+            let order = RangeOrder::new(
+                get_sqrt_ratio_at_tick(limit_tick).unwrap(),
+                60,
+                zero_for_one,
+                U256::from(1e16 as u64),
+                pool.slot0.sqrt_price_x_96,
+            )
+            .unwrap();
+            let owner = Address::repeat_byte(1);
+            pool.mint(owner, order.tick_lower, order.tick_upper, order.liquidity)
+                .unwrap();
+
+            // push the price through the range
+            let (swap_zero_for_one, limit) = if zero_for_one {
+                (false, MAX_SQRT_RATIO - U256::from(1))
+            } else {
+                (true, MIN_SQRT_RATIO + U256::from(1))
+            };
+            pool.swap(
+                swap_zero_for_one,
+                I256::try_from(1e17 as i128).unwrap(),
+                limit,
+            )
+            .unwrap();
+            assert_eq!(order.status(pool.slot0.tick), RangeOrderStatus::Filled);
+
+            // the order withdraws exactly the converted amount, its swap fees are owed separately
+            let (amount_0, amount_1) = pool
+                .burn(owner, order.tick_lower, order.tick_upper, order.liquidity)
+                .unwrap();
+            let amount_out = order.amount_out_when_filled().unwrap();
+            if zero_for_one {
+                assert_eq!((amount_0, amount_1), (U256::ZERO, amount_out));
+            } else {
+                assert_eq!((amount_0, amount_1), (amount_out, U256::ZERO));
+            }
+            assert_eq!(
+                order.amounts(pool.slot0.sqrt_price_x_96).unwrap(),
+                (amount_0, amount_1)
+            );
+
+            // at a price no worse than the limit
+            let price = |sqrt_price: U256| (sqrt_price.to::<u128>() as f64 / 2f64.powi(96)).powi(2);
+            let limit_price = price(get_sqrt_ratio_at_tick(limit_tick).unwrap());
+            let amount_out = amount_out.to::<u128>() as f64;
+            if zero_for_one {
+                assert!(amount_out / 1e16 >= limit_price);
+            } else {
+                assert!(1e16 / amount_out <= limit_price);
+            }
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use alloy_primitives::{I256, U256};
+
+use crate::{
+    error::UniswapV3MathError,
+    pool::Pool,
+    tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO},
+    U256_1,
+};
+
+//The fields of a Swap event, with amounts positive when paid to the pool and the pool state after the swap
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordedSwap {
+    pub amount_0: I256,
+    pub amount_1: I256,
+    pub sqrt_price_x_96: U256,
+    pub liquidity: u128,
+    pub tick: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    //The name of the field in the Swap event
+    pub field: &'static str,
+    pub expected: String,
+    pub computed: String,
+}
+
+//The first recorded swap the simulation disagrees with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    //The index of the swap in the replayed sequence
+    pub index: usize,
+    pub expected: RecordedSwap,
+    //The values computed by the simulation, None when the simulated swap failed
+    pub computed: Option<RecordedSwap>,
+    pub error: Option<String>,
+}
+
+impl Divergence {
+    //Returns the fields whose expected and computed values differ, every field when the simulated swap failed
+    pub fn diff(&self) -> Vec<FieldDiff> {
+        let expected = fields(&self.expected);
+        match self.computed {
+            Some(computed) => expected
+                .into_iter()
+                .zip(fields(&computed))
+                .filter(|((_, expected), (_, computed))| expected != computed)
+                .map(|((field, expected), (_, computed))| FieldDiff {
+                    field,
+                    expected,
+                    computed,
+                })
+                .collect(),
+            None => expected
+                .into_iter()
+                .map(|(field, expected)| FieldDiff {
+                    field,
+                    expected,
+                    computed: self.error.clone().unwrap_or_default(),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn fields(swap: &RecordedSwap) -> [(&'static str, String); 5] {
+    [
+        ("amount0", swap.amount_0.to_string()),
+        ("amount1", swap.amount_1.to_string()),
+        ("sqrtPriceX96", swap.sqrt_price_x_96.to_string()),
+        ("liquidity", swap.liquidity.to_string()),
+        ("tick", swap.tick.to_string()),
+    ]
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    //The number of swaps reproduced exactly, all of them when there is no divergence
+    pub swaps_verified: usize,
+    pub divergence: Option<Divergence>,
+}
+
+//Re-simulates a recorded swap. The event does not tell whether the amount was exact input or output, so the swap is
+//replayed as an exact input of the amount paid to the pool limited to the recorded price, which reproduces both kinds
+//on a pool in the same state.
+fn simulate(pool: &mut Pool, swap: &RecordedSwap) -> Result<RecordedSwap, UniswapV3MathError> {
+    let zero_for_one = swap.amount_0.is_positive();
+    let amount_specified = if zero_for_one {
+        swap.amount_0
+    } else {
+        swap.amount_1
+    };
+
+    // a swap that did not move the price has no usable limit
+    let sqrt_price_x_96 = pool.slot0.sqrt_price_x_96;
+    let sqrt_price_limit_x_96 = if zero_for_one && swap.sqrt_price_x_96 < sqrt_price_x_96 {
+        swap.sqrt_price_x_96.max(MIN_SQRT_RATIO + U256_1)
+    } else if !zero_for_one && swap.sqrt_price_x_96 > sqrt_price_x_96 {
+        swap.sqrt_price_x_96.min(MAX_SQRT_RATIO - U256_1)
+    } else if zero_for_one {
+        MIN_SQRT_RATIO + U256_1
+    } else {
+        MAX_SQRT_RATIO - U256_1
+    };
+
+    let result = pool.swap(zero_for_one, amount_specified, sqrt_price_limit_x_96)?;
+    Ok(RecordedSwap {
+        amount_0: result.amount_0,
+        amount_1: result.amount_1,
+        sqrt_price_x_96: pool.slot0.sqrt_price_x_96,
+        liquidity: pool.liquidity,
+        tick: pool.slot0.tick,
+    })
+}
+
+//Replays recorded swaps on a pool snapshot, stopping at the first swap whose amounts or resulting state differ from
+//the recorded ones. The pool is left in the state after the last replayed swap, including the divergent one.
+pub fn verify_swaps(pool: &mut Pool, swaps: &[RecordedSwap]) -> ReplayReport {
+    let mut report = ReplayReport::default();
+
+    for (index, expected) in swaps.iter().enumerate() {
+        let (computed, error) = match simulate(pool, expected) {
+            Ok(computed) if computed == *expected => {
+                report.swaps_verified += 1;
+                continue;
+            }
+            Ok(computed) => (Some(computed), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+
+        report.divergence = Some(Divergence {
+            index,
+            expected: *expected,
+            computed,
+            error,
+        });
+        break;
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_utils, tick_math::get_sqrt_ratio_at_tick};
+    use alloy_primitives::Address;
+
+    fn new_pool() -> Pool {
+        test_utils::new_pool(
+            Address::ZERO,
+            &[(-6000, 6000, 1e18 as u128), (-600, 600, 1e18 as u128)],
+        )
+    }
+
+    // swaps of both kinds and directions, some stopped by a price limit, recorded as Swap events
+    fn record(pool: &mut Pool) -> Vec<RecordedSwap> {
+        let mut swaps = vec![];
+        // the limits are given in ticks away from the price before the swap
+        for (zero_for_one, amount_specified, limit_ticks) in [
+            (false, 1e16 as i128, None),
+            (true, -5e16 as i128, None),
+            (true, 1e18 as i128, Some(-500)),
+            (false, -2e17 as i128, None),
+            (false, 1e18 as i128, Some(700)),
+        ] {
+            let sqrt_price_limit_x_96 = match limit_ticks {
+                Some(ticks) => get_sqrt_ratio_at_tick(pool.slot0.tick + ticks).unwrap(),
+                None if zero_for_one => MIN_SQRT_RATIO + U256_1,
+                None => MAX_SQRT_RATIO - U256_1,
+            };
+            let result = pool
+                .swap(
+                    zero_for_one,
+                    I256::try_from(amount_specified).unwrap(),
+                    sqrt_price_limit_x_96,
+                )
+                .unwrap();
+            swaps.push(RecordedSwap {
+                amount_0: result.amount_0,
+                amount_1: result.amount_1,
+                sqrt_price_x_96: pool.slot0.sqrt_price_x_96,
+                liquidity: pool.liquidity,
+                tick: pool.slot0.tick,
+            });
+        }
+        swaps
+    }
+
+    #[test]
+    fn test_verify_swaps() {
+        let swaps = record(&mut new_pool());
+
+        let mut pool = new_pool();
+        let report = verify_swaps(&mut pool, &swaps);
+        assert_eq!(report.swaps_verified, swaps.len());
+        assert_eq!(report.divergence, None);
+        assert_eq!(pool.slot0.sqrt_price_x_96, swaps[4].sqrt_price_x_96);
+    }
+
+    #[test]
+    fn test_verify_swaps_divergence() {
+        let swaps = record(&mut new_pool());
+
+        // a snapshot missing the narrow position diverges on the first swap
+        let mut pool = test_utils::new_pool(Address::ZERO, &[(-6000, 6000, 1e18 as u128)]);
+        let report = verify_swaps(&mut pool, &swaps);
+        assert_eq!(report.swaps_verified, 0);
+
+        let divergence = report.divergence.unwrap();
+        assert_eq!(divergence.index, 0);
+        assert_eq!(divergence.expected, swaps[0]);
+        let diff = divergence.diff();
+        assert!(diff.iter().any(|diff| diff.field == "liquidity"
+            && diff.expected == "2000000000000000000"
+            && diff.computed == "1000000000000000000"));
+        assert!(diff.iter().all(|diff| diff.expected != diff.computed));
+
+        // a swap the snapshot cannot simulate reports the error
+        let mut swap = swaps[0];
+        swap.amount_1 = I256::ZERO;
+        let report = verify_swaps(&mut new_pool(), &[swap]);
+        let divergence = report.divergence.unwrap();
+        assert_eq!(divergence.computed, None);
+        assert_eq!(
+            divergence.error.as_deref(),
+            Some("Swap amount cannot be zero")
+        );
+        assert_eq!(divergence.diff().len(), 5);
+    }
+}
@@ -0,0 +1,95 @@
+use alloy_primitives::{I256, U256};
+
+use crate::{error::UniswapV3MathError, sqrt_price_math::MAX_U160};
+
+// returns (uint160 z)
+pub fn to_uint160(y: U256) -> Result<U256, UniswapV3MathError> {
+    if y > MAX_U160 {
+        Err(UniswapV3MathError::SafeCastToU160Overflow)
+    } else {
+        Ok(y)
+    }
+}
+
+// returns (uint128 z)
+pub fn to_uint128(y: U256) -> Result<u128, UniswapV3MathError> {
+    u128::try_from(y).map_err(|_| UniswapV3MathError::SafeCastToU128Overflow)
+}
+
+// returns (int128 z)
+pub fn to_int128(y: I256) -> Result<i128, UniswapV3MathError> {
+    i128::try_from(y).map_err(|_| UniswapV3MathError::SafeCastToI128Overflow)
+}
+
+// returns (int256 z)
+pub fn to_int256(y: U256) -> Result<I256, UniswapV3MathError> {
+    I256::try_from(y).map_err(|_| UniswapV3MathError::SafeCastToI256Overflow)
+}
+
+#[cfg(test)]
+mod test {
+    use alloy_primitives::{I256, U256};
+
+    use super::{to_int128, to_int256, to_uint128, to_uint160};
+    use crate::{sqrt_price_math::MAX_U160, U256_1};
+
+    #[test]
+    fn test_to_uint160() {
+        assert_eq!(to_uint160(U256::ZERO).unwrap(), U256::ZERO);
+        assert_eq!(to_uint160(MAX_U160).unwrap(), MAX_U160);
+
+        let result = to_uint160(MAX_U160 + U256_1);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Overflow when casting to U160"
+        );
+    }
+
+    #[test]
+    fn test_to_uint128() {
+        assert_eq!(to_uint128(U256::from(u128::MAX)).unwrap(), u128::MAX);
+
+        let result = to_uint128(U256::from(u128::MAX) + U256_1);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Overflow when casting to U128"
+        );
+    }
+
+    #[test]
+    fn test_to_int128() {
+        assert_eq!(
+            to_int128(I256::try_from(i128::MAX).unwrap()).unwrap(),
+            i128::MAX
+        );
+        assert_eq!(
+            to_int128(I256::try_from(i128::MIN).unwrap()).unwrap(),
+            i128::MIN
+        );
+
+        let result = to_int128(I256::try_from(i128::MAX).unwrap() + I256::ONE);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Overflow when casting to I128"
+        );
+
+        let result = to_int128(I256::try_from(i128::MIN).unwrap() - I256::ONE);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Overflow when casting to I128"
+        );
+    }
+
+    #[test]
+    fn test_to_int256() {
+        assert_eq!(to_int256(U256::ZERO).unwrap(), I256::ZERO);
+        assert_eq!(to_int256(I256::MAX.into_raw()).unwrap(), I256::MAX);
+
+        // 2**255
+        let result = to_int256(U256_1 << 255);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Overflow when casting to I256"
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use alloy_primitives::{Address, I256, U256};
+
+use crate::{error::UniswapV3MathError, pool::Pool};
+
+//A pool interaction of a scenario
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    //Adds liquidity to the owner's position
+    Mint {
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    },
+    //Removes liquidity from the owner's position and collects everything it is owed, principal and fees
+    Burn {
+        owner: Address,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: u128,
+    },
+    //A positive amount specified is the exact input, a negative amount the exact output
+    Swap {
+        sender: Address,
+        zero_for_one: bool,
+        amount_specified: I256,
+        sqrt_price_limit_x_96: U256,
+    },
+}
+
+impl Action {
+    pub fn actor(&self) -> Address {
+        match *self {
+            Action::Mint { owner, .. } | Action::Burn { owner, .. } => owner,
+            Action::Swap { sender, .. } => sender,
+        }
+    }
+}
+
+//Token balance changes from the actor's point of view, positive amounts being received from the pool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActorDeltas {
+    pub amount_0: I256,
+    pub amount_1: I256,
+    //The swap fees collected by the actor's positions, included in the amounts
+    pub fees_earned_0: U256,
+    pub fees_earned_1: U256,
+}
+
+impl ActorDeltas {
+    fn add(&mut self, other: &ActorDeltas) {
+        self.amount_0 += other.amount_0;
+        self.amount_1 += other.amount_1;
+        self.fees_earned_0 += other.fees_earned_0;
+        self.fees_earned_1 += other.fees_earned_1;
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScenarioResult {
+    //The deltas of each action, in order
+    pub actions: Vec<ActorDeltas>,
+    //The deltas of each actor summed over the scenario
+    pub actors: HashMap<Address, ActorDeltas>,
+}
+
+impl ScenarioResult {
+    pub fn actor(&self, actor: Address) -> ActorDeltas {
+        self.actors.get(&actor).copied().unwrap_or_default()
+    }
+}
+
+//Applies a single action to the pool, returning the actor's deltas
+pub fn apply_action(pool: &mut Pool, action: &Action) -> Result<ActorDeltas, UniswapV3MathError> {
+    match *action {
+        Action::Mint {
+            owner,
+            tick_lower,
+            tick_upper,
+            amount,
+        } => {
+            let (amount_0, amount_1) = pool.mint(owner, tick_lower, tick_upper, amount)?;
+            Ok(ActorDeltas {
+                amount_0: -I256::from_raw(amount_0),
+                amount_1: -I256::from_raw(amount_1),
+                ..Default::default()
+            })
+        }
+        Action::Burn {
+            owner,
+            tick_lower,
+            tick_upper,
+            amount,
+        } => {
+            let (amount_0, amount_1) = pool.burn(owner, tick_lower, tick_upper, amount)?;
+            let (collected_0, collected_1) =
+                pool.collect(owner, tick_lower, tick_upper, u128::MAX, u128::MAX);
+            let (collected_0, collected_1) = (U256::from(collected_0), U256::from(collected_1));
+            Ok(ActorDeltas {
+                amount_0: I256::from_raw(collected_0),
+                amount_1: I256::from_raw(collected_1),
+                fees_earned_0: collected_0.saturating_sub(amount_0),
+                fees_earned_1: collected_1.saturating_sub(amount_1),
+            })
+        }
+        Action::Swap {
+            zero_for_one,
+            amount_specified,
+            sqrt_price_limit_x_96,
+            ..
+        } => {
+            let result = pool.swap(zero_for_one, amount_specified, sqrt_price_limit_x_96)?;
+            Ok(ActorDeltas {
+                amount_0: -result.amount_0,
+                amount_1: -result.amount_1,
+                ..Default::default()
+            })
+        }
+    }
+}
+
+//Applies an ordered list of actions to the pool, e.g. a mint-swap-burn just-in-time liquidity sequence or a
+//front-run, victim swap and back-run sandwich, returning the deltas of every action and actor
+pub fn run_scenario(
+    pool: &mut Pool,
+    actions: &[Action],
+) -> Result<ScenarioResult, UniswapV3MathError> {
+    let mut result = ScenarioResult::default();
+    for action in actions {
+        let deltas = apply_action(pool, action)?;
+        result
+            .actors
+            .entry(action.actor())
+            .or_default()
+            .add(&deltas);
+        result.actions.push(deltas);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test_utils,
+        tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO},
+    };
+
+    const LP: Address = Address::repeat_byte(1);
+    const VICTIM: Address = Address::repeat_byte(2);
+    const ATTACKER: Address = Address::repeat_byte(3);
+
+    fn new_pool() -> Pool {
+        test_utils::new_pool(LP, &[(-600, 600, 1e18 as u128)])
+    }
+
+    fn swap(sender: Address, zero_for_one: bool, amount_specified: i128) -> Action {
+        Action::Swap {
+            sender,
+            zero_for_one,
+            amount_specified: I256::try_from(amount_specified).unwrap(),
+            sqrt_price_limit_x_96: if zero_for_one {
+                MIN_SQRT_RATIO + U256::from(1)
+            } else {
+                MAX_SQRT_RATIO - U256::from(1)
+            },
+        }
+    }
+
+    #[test]
+    fn test_just_in_time_liquidity() {
+        let victim_swap = swap(VICTIM, true, 1e16 as i128);
+        let alone = run_scenario(&mut new_pool(), &[victim_swap]).unwrap();
+
+        let jit = [
+            Action::Mint {
+                owner: ATTACKER,
+                tick_lower: -60,
+                tick_upper: 60,
+                amount: 9e18 as u128,
+            },
+            victim_swap,
+            Action::Burn {
+                owner: ATTACKER,
+                tick_lower: -60,
+                tick_upper: 60,
+                amount: 9e18 as u128,
+            },
+        ];
+        let mut pool = new_pool();
+        let result = run_scenario(&mut pool, &jit).unwrap();
+        assert_eq!(result.actions.len(), 3);
+
+        // the victim gets a better price from the deeper pool
+        let victim = result.actor(VICTIM);
+        assert_eq!(victim.amount_0, alone.actor(VICTIM).amount_0);
+        assert!(victim.amount_1 > alone.actor(VICTIM).amount_1);
+
+        // the attacker takes 90% of the 0.3% fee, rounded down
+        let attacker = result.actor(ATTACKER);
+        assert_eq!(attacker.fees_earned_1, U256::ZERO);
+        assert_eq!(attacker.fees_earned_0, U256::from(27e12 as u64 - 1));
+
+        // no tokens are created: what the actors gained, the pool lost
+        let lp = pool
+            .burn(LP, -600, 600, 1e18 as u128)
+            .map(|_| pool.collect(LP, -600, 600, u128::MAX, u128::MAX))
+            .unwrap();
+        let total_0 = victim.amount_0 + attacker.amount_0 + I256::try_from(lp.0).unwrap();
+        let total_1 = victim.amount_1 + attacker.amount_1 + I256::try_from(lp.1).unwrap();
+        let (minted_0, minted_1) = new_pool().burn(LP, -600, 600, 1e18 as u128).unwrap();
+        assert!(total_0 <= I256::from_raw(minted_0));
+        assert!(total_1 <= I256::from_raw(minted_1));
+    }
+
+    #[test]
+    fn test_sandwich() {
+        let victim_swap = swap(VICTIM, true, 1e16 as i128);
+        let alone = run_scenario(&mut new_pool(), &[victim_swap]).unwrap();
+
+        let front_run = swap(ATTACKER, true, 2e16 as i128);
+        let mut pool = new_pool();
+        let front_run_deltas = apply_action(&mut pool, &front_run).unwrap();
+        let result = run_scenario(
+            &mut pool,
+            &[
+                victim_swap,
+                // sell back exactly the token1 bought in the front-run
+                swap(
+                    ATTACKER,
+                    false,
+                    i128::try_from(front_run_deltas.amount_1).unwrap(),
+                ),
+            ],
+        )
+        .unwrap();
+
+        // the victim receives less token1 for the same token0
+        let victim = result.actor(VICTIM);
+        assert_eq!(victim.amount_0, alone.actor(VICTIM).amount_0);
+        assert!(victim.amount_1 < alone.actor(VICTIM).amount_1);
+
+        // the attacker ends with more token0 than they started with and no token1 left
+        let back_run = result.actor(ATTACKER);
+        assert_eq!(back_run.amount_1, -front_run_deltas.amount_1);
+        assert!(front_run_deltas.amount_0 + back_run.amount_0 > I256::ZERO);
+    }
+}
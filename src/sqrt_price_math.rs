@@ -3,6 +3,8 @@ use alloy_primitives::{I256, U256};
 use crate::{
     error::UniswapV3MathError,
     full_math::{mul_div, mul_div_rounding_up},
+    low_gas_safe_math,
+    safe_cast::{to_int256, to_uint160},
     unsafe_math::div_rounding_up,
 };
 
@@ -77,14 +79,18 @@ pub fn get_next_sqrt_price_from_amount_0_rounding_up(
 
         Ok(div_rounding_up(
             numerator_1,
-            (numerator_1.wrapping_div(sqrt_price_x_96)).wrapping_add(amount),
+            low_gas_safe_math::add(numerator_1.wrapping_div(sqrt_price_x_96), amount)?,
         ))
     } else {
         let product = amount.wrapping_mul(sqrt_price_x_96);
         if product.wrapping_div(amount) == sqrt_price_x_96 && numerator_1 > product {
             let denominator = numerator_1.wrapping_sub(product);
 
-            mul_div_rounding_up(numerator_1, sqrt_price_x_96, denominator)
+            to_uint160(mul_div_rounding_up(
+                numerator_1,
+                sqrt_price_x_96,
+                denominator,
+            )?)
         } else {
             Err(UniswapV3MathError::ProductDivAmount)
         }
@@ -107,13 +113,7 @@ pub fn get_next_sqrt_price_from_amount_1_rounding_down(
             mul_div(amount, Q96, liquidity)?
         };
 
-        let next_sqrt_price = sqrt_price_x_96 + quotient;
-
-        if next_sqrt_price > MAX_U160 {
-            Err(UniswapV3MathError::SafeCastToU160Overflow)
-        } else {
-            Ok(next_sqrt_price)
-        }
+        to_uint160(low_gas_safe_math::add(sqrt_price_x_96, quotient)?)
     } else {
         let quotient = if amount <= MAX_U160 {
             div_rounding_up(amount << FIXED_POINT_96_RESOLUTION, liquidity)
@@ -190,19 +190,19 @@ pub fn get_amount_0_delta(
     liquidity: i128,
 ) -> Result<I256, UniswapV3MathError> {
     if liquidity < 0 {
-        Ok(-I256::from_raw(_get_amount_0_delta(
+        Ok(-to_int256(_get_amount_0_delta(
             sqrt_ratio_a_x_96,
             sqrt_ratio_b_x_96,
-            -liquidity as u128,
+            liquidity.unsigned_abs(),
             false,
-        )?))
+        )?)?)
     } else {
-        Ok(I256::from_raw(_get_amount_0_delta(
+        to_int256(_get_amount_0_delta(
             sqrt_ratio_a_x_96,
             sqrt_ratio_b_x_96,
             liquidity as u128,
             true,
-        )?))
+        )?)
     }
 }
 
@@ -212,19 +212,19 @@ pub fn get_amount_1_delta(
     liquidity: i128,
 ) -> Result<I256, UniswapV3MathError> {
     if liquidity < 0 {
-        Ok(-I256::from_raw(_get_amount_1_delta(
+        Ok(-to_int256(_get_amount_1_delta(
             sqrt_ratio_a_x_96,
             sqrt_ratio_b_x_96,
-            -liquidity as u128,
+            liquidity.unsigned_abs(),
             false,
-        )?))
+        )?)?)
     } else {
-        Ok(I256::from_raw(_get_amount_1_delta(
+        to_int256(_get_amount_1_delta(
             sqrt_ratio_a_x_96,
             sqrt_ratio_b_x_96,
             liquidity as u128,
             true,
-        )?))
+        )?)
     }
 }
 
@@ -235,14 +235,21 @@ mod test {
         str::FromStr,
     };
 
-    use alloy_primitives::U256;
+    use alloy_primitives::{I256, U256};
+    #[cfg(not(target_arch = "wasm32"))]
+    use proptest::prelude::*;
 
+    #[cfg(not(target_arch = "wasm32"))]
+    use crate::tick_math::{MAX_TICK, MIN_TICK};
     use crate::{
         sqrt_price_math::{_get_amount_1_delta, get_next_sqrt_price_from_output, MAX_U160},
+        tick_math::get_sqrt_ratio_at_tick,
         U256_1, U256_2,
     };
 
-    use super::{_get_amount_0_delta, get_next_sqrt_price_from_input};
+    use super::{
+        _get_amount_0_delta, get_amount_0_delta, get_amount_1_delta, get_next_sqrt_price_from_input,
+    };
 
     #[test]
     fn test_get_next_sqrt_price_from_input() {
@@ -652,4 +659,80 @@ mod test {
 
         assert_eq!(amount_0_delta, U256::from(406));
     }
+
+    #[test]
+    fn test_get_amount_delta_min_liquidity() {
+        // -i128::MIN does not fit in an i128 but its magnitude fits in the u128 liquidity
+        let sqrt_ratio_a_x_96 = get_sqrt_ratio_at_tick(-60).unwrap();
+        let sqrt_ratio_b_x_96 = get_sqrt_ratio_at_tick(60).unwrap();
+
+        let result = get_amount_0_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, i128::MIN).unwrap();
+        let expected =
+            _get_amount_0_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, 1 << 127, false).unwrap();
+        assert_eq!(result, -I256::from_raw(expected));
+
+        let result = get_amount_1_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, i128::MIN).unwrap();
+        let expected =
+            _get_amount_1_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, 1 << 127, false).unwrap();
+        assert_eq!(result, -I256::from_raw(expected));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn sqrt_ratio() -> impl Strategy<Value = U256> {
+        (MIN_TICK..=MAX_TICK).prop_map(|tick| get_sqrt_ratio_at_tick(tick).unwrap())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    proptest! {
+        #[test]
+        fn prop_amount_deltas_round_in_the_pools_favor(
+            sqrt_ratio_a_x_96 in sqrt_ratio(),
+            sqrt_ratio_b_x_96 in sqrt_ratio(),
+            liquidity in 0..i128::MAX,
+        ) {
+            let amount_0_up =
+                _get_amount_0_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity as u128, true);
+            let amount_0_down =
+                _get_amount_0_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity as u128, false);
+            if let (Ok(up), Ok(down)) = (amount_0_up, amount_0_down) {
+                prop_assert!(up >= down && up - down <= U256_1);
+
+                // amounts paid to the pool round up, amounts paid by the pool round down
+                let signed = get_amount_0_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity);
+                prop_assert_eq!(signed.unwrap(), I256::from_raw(up));
+                let signed = get_amount_0_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, -liquidity);
+                prop_assert_eq!(signed.unwrap(), -I256::from_raw(down));
+            }
+
+            let amount_1_up =
+                _get_amount_1_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity as u128, true);
+            let amount_1_down =
+                _get_amount_1_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity as u128, false);
+            if let (Ok(up), Ok(down)) = (amount_1_up, amount_1_down) {
+                prop_assert!(up >= down && up - down <= U256_1);
+
+                let signed = get_amount_1_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity);
+                prop_assert_eq!(signed.unwrap(), I256::from_raw(up));
+                let signed = get_amount_1_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, -liquidity);
+                prop_assert_eq!(signed.unwrap(), -I256::from_raw(down));
+            }
+        }
+
+        #[test]
+        fn prop_amount_deltas_are_symmetric(
+            sqrt_ratio_a_x_96 in sqrt_ratio(),
+            sqrt_ratio_b_x_96 in sqrt_ratio(),
+            liquidity in any::<u128>(),
+            round_up in any::<bool>(),
+        ) {
+            prop_assert_eq!(
+                _get_amount_0_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity, round_up).ok(),
+                _get_amount_0_delta(sqrt_ratio_b_x_96, sqrt_ratio_a_x_96, liquidity, round_up).ok()
+            );
+            prop_assert_eq!(
+                _get_amount_1_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity, round_up).ok(),
+                _get_amount_1_delta(sqrt_ratio_b_x_96, sqrt_ratio_a_x_96, liquidity, round_up).ok()
+            );
+        }
+    }
 }
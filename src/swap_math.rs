@@ -3,12 +3,60 @@ use alloy_primitives::{I256, U256};
 use crate::{
     error::UniswapV3MathError,
     full_math::{mul_div, mul_div_rounding_up},
+    low_gas_safe_math,
+    safe_cast::to_int256,
     sqrt_price_math::{
         _get_amount_0_delta, _get_amount_1_delta, get_next_sqrt_price_from_input,
         get_next_sqrt_price_from_output,
     },
 };
 
+//Whether the amount specified for a swap is the exact input or the exact output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapKind {
+    ExactIn,
+    ExactOut,
+}
+
+impl SwapKind {
+    //Splits a signed amountSpecified into its magnitude and kind, positive amounts being exact input
+    pub fn from_amount_specified(amount_specified: I256) -> (U256, SwapKind) {
+        if amount_specified >= I256::ZERO {
+            (amount_specified.into_raw(), SwapKind::ExactIn)
+        } else {
+            (amount_specified.unsigned_abs(), SwapKind::ExactOut)
+        }
+    }
+}
+
+//The result of swapping within a single tick range
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapStep {
+    //The price after swapping the amount in/out, not to exceed the price target
+    pub sqrt_ratio_next_x_96: U256,
+    //The amount to be swapped in, of either token0 or token1, based on the direction of the swap
+    pub amount_in: U256,
+    //The amount to be received, of either token0 or token1, based on the direction of the swap
+    pub amount_out: U256,
+    //The amount of input that will be taken as a fee
+    pub fee_amount: U256,
+}
+
+impl SwapStep {
+    //Returns the signed (amount0, amount1) deltas of the step from the pool's perspective, where a positive
+    //amount is paid into the pool (amount in plus fee) and a negative amount is paid out of it
+    pub fn token_deltas(&self, zero_for_one: bool) -> Result<(I256, I256), UniswapV3MathError> {
+        let paid_in = to_int256(low_gas_safe_math::add(self.amount_in, self.fee_amount)?)?;
+        let paid_out = -to_int256(self.amount_out)?;
+
+        if zero_for_one {
+            Ok((paid_in, paid_out))
+        } else {
+            Ok((paid_out, paid_in))
+        }
+    }
+}
+
 // //returns (
 //         uint160 sqrtRatioNextX96,
 //         uint256 amountIn,
@@ -22,8 +70,37 @@ pub fn compute_swap_step(
     amount_remaining: I256,
     fee_pips: u32,
 ) -> Result<(U256, U256, U256, U256), UniswapV3MathError> {
+    let (amount_remaining, kind) = SwapKind::from_amount_specified(amount_remaining);
+
+    let step = compute_swap_step_with_kind(
+        sqrt_ratio_current_x_96,
+        sqrt_ratio_target_x_96,
+        liquidity,
+        amount_remaining,
+        kind,
+        fee_pips,
+    )?;
+
+    Ok((
+        step.sqrt_ratio_next_x_96,
+        step.amount_in,
+        step.amount_out,
+        step.fee_amount,
+    ))
+}
+
+//Same as compute_swap_step, with the amount remaining given as an unsigned magnitude and an explicit swap kind
+pub fn compute_swap_step_with_kind(
+    sqrt_ratio_current_x_96: U256,
+    sqrt_ratio_target_x_96: U256,
+    liquidity: u128,
+    amount_remaining: U256,
+    kind: SwapKind,
+    fee_pips: u32,
+) -> Result<SwapStep, UniswapV3MathError> {
     let zero_for_one = sqrt_ratio_current_x_96 >= sqrt_ratio_target_x_96;
-    let exact_in = amount_remaining >= I256::ZERO;
+    let exact_in = kind == SwapKind::ExactIn;
+    let fee_complement = low_gas_safe_math::sub(U256::from(1e6 as u32), U256::from(fee_pips))?; //1e6 - fee_pips
 
     let sqrt_ratio_next_x_96: U256;
     let mut amount_in = U256::ZERO;
@@ -31,8 +108,8 @@ pub fn compute_swap_step(
 
     if exact_in {
         let amount_remaining_less_fee = mul_div(
-            amount_remaining.into_raw(),
-            U256::from(1e6 as u32 - fee_pips),    //1e6 - fee_pips
+            amount_remaining,
+            fee_complement,
             U256::from_limbs([1000000, 0, 0, 0]), //1e6
         )?;
 
@@ -79,13 +156,13 @@ pub fn compute_swap_step(
             )?
         };
 
-        sqrt_ratio_next_x_96 = if (-amount_remaining).into_raw() >= amount_out {
+        sqrt_ratio_next_x_96 = if amount_remaining >= amount_out {
             sqrt_ratio_target_x_96
         } else {
             get_next_sqrt_price_from_output(
                 sqrt_ratio_current_x_96,
                 liquidity,
-                (-amount_remaining).into_raw(),
+                amount_remaining,
                 zero_for_one,
             )?
         };
@@ -131,31 +208,35 @@ pub fn compute_swap_step(
         }
     }
 
-    if !exact_in && amount_out > (-amount_remaining).into_raw() {
-        amount_out = (-amount_remaining).into_raw();
+    if !exact_in && amount_out > amount_remaining {
+        amount_out = amount_remaining;
     }
 
-    if exact_in && sqrt_ratio_next_x_96 != sqrt_ratio_target_x_96 {
-        let fee_amount = amount_remaining.into_raw() - amount_in;
-        Ok((sqrt_ratio_next_x_96, amount_in, amount_out, fee_amount))
+    let fee_amount = if exact_in && sqrt_ratio_next_x_96 != sqrt_ratio_target_x_96 {
+        amount_remaining - amount_in
     } else {
-        let fee_amount = mul_div_rounding_up(
-            amount_in,
-            U256::from(fee_pips),
-            U256::from(1e6 as u32 - fee_pips),
-        )?;
-
-        Ok((sqrt_ratio_next_x_96, amount_in, amount_out, fee_amount))
-    }
+        mul_div_rounding_up(amount_in, U256::from(fee_pips), fee_complement)?
+    };
+
+    Ok(SwapStep {
+        sqrt_ratio_next_x_96,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
 }
 
 #[cfg(test)]
 mod test {
 
     use crate::sqrt_price_math::{get_next_sqrt_price_from_input, get_next_sqrt_price_from_output};
-    use crate::swap_math::compute_swap_step;
+    use crate::swap_math::{compute_swap_step, compute_swap_step_with_kind, SwapKind, SwapStep};
+    #[cfg(not(target_arch = "wasm32"))]
+    use crate::tick_math::{get_sqrt_ratio_at_tick, MAX_TICK, MIN_TICK};
     use crate::U256_1;
     use alloy_primitives::{I256, U256};
+    #[cfg(not(target_arch = "wasm32"))]
+    use proptest::prelude::*;
     use std::str::FromStr;
 
     #[allow(unused)]
@@ -389,4 +470,131 @@ mod test {
         assert_eq!(amount_in, U256_1);
         assert_eq!(fee_amount, U256_1);
     }
+
+    #[test]
+    fn test_compute_swap_step_fee_above_one_hundred_percent() {
+        let result = compute_swap_step(
+            U256::from_str("79228162514264337593543950336").unwrap(),
+            U256::from_str("79623317895830914510639640423").unwrap(),
+            2e18 as u128,
+            I256::from_str("1000000000000000000").unwrap(),
+            1_000_001,
+        );
+        assert_eq!(result.unwrap_err().to_string(), "Subtraction underflow");
+    }
+
+    #[test]
+    fn test_compute_swap_step_with_kind() {
+        let price = U256::from_str("79228162514264337593543950336").unwrap();
+        let price_target = U256::from_str("79623317895830914510639640423").unwrap();
+        let liquidity = 2e18 as u128;
+        let amount = U256::from_str("1000000000000000000").unwrap();
+
+        let step = compute_swap_step_with_kind(
+            price,
+            price_target,
+            liquidity,
+            amount,
+            SwapKind::ExactIn,
+            600,
+        )
+        .unwrap();
+
+        assert_eq!(
+            step,
+            SwapStep {
+                sqrt_ratio_next_x_96: price_target,
+                amount_in: U256::from_str("9975124224178055").unwrap(),
+                amount_out: U256::from_str("9925619580021728").unwrap(),
+                fee_amount: U256::from_str("5988667735148").unwrap(),
+            }
+        );
+
+        // one for zero, so token1 is paid in and token0 is paid out
+        let (amount_0, amount_1) = step.token_deltas(false).unwrap();
+        assert_eq!(amount_0, I256::from_str("-9925619580021728").unwrap());
+        assert_eq!(amount_1, I256::from_str("9981112891913203").unwrap());
+
+        let (amount_0, amount_1) = step.token_deltas(true).unwrap();
+        assert_eq!(amount_0, I256::from_str("9981112891913203").unwrap());
+        assert_eq!(amount_1, I256::from_str("-9925619580021728").unwrap());
+
+        // matches the signed api for exact output
+        let step = compute_swap_step_with_kind(
+            price,
+            price_target,
+            liquidity,
+            amount,
+            SwapKind::ExactOut,
+            600,
+        )
+        .unwrap();
+        let (sqrt_p, amount_in, amount_out, fee_amount) =
+            compute_swap_step(price, price_target, liquidity, -I256::from_raw(amount), 600)
+                .unwrap();
+        assert_eq!(step.sqrt_ratio_next_x_96, sqrt_p);
+        assert_eq!(step.amount_in, amount_in);
+        assert_eq!(step.amount_out, amount_out);
+        assert_eq!(step.fee_amount, fee_amount);
+    }
+
+    #[test]
+    fn test_swap_kind_from_amount_specified() {
+        assert_eq!(
+            SwapKind::from_amount_specified(I256::ZERO),
+            (U256::ZERO, SwapKind::ExactIn)
+        );
+        assert_eq!(
+            SwapKind::from_amount_specified(I256::MINUS_ONE),
+            (U256_1, SwapKind::ExactOut)
+        );
+        assert_eq!(
+            SwapKind::from_amount_specified(I256::MIN),
+            (U256_1 << 255, SwapKind::ExactOut)
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn sqrt_ratio() -> impl Strategy<Value = U256> {
+        (MIN_TICK..=MAX_TICK).prop_map(|tick| get_sqrt_ratio_at_tick(tick).unwrap())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    proptest! {
+        #[test]
+        fn prop_compute_swap_step_respects_amount_remaining(
+            sqrt_ratio_current_x_96 in sqrt_ratio(),
+            sqrt_ratio_target_x_96 in sqrt_ratio(),
+            liquidity in any::<u128>(),
+            amount_remaining in any::<i128>(),
+            fee_pips in 0..1_000_000u32,
+        ) {
+            let amount_remaining = I256::try_from(amount_remaining).unwrap();
+            let Ok((sqrt_ratio_next_x_96, amount_in, amount_out, fee_amount)) = compute_swap_step(
+                sqrt_ratio_current_x_96,
+                sqrt_ratio_target_x_96,
+                liquidity,
+                amount_remaining,
+                fee_pips,
+            ) else {
+                return Ok(());
+            };
+
+            if amount_remaining >= I256::ZERO {
+                // exact in never consumes more than the amount remaining, fee included
+                prop_assert!(amount_in + fee_amount <= amount_remaining.into_raw());
+            } else {
+                prop_assert!(amount_out <= amount_remaining.unsigned_abs());
+            }
+
+            // the next price always lies between the current price and the target
+            if sqrt_ratio_current_x_96 >= sqrt_ratio_target_x_96 {
+                prop_assert!(sqrt_ratio_next_x_96 <= sqrt_ratio_current_x_96);
+                prop_assert!(sqrt_ratio_next_x_96 >= sqrt_ratio_target_x_96);
+            } else {
+                prop_assert!(sqrt_ratio_next_x_96 >= sqrt_ratio_current_x_96);
+                prop_assert!(sqrt_ratio_next_x_96 <= sqrt_ratio_target_x_96);
+            }
+        }
+    }
 }
@@ -0,0 +1,195 @@
+use std::fmt::Write;
+
+use alloy_primitives::{I256, U256};
+
+//A single iteration of the swap loop: the inputs and outputs of computeSwapStep and the state transition that follows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapStepTrace {
+    //The tick bitmap word loaded to find the next tick
+    pub word_position: i16,
+    //The next initialized tick, or the boundary of the bitmap word, and whether it is initialized
+    pub tick_next: i32,
+    pub initialized: bool,
+    //The inputs of computeSwapStep, with the amount remaining signed as amountSpecified
+    pub sqrt_price_start_x_96: U256,
+    pub sqrt_price_target_x_96: U256,
+    pub liquidity: u128,
+    pub amount_remaining: I256,
+    //The outputs of computeSwapStep, with the fee amount before the protocol and gauge take their share
+    pub sqrt_price_next_x_96: U256,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub fee_amount: U256,
+    pub protocol_fee: U256,
+    pub gauge_fee: U256,
+    //feeGrowthGlobalX128 of the input token before and after the step
+    pub fee_growth_global_before_x_128: U256,
+    pub fee_growth_global_after_x_128: U256,
+    //The initialized tick crossed at the end of the step, if any, and the liquidity net applied in the swap direction
+    pub tick_crossed: Option<i32>,
+    pub liquidity_net: i128,
+    //The state after the step
+    pub liquidity_after: u128,
+    pub tick_after: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SwapTrace {
+    pub steps: Vec<SwapStepTrace>,
+}
+
+const CSV_HEADER: &str = "step,wordPosition,tickNext,initialized,sqrtPriceStartX96,sqrtPriceTargetX96,liquidity,\
+amountRemaining,sqrtPriceNextX96,amountIn,amountOut,feeAmount,protocolFee,gaugeFee,feeGrowthGlobalBeforeX128,\
+feeGrowthGlobalAfterX128,tickCrossed,liquidityNet,liquidityAfter,tickAfter";
+
+impl SwapTrace {
+    //Returns the ticks crossed by the swap, in order
+    pub fn ticks_crossed(&self) -> Vec<i32> {
+        self.steps
+            .iter()
+            .filter_map(|step| step.tick_crossed)
+            .collect()
+    }
+
+    //Exports the steps as CSV with a header row, leaving tickCrossed empty for steps that crossed no tick
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{CSV_HEADER}\n");
+        for (index, step) in self.steps.iter().enumerate() {
+            let tick_crossed = step
+                .tick_crossed
+                .map(|tick| tick.to_string())
+                .unwrap_or_default();
+            // writing to a String cannot fail
+            let _ = writeln!(
+                csv,
+                "{index},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{tick_crossed},{},{},{}",
+                step.word_position,
+                step.tick_next,
+                step.initialized,
+                step.sqrt_price_start_x_96,
+                step.sqrt_price_target_x_96,
+                step.liquidity,
+                step.amount_remaining,
+                step.sqrt_price_next_x_96,
+                step.amount_in,
+                step.amount_out,
+                step.fee_amount,
+                step.protocol_fee,
+                step.gauge_fee,
+                step.fee_growth_global_before_x_128,
+                step.fee_growth_global_after_x_128,
+                step.liquidity_net,
+                step.liquidity_after,
+                step.tick_after,
+            );
+        }
+        csv
+    }
+
+    //Formats the steps for reading, one block per step
+    pub fn pretty(&self) -> String {
+        let mut output = String::new();
+        for (index, step) in self.steps.iter().enumerate() {
+            let _ = writeln!(
+                output,
+                "step {index}: next tick {}{}, tick after {}{}",
+                step.tick_next,
+                if step.initialized {
+                    " (initialized)"
+                } else {
+                    ""
+                },
+                step.tick_after,
+                match step.tick_crossed {
+                    Some(tick) =>
+                        format!(", crossed {tick} (liquidity net {})", step.liquidity_net),
+                    None => String::new(),
+                }
+            );
+            let _ = writeln!(
+                output,
+                "  sqrt price      {} -> {} (target {})",
+                step.sqrt_price_start_x_96, step.sqrt_price_next_x_96, step.sqrt_price_target_x_96
+            );
+            let _ = writeln!(
+                output,
+                "  liquidity       {} -> {}",
+                step.liquidity, step.liquidity_after
+            );
+            let _ = writeln!(
+                output,
+                "  amount          remaining {}, in {}, out {}",
+                step.amount_remaining, step.amount_in, step.amount_out
+            );
+            let _ = writeln!(
+                output,
+                "  fee             {} (protocol {}, gauge {})",
+                step.fee_amount, step.protocol_fee, step.gauge_fee
+            );
+            let _ = writeln!(
+                output,
+                "  fee growth      {} -> {}",
+                step.fee_growth_global_before_x_128, step.fee_growth_global_after_x_128
+            );
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_utils::new_pool, tick_math::MIN_SQRT_RATIO};
+    use alloy_primitives::Address;
+
+    fn trace() -> SwapTrace {
+        let mut pool = new_pool(
+            Address::ZERO,
+            &[(-120, 120, 1e18 as u128), (-600, 600, 1e18 as u128)],
+        );
+        let (_, trace) = pool
+            .swap_with_trace(
+                true,
+                I256::try_from(2e16 as i128).unwrap(),
+                MIN_SQRT_RATIO + U256::from(1),
+            )
+            .unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let trace = trace();
+        let csv = trace.to_csv();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), trace.steps.len() + 1);
+
+        // every row has a value for each column, the crossing step names its tick
+        let columns = lines[0].split(',').collect::<Vec<_>>();
+        assert_eq!(columns.len(), 20);
+        let tick_crossed = columns.iter().position(|c| *c == "tickCrossed").unwrap();
+        for (line, step) in lines[1..].iter().zip(&trace.steps) {
+            let values = line.split(',').collect::<Vec<_>>();
+            assert_eq!(values.len(), columns.len());
+            assert_eq!(
+                values[tick_crossed],
+                step.tick_crossed.map(|t| t.to_string()).unwrap_or_default()
+            );
+        }
+        assert!(lines[1..].iter().any(|line| line.contains(",-120,")));
+    }
+
+    #[test]
+    fn test_pretty() {
+        let trace = trace();
+        let pretty = trace.pretty();
+        // starting on a word boundary, the first step moves nothing
+        assert!(pretty.starts_with("step 0: next tick 0, tick after -1\n"));
+        assert!(pretty.contains(
+            "step 1: next tick -120 (initialized), tick after -121, crossed -120 (liquidity net \
+             -1000000000000000000)\n"
+        ));
+        assert_eq!(pretty.matches("step ").count(), trace.steps.len());
+        assert_eq!(SwapTrace::default().pretty(), "");
+    }
+}
@@ -0,0 +1,21 @@
+//Fixtures shared by the unit tests
+
+use alloy_primitives::{Address, U256};
+
+use crate::{
+    dialect::{Dialect, PoolConfig},
+    pool::Pool,
+};
+
+//A sqrt price of 1 in Q64.96
+pub const SQRT_PRICE_1_1: U256 = U256::from_limbs([0, 4294967296, 0, 0]);
+
+//A UniswapV3 pool with a 0.3% fee at a price of 1, with each (tickLower, tickUpper, liquidity) range minted to owner
+pub fn new_pool(owner: Address, ranges: &[(i32, i32, u128)]) -> Pool {
+    let config = PoolConfig::new(Dialect::UniswapV3, 3000).unwrap();
+    let mut pool = Pool::initialize(config, SQRT_PRICE_1_1).unwrap();
+    for &(tick_lower, tick_upper, liquidity) in ranges {
+        pool.mint(owner, tick_lower, tick_upper, liquidity).unwrap();
+    }
+    pool
+}
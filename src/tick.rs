@@ -1,5 +1,14 @@
+use std::collections::HashMap;
+
 use alloy_primitives::U256;
 
+use crate::{
+    error::UniswapV3MathError,
+    liquidity_math,
+    tick_math::{MAX_TICK, MIN_TICK},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Tick {
     pub liquidity_gross: u128,
     pub liquidity_net: i128,
@@ -10,3 +19,408 @@ pub struct Tick {
     pub seconds_outside: u32,
     pub initialized: bool,
 }
+
+//Derives max liquidity per tick from given tick spacing
+pub fn tick_spacing_to_max_liquidity_per_tick(
+    tick_spacing: i32,
+) -> Result<u128, UniswapV3MathError> {
+    if tick_spacing <= 0 {
+        return Err(UniswapV3MathError::TickSpacingOutOfBounds(tick_spacing));
+    }
+
+    let min_tick = (MIN_TICK / tick_spacing) * tick_spacing;
+    let max_tick = (MAX_TICK / tick_spacing) * tick_spacing;
+    let num_ticks = ((max_tick - min_tick) / tick_spacing) as u128 + 1;
+    Ok(u128::MAX / num_ticks)
+}
+
+//Common checks for valid tick inputs
+pub fn check_ticks(tick_lower: i32, tick_upper: i32) -> Result<(), UniswapV3MathError> {
+    if tick_lower >= tick_upper {
+        return Err(UniswapV3MathError::TicksMisordered(tick_lower, tick_upper));
+    }
+    if tick_lower < MIN_TICK {
+        return Err(UniswapV3MathError::TickLowerOutOfBounds(tick_lower));
+    }
+    if tick_upper > MAX_TICK {
+        return Err(UniswapV3MathError::TickUpperOutOfBounds(tick_upper));
+    }
+    Ok(())
+}
+
+// returns (uint256 feeGrowthInside0X128, uint256 feeGrowthInside1X128)
+pub fn get_fee_growth_inside(
+    ticks: &HashMap<i32, Tick>,
+    tick_lower: i32,
+    tick_upper: i32,
+    tick_current: i32,
+    fee_growth_global_0_x_128: U256,
+    fee_growth_global_1_x_128: U256,
+) -> (U256, U256) {
+    let lower = ticks.get(&tick_lower).copied().unwrap_or_default();
+    let upper = ticks.get(&tick_upper).copied().unwrap_or_default();
+
+    // calculate fee growth below
+    let (fee_growth_below_0_x_128, fee_growth_below_1_x_128) = if tick_current >= tick_lower {
+        (
+            lower.fee_growth_outside_0_x_128,
+            lower.fee_growth_outside_1_x_128,
+        )
+    } else {
+        (
+            fee_growth_global_0_x_128.wrapping_sub(lower.fee_growth_outside_0_x_128),
+            fee_growth_global_1_x_128.wrapping_sub(lower.fee_growth_outside_1_x_128),
+        )
+    };
+
+    // calculate fee growth above
+    let (fee_growth_above_0_x_128, fee_growth_above_1_x_128) = if tick_current < tick_upper {
+        (
+            upper.fee_growth_outside_0_x_128,
+            upper.fee_growth_outside_1_x_128,
+        )
+    } else {
+        (
+            fee_growth_global_0_x_128.wrapping_sub(upper.fee_growth_outside_0_x_128),
+            fee_growth_global_1_x_128.wrapping_sub(upper.fee_growth_outside_1_x_128),
+        )
+    };
+
+    (
+        fee_growth_global_0_x_128
+            .wrapping_sub(fee_growth_below_0_x_128)
+            .wrapping_sub(fee_growth_above_0_x_128),
+        fee_growth_global_1_x_128
+            .wrapping_sub(fee_growth_below_1_x_128)
+            .wrapping_sub(fee_growth_above_1_x_128),
+    )
+}
+
+//Updates a tick and returns true if the tick was flipped from initialized to uninitialized, or vice versa
+#[allow(clippy::too_many_arguments)]
+pub fn update(
+    ticks: &mut HashMap<i32, Tick>,
+    tick: i32,
+    tick_current: i32,
+    liquidity_delta: i128,
+    fee_growth_global_0_x_128: U256,
+    fee_growth_global_1_x_128: U256,
+    seconds_per_liquidity_cumulative_x_128: U256,
+    tick_cumulative: U256,
+    time: u32,
+    upper: bool,
+    max_liquidity: u128,
+) -> Result<bool, UniswapV3MathError> {
+    let info = ticks.entry(tick).or_default();
+
+    let liquidity_gross_before = info.liquidity_gross;
+    let liquidity_gross_after = liquidity_math::add_delta(liquidity_gross_before, liquidity_delta)?;
+
+    if liquidity_gross_after > max_liquidity {
+        return Err(UniswapV3MathError::LO);
+    }
+
+    let flipped = (liquidity_gross_after == 0) != (liquidity_gross_before == 0);
+
+    if liquidity_gross_before == 0 {
+        // by convention, we assume that all growth before a tick was initialized happened _below_ the tick
+        if tick <= tick_current {
+            info.fee_growth_outside_0_x_128 = fee_growth_global_0_x_128;
+            info.fee_growth_outside_1_x_128 = fee_growth_global_1_x_128;
+            info.seconds_per_liquidity_outside_x_128 = seconds_per_liquidity_cumulative_x_128;
+            info.tick_cumulative_outside = tick_cumulative;
+            info.seconds_outside = time;
+        }
+        info.initialized = true;
+    }
+
+    info.liquidity_gross = liquidity_gross_after;
+
+    // when the lower (upper) tick is crossed left to right (right to left), liquidity must be added (removed)
+    info.liquidity_net = if upper {
+        info.liquidity_net.checked_sub(liquidity_delta)
+    } else {
+        info.liquidity_net.checked_add(liquidity_delta)
+    }
+    .ok_or(UniswapV3MathError::SafeCastToI128Overflow)?;
+
+    Ok(flipped)
+}
+
+//Clears tick data
+pub fn clear(ticks: &mut HashMap<i32, Tick>, tick: i32) {
+    ticks.remove(&tick);
+}
+
+//Transitions to next tick as needed by price movement, returning the amount of liquidity added (subtracted) when
+//the tick is crossed from left to right (right to left)
+pub fn cross(
+    ticks: &mut HashMap<i32, Tick>,
+    tick: i32,
+    fee_growth_global_0_x_128: U256,
+    fee_growth_global_1_x_128: U256,
+    seconds_per_liquidity_cumulative_x_128: U256,
+    tick_cumulative: U256,
+    time: u32,
+) -> i128 {
+    let info = ticks.entry(tick).or_default();
+
+    info.fee_growth_outside_0_x_128 =
+        fee_growth_global_0_x_128.wrapping_sub(info.fee_growth_outside_0_x_128);
+    info.fee_growth_outside_1_x_128 =
+        fee_growth_global_1_x_128.wrapping_sub(info.fee_growth_outside_1_x_128);
+    info.seconds_per_liquidity_outside_x_128 = seconds_per_liquidity_cumulative_x_128
+        .wrapping_sub(info.seconds_per_liquidity_outside_x_128);
+    info.tick_cumulative_outside = tick_cumulative.wrapping_sub(info.tick_cumulative_outside);
+    info.seconds_outside = time.wrapping_sub(info.seconds_outside);
+
+    info.liquidity_net
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tick_spacing_to_max_liquidity_per_tick() {
+        // returns the correct value for low fee
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(10).unwrap(),
+            1917569901783203986719870431555990
+        );
+        // returns the correct value for medium fee
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(60).unwrap(),
+            11505743598341114571880798222544994
+        );
+        // returns the correct value for high fee
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(200).unwrap(),
+            38350317471085141830651933667504588
+        );
+        // returns the correct value for the entire range
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(887272).unwrap(),
+            u128::MAX / 3
+        );
+        // returns the correct value for 2302
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(2302).unwrap(),
+            441351967472034323558203122479595605
+        );
+        // reverts for a tick spacing that is not positive
+        for tick_spacing in [0, -60] {
+            assert!(matches!(
+                tick_spacing_to_max_liquidity_per_tick(tick_spacing),
+                Err(UniswapV3MathError::TickSpacingOutOfBounds(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_get_fee_growth_inside() {
+        let mut ticks = HashMap::new();
+
+        // returns all for two uninitialized ticks if tick is inside
+        let (fee_growth_inside_0, fee_growth_inside_1) =
+            get_fee_growth_inside(&ticks, -2, 2, 0, U256::from(15), U256::from(15));
+        assert_eq!(fee_growth_inside_0, U256::from(15));
+        assert_eq!(fee_growth_inside_1, U256::from(15));
+
+        // returns 0 for two uninitialized ticks if tick is above
+        let (fee_growth_inside_0, fee_growth_inside_1) =
+            get_fee_growth_inside(&ticks, -2, 2, 4, U256::from(15), U256::from(15));
+        assert_eq!(fee_growth_inside_0, U256::ZERO);
+        assert_eq!(fee_growth_inside_1, U256::ZERO);
+
+        // subtracts upper tick if below
+        ticks.insert(
+            2,
+            Tick {
+                fee_growth_outside_0_x_128: U256::from(2),
+                fee_growth_outside_1_x_128: U256::from(3),
+                liquidity_gross: 0,
+                liquidity_net: 0,
+                initialized: true,
+                ..Default::default()
+            },
+        );
+        let (fee_growth_inside_0, fee_growth_inside_1) =
+            get_fee_growth_inside(&ticks, -2, 2, 0, U256::from(15), U256::from(15));
+        assert_eq!(fee_growth_inside_0, U256::from(13));
+        assert_eq!(fee_growth_inside_1, U256::from(12));
+
+        // works correctly with overflow on inside tick
+        ticks.insert(
+            -2,
+            Tick {
+                fee_growth_outside_0_x_128: U256::MAX - U256::from(3),
+                fee_growth_outside_1_x_128: U256::MAX - U256::from(2),
+                initialized: true,
+                ..Default::default()
+            },
+        );
+        ticks.insert(
+            2,
+            Tick {
+                fee_growth_outside_0_x_128: U256::from(3),
+                fee_growth_outside_1_x_128: U256::from(5),
+                initialized: true,
+                ..Default::default()
+            },
+        );
+        let (fee_growth_inside_0, fee_growth_inside_1) =
+            get_fee_growth_inside(&ticks, -2, 2, 0, U256::from(15), U256::from(15));
+        assert_eq!(fee_growth_inside_0, U256::from(16));
+        assert_eq!(fee_growth_inside_1, U256::from(13));
+    }
+
+    #[test]
+    fn test_update() {
+        let mut ticks = HashMap::new();
+        let zero = U256::ZERO;
+
+        // flips from zero to nonzero
+        let flipped = update(&mut ticks, 0, 0, 1, zero, zero, zero, zero, 0, false, 3).unwrap();
+        assert!(flipped);
+
+        // does not flip from nonzero to greater nonzero
+        let flipped = update(&mut ticks, 0, 0, 1, zero, zero, zero, zero, 0, false, 3).unwrap();
+        assert!(!flipped);
+
+        // reverts if total liquidity gross is greater than max
+        let result = update(&mut ticks, 0, 0, 2, zero, zero, zero, zero, 0, true, 3);
+        assert!(matches!(result, Err(UniswapV3MathError::LO)));
+
+        // nets the liquidity based on upper flag
+        update(&mut ticks, 0, 0, 1, zero, zero, zero, zero, 0, true, 10).unwrap();
+        assert_eq!(ticks[&0].liquidity_gross, 3);
+        assert_eq!(ticks[&0].liquidity_net, 1);
+
+        // reverts if the liquidity net overflows
+        ticks.insert(
+            2,
+            Tick {
+                liquidity_gross: 1,
+                liquidity_net: i128::MAX,
+                initialized: true,
+                ..Default::default()
+            },
+        );
+        let result = update(
+            &mut ticks,
+            2,
+            0,
+            1,
+            zero,
+            zero,
+            zero,
+            zero,
+            0,
+            false,
+            u128::MAX,
+        );
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::SafeCastToI128Overflow)
+        ));
+        ticks.remove(&2);
+
+        // flips from nonzero to zero
+        let flipped = update(&mut ticks, 0, 0, -3, zero, zero, zero, zero, 0, false, 10).unwrap();
+        assert!(flipped);
+
+        // assumes all growth happens below ticks lte current tick
+        let flipped = update(
+            &mut ticks,
+            1,
+            1,
+            1,
+            U256::from(1),
+            U256::from(2),
+            U256::from(3),
+            U256::from(4),
+            5,
+            false,
+            u128::MAX,
+        )
+        .unwrap();
+        assert!(flipped);
+        assert_eq!(ticks[&1].fee_growth_outside_0_x_128, U256::from(1));
+        assert_eq!(ticks[&1].fee_growth_outside_1_x_128, U256::from(2));
+        assert_eq!(ticks[&1].seconds_per_liquidity_outside_x_128, U256::from(3));
+        assert_eq!(ticks[&1].tick_cumulative_outside, U256::from(4));
+        assert_eq!(ticks[&1].seconds_outside, 5);
+        assert!(ticks[&1].initialized);
+
+        // does not set any growth fields for ticks gt current tick
+        update(
+            &mut ticks,
+            2,
+            1,
+            1,
+            U256::from(1),
+            U256::from(2),
+            U256::from(3),
+            U256::from(4),
+            5,
+            false,
+            u128::MAX,
+        )
+        .unwrap();
+        assert_eq!(ticks[&2].fee_growth_outside_0_x_128, zero);
+        assert_eq!(ticks[&2].seconds_outside, 0);
+
+        clear(&mut ticks, 2);
+        assert!(!ticks.contains_key(&2));
+    }
+
+    #[test]
+    fn test_cross() {
+        let mut ticks = HashMap::new();
+        ticks.insert(
+            2,
+            Tick {
+                fee_growth_outside_0_x_128: U256::from(1),
+                fee_growth_outside_1_x_128: U256::from(2),
+                liquidity_gross: 3,
+                liquidity_net: 4,
+                seconds_per_liquidity_outside_x_128: U256::from(5),
+                tick_cumulative_outside: U256::from(6),
+                seconds_outside: 7,
+                initialized: true,
+            },
+        );
+
+        // flips the growth variables
+        let liquidity_net = cross(
+            &mut ticks,
+            2,
+            U256::from(7),
+            U256::from(9),
+            U256::from(8),
+            U256::from(15),
+            10,
+        );
+        assert_eq!(liquidity_net, 4);
+        assert_eq!(ticks[&2].fee_growth_outside_0_x_128, U256::from(6));
+        assert_eq!(ticks[&2].fee_growth_outside_1_x_128, U256::from(7));
+        assert_eq!(ticks[&2].seconds_per_liquidity_outside_x_128, U256::from(3));
+        assert_eq!(ticks[&2].tick_cumulative_outside, U256::from(9));
+        assert_eq!(ticks[&2].seconds_outside, 3);
+
+        // two flips are no op
+        cross(
+            &mut ticks,
+            2,
+            U256::from(7),
+            U256::from(9),
+            U256::from(8),
+            U256::from(15),
+            10,
+        );
+        assert_eq!(ticks[&2].fee_growth_outside_0_x_128, U256::from(1));
+        assert_eq!(ticks[&2].fee_growth_outside_1_x_128, U256::from(2));
+        assert_eq!(ticks[&2].seconds_outside, 7);
+    }
+}
@@ -229,6 +229,8 @@ pub fn get_tick_at_sqrt_ratio(sqrt_price_x_96: U256) -> Result<i32, UniswapV3Mat
 #[cfg(test)]
 mod test {
     use super::*;
+    #[cfg(not(target_arch = "wasm32"))]
+    use proptest::prelude::*;
     use std::{ops::Sub, str::FromStr};
 
     #[test]
@@ -360,4 +362,35 @@ mod test {
         let result = get_tick_at_sqrt_ratio(U256::from_str("4295343490").unwrap()).unwrap();
         assert_eq!(result, MIN_TICK + 1);
     }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    proptest! {
+        #[test]
+        fn prop_get_tick_at_sqrt_ratio_round_trips(tick in MIN_TICK..MAX_TICK) {
+            let sqrt_ratio = get_sqrt_ratio_at_tick(tick).unwrap();
+            prop_assert_eq!(get_tick_at_sqrt_ratio(sqrt_ratio).unwrap(), tick);
+        }
+
+        #[test]
+        fn prop_get_sqrt_ratio_at_tick_is_monotonic(tick in MIN_TICK..MAX_TICK) {
+            let lower = get_sqrt_ratio_at_tick(tick).unwrap();
+            let upper = get_sqrt_ratio_at_tick(tick + 1).unwrap();
+            prop_assert!(lower < upper);
+            prop_assert!(lower >= MIN_SQRT_RATIO && upper <= MAX_SQRT_RATIO);
+        }
+
+        #[test]
+        fn prop_get_tick_at_sqrt_ratio_is_greatest_tick_below(
+            limbs in any::<[u64; 3]>()
+        ) {
+            // map the random 192 bits into [MIN_SQRT_RATIO, MAX_SQRT_RATIO)
+            let sqrt_ratio = MIN_SQRT_RATIO
+                + U256::from_limbs([limbs[0], limbs[1], limbs[2], 0])
+                    % (MAX_SQRT_RATIO - MIN_SQRT_RATIO);
+            let tick = get_tick_at_sqrt_ratio(sqrt_ratio).unwrap();
+
+            prop_assert!(get_sqrt_ratio_at_tick(tick).unwrap() <= sqrt_ratio);
+            prop_assert!(get_sqrt_ratio_at_tick(tick + 1).unwrap() > sqrt_ratio);
+        }
+    }
 }
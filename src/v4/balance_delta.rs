@@ -0,0 +1,70 @@
+use crate::error::UniswapV3MathError;
+
+//Two signed int128 amounts of currency0 and currency1 from the caller's perspective. A negative amount is owed by
+//the caller to the pool and a positive amount is owed by the pool to the caller.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BalanceDelta {
+    pub amount_0: i128,
+    pub amount_1: i128,
+}
+
+pub const ZERO_DELTA: BalanceDelta = BalanceDelta {
+    amount_0: 0,
+    amount_1: 0,
+};
+
+impl BalanceDelta {
+    pub const fn new(amount_0: i128, amount_1: i128) -> Self {
+        BalanceDelta { amount_0, amount_1 }
+    }
+
+    //Adds two deltas, erroring on overflow like BalanceDeltaLibrary
+    pub fn checked_add(self, other: BalanceDelta) -> Result<BalanceDelta, UniswapV3MathError> {
+        Ok(BalanceDelta {
+            amount_0: self
+                .amount_0
+                .checked_add(other.amount_0)
+                .ok_or(UniswapV3MathError::SafeCastToI128Overflow)?,
+            amount_1: self
+                .amount_1
+                .checked_add(other.amount_1)
+                .ok_or(UniswapV3MathError::SafeCastToI128Overflow)?,
+        })
+    }
+
+    //Subtracts two deltas, erroring on overflow like BalanceDeltaLibrary
+    pub fn checked_sub(self, other: BalanceDelta) -> Result<BalanceDelta, UniswapV3MathError> {
+        Ok(BalanceDelta {
+            amount_0: self
+                .amount_0
+                .checked_sub(other.amount_0)
+                .ok_or(UniswapV3MathError::SafeCastToI128Overflow)?,
+            amount_1: self
+                .amount_1
+                .checked_sub(other.amount_1)
+                .ok_or(UniswapV3MathError::SafeCastToI128Overflow)?,
+        })
+    }
+}
+
+//The delta returned by a beforeSwap hook, in the specified and unspecified currencies of the swap rather than
+//currency0 and currency1
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BeforeSwapDelta {
+    pub specified: i128,
+    pub unspecified: i128,
+}
+
+pub const ZERO_BEFORE_SWAP_DELTA: BeforeSwapDelta = BeforeSwapDelta {
+    specified: 0,
+    unspecified: 0,
+};
+
+impl BeforeSwapDelta {
+    pub const fn new(specified: i128, unspecified: i128) -> Self {
+        BeforeSwapDelta {
+            specified,
+            unspecified,
+        }
+    }
+}
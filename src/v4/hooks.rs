@@ -0,0 +1,108 @@
+use alloy_primitives::{Address, U256};
+
+use crate::{
+    error::UniswapV3MathError,
+    v4::{
+        balance_delta::{BalanceDelta, BeforeSwapDelta, ZERO_BEFORE_SWAP_DELTA, ZERO_DELTA},
+        pool::Pool,
+        pool_manager::{ModifyLiquidityParams, PoolKey, SwapParams},
+    },
+};
+
+//Callbacks invoked by the pool manager around each pool action. Every callback defaults to a no-op, so an
+//implementation only overrides the hooks it would have permission flags for on chain. Deltas returned by hooks are
+//from the hook's perspective, a positive amount is owed to the hook.
+pub trait Hooks {
+    fn before_initialize(
+        &mut self,
+        _key: &PoolKey,
+        _sqrt_price_x_96: U256,
+    ) -> Result<(), UniswapV3MathError> {
+        Ok(())
+    }
+
+    fn after_initialize(
+        &mut self,
+        _key: &PoolKey,
+        _sqrt_price_x_96: U256,
+        _tick: i32,
+    ) -> Result<(), UniswapV3MathError> {
+        Ok(())
+    }
+
+    fn before_modify_liquidity(
+        &mut self,
+        _key: &PoolKey,
+        _pool: &Pool,
+        _sender: Address,
+        _params: &ModifyLiquidityParams,
+    ) -> Result<(), UniswapV3MathError> {
+        Ok(())
+    }
+
+    //Returns the hook's delta, which is taken out of the caller's delta
+    fn after_modify_liquidity(
+        &mut self,
+        _key: &PoolKey,
+        _pool: &Pool,
+        _sender: Address,
+        _params: &ModifyLiquidityParams,
+        _delta: BalanceDelta,
+        _fees_accrued: BalanceDelta,
+    ) -> Result<BalanceDelta, UniswapV3MathError> {
+        Ok(ZERO_DELTA)
+    }
+
+    //Returns the hook's delta in the specified and unspecified currencies, and an lp fee override which is only
+    //used by dynamic fee pools when it has the override flag set
+    // returns (BeforeSwapDelta hookDelta, uint24 lpFeeOverride)
+    fn before_swap(
+        &mut self,
+        _key: &PoolKey,
+        _pool: &Pool,
+        _sender: Address,
+        _params: &SwapParams,
+    ) -> Result<(BeforeSwapDelta, u32), UniswapV3MathError> {
+        Ok((ZERO_BEFORE_SWAP_DELTA, 0))
+    }
+
+    //Returns the hook's delta in the unspecified currency
+    fn after_swap(
+        &mut self,
+        _key: &PoolKey,
+        _pool: &Pool,
+        _sender: Address,
+        _params: &SwapParams,
+        _delta: BalanceDelta,
+    ) -> Result<i128, UniswapV3MathError> {
+        Ok(0)
+    }
+
+    fn before_donate(
+        &mut self,
+        _key: &PoolKey,
+        _pool: &Pool,
+        _sender: Address,
+        _amount_0: U256,
+        _amount_1: U256,
+    ) -> Result<(), UniswapV3MathError> {
+        Ok(())
+    }
+
+    fn after_donate(
+        &mut self,
+        _key: &PoolKey,
+        _pool: &Pool,
+        _sender: Address,
+        _amount_0: U256,
+        _amount_1: U256,
+    ) -> Result<(), UniswapV3MathError> {
+        Ok(())
+    }
+}
+
+//A pool without a hook contract
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoHooks;
+
+impl Hooks for NoHooks {}
@@ -0,0 +1,83 @@
+use crate::error::UniswapV3MathError;
+
+//An lp fee of exactly 0b1000000... signals a dynamic fee pool. This isn't a valid static fee as it is > MAX_LP_FEE
+pub const DYNAMIC_FEE_FLAG: u32 = 0x800000;
+//The second bit of the fee returned by beforeSwap is used to signal if the stored LP fee should be overridden
+pub const OVERRIDE_FEE_FLAG: u32 = 0x400000;
+//Mask to remove the override fee flag from a fee returned by the beforeSwap hook
+pub const REMOVE_OVERRIDE_MASK: u32 = 0xBFFFFF;
+//The lp fee is represented in hundredths of a bip, so the max is 100%
+pub const MAX_LP_FEE: u32 = 1_000_000;
+
+//Returns true if the pool was created with a dynamic fee
+pub fn is_dynamic_fee(fee: u32) -> bool {
+    fee == DYNAMIC_FEE_FLAG
+}
+
+//Returns true if the fee is at most 100%
+pub fn is_valid(fee: u32) -> bool {
+    fee <= MAX_LP_FEE
+}
+
+//Errors if the fee is larger than 100%
+pub fn validate(fee: u32) -> Result<(), UniswapV3MathError> {
+    if is_valid(fee) {
+        Ok(())
+    } else {
+        Err(UniswapV3MathError::LPFeeTooLarge(fee))
+    }
+}
+
+//Returns the lp fee a pool is initialized with, dynamic fee pools start at 0
+pub fn get_initial_lp_fee(fee: u32) -> Result<u32, UniswapV3MathError> {
+    if is_dynamic_fee(fee) {
+        return Ok(0);
+    }
+    validate(fee)?;
+    Ok(fee)
+}
+
+//Returns true if the fee returned by beforeSwap has the override flag set
+pub fn is_override(fee: u32) -> bool {
+    fee & OVERRIDE_FEE_FLAG != 0
+}
+
+pub fn remove_override_flag(fee: u32) -> u32 {
+    fee & REMOVE_OVERRIDE_MASK
+}
+
+//Removes the override flag and errors if the remaining fee is larger than 100%
+pub fn remove_override_flag_and_validate(fee: u32) -> Result<u32, UniswapV3MathError> {
+    let fee = remove_override_flag(fee);
+    validate(fee)?;
+    Ok(fee)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_initial_lp_fee() {
+        assert_eq!(get_initial_lp_fee(3000).unwrap(), 3000);
+        assert_eq!(get_initial_lp_fee(MAX_LP_FEE).unwrap(), MAX_LP_FEE);
+        assert_eq!(get_initial_lp_fee(DYNAMIC_FEE_FLAG).unwrap(), 0);
+
+        let result = get_initial_lp_fee(MAX_LP_FEE + 1);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "LP fee 1000001 is larger than 100%"
+        );
+    }
+
+    #[test]
+    fn test_override_flag() {
+        assert!(!is_override(3000));
+        assert!(is_override(3000 | OVERRIDE_FEE_FLAG));
+        assert_eq!(
+            remove_override_flag_and_validate(3000 | OVERRIDE_FEE_FLAG).unwrap(),
+            3000
+        );
+        assert!(remove_override_flag_and_validate((MAX_LP_FEE + 1) | OVERRIDE_FEE_FLAG).is_err());
+    }
+}
@@ -0,0 +1,11 @@
+//! Ports of the Uniswap v4 core libraries that differ from their v3 counterparts. Libraries whose
+//! semantics are unchanged in v4 (`FullMath`, `SqrtPriceMath`, `TickMath`, ...) are shared with the
+//! v3 modules of this crate.
+
+pub mod balance_delta;
+pub mod hooks;
+pub mod lp_fee_library;
+pub mod pool;
+pub mod pool_manager;
+pub mod protocol_fee_library;
+pub mod swap_math;
@@ -0,0 +1,976 @@
+use std::collections::HashMap;
+
+use alloy_primitives::{Address, B256, I256, U256};
+
+use crate::{
+    error::UniswapV3MathError,
+    full_math::mul_div,
+    liquidity_math,
+    pool::restore_entry,
+    safe_cast::{to_int128, to_int256},
+    sqrt_price_math::{get_amount_0_delta, get_amount_1_delta},
+    tick::{self, check_ticks, Tick},
+    tick_bitmap::{flip_tick, next_initialized_tick_within_one_word, position},
+    tick_math::{
+        get_sqrt_ratio_at_tick, get_tick_at_sqrt_ratio, MAX_SQRT_RATIO, MAX_TICK, MIN_SQRT_RATIO,
+        MIN_TICK,
+    },
+    v4::{
+        balance_delta::{BalanceDelta, ZERO_DELTA},
+        lp_fee_library,
+        protocol_fee_library::{self, calculate_swap_fee, PIPS_DENOMINATOR},
+        swap_math::{compute_swap_step_with_kind, get_sqrt_price_target, swap_kind, MAX_SWAP_FEE},
+    },
+};
+
+pub const MIN_TICK_SPACING: i32 = 1;
+pub const MAX_TICK_SPACING: i32 = 32767;
+
+const Q128: U256 = U256::from_limbs([0, 0, 1, 0]);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Slot0 {
+    pub sqrt_price_x_96: U256,
+    pub tick: i32,
+    //Upper 12 bits are the one for zero fee, lower 12 bits are the zero for one fee
+    pub protocol_fee: u32,
+    //Used for the lp fee, either static at initialize or dynamic via the hook
+    pub lp_fee: u32,
+}
+
+//Positions are owned by an address and identified by their range and a salt, allowing an owner to hold several
+//positions over the same range
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PositionKey {
+    pub owner: Address,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub salt: B256,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    pub liquidity: u128,
+    pub fee_growth_inside_0_last_x_128: U256,
+    pub fee_growth_inside_1_last_x_128: U256,
+}
+
+impl Position {
+    //Credits accumulated fees to a user's position. Unlike v3, the fees are returned rather than stored as tokens owed.
+    // returns (uint256 feesOwed0, uint256 feesOwed1)
+    pub fn update(
+        &mut self,
+        liquidity_delta: i128,
+        fee_growth_inside_0_x_128: U256,
+        fee_growth_inside_1_x_128: U256,
+    ) -> Result<(U256, U256), UniswapV3MathError> {
+        let liquidity = self.liquidity;
+
+        if liquidity_delta == 0 {
+            // disallow pokes for 0 liquidity positions
+            if liquidity == 0 {
+                return Err(UniswapV3MathError::CannotUpdateEmptyPosition);
+            }
+        } else {
+            self.liquidity = liquidity_math::add_delta(liquidity, liquidity_delta)?;
+        }
+
+        // overflow in the subtraction of fee growth is expected
+        let fees_owed_0 = mul_div(
+            fee_growth_inside_0_x_128.wrapping_sub(self.fee_growth_inside_0_last_x_128),
+            U256::from(liquidity),
+            Q128,
+        )?;
+        let fees_owed_1 = mul_div(
+            fee_growth_inside_1_x_128.wrapping_sub(self.fee_growth_inside_1_last_x_128),
+            U256::from(liquidity),
+            Q128,
+        )?;
+
+        self.fee_growth_inside_0_last_x_128 = fee_growth_inside_0_x_128;
+        self.fee_growth_inside_1_last_x_128 = fee_growth_inside_1_x_128;
+
+        Ok((fees_owed_0, fees_owed_1))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModifyLiquidityParams {
+    //the address and salt that own the position
+    pub owner: Address,
+    pub salt: B256,
+    //the lower and upper tick of the position
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    //any change in liquidity
+    pub liquidity_delta: i128,
+    //the spacing between ticks
+    pub tick_spacing: i32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapParams {
+    //Negative for exact input, positive for exact output
+    pub amount_specified: I256,
+    pub tick_spacing: i32,
+    pub zero_for_one: bool,
+    pub sqrt_price_limit_x_96: U256,
+    //Fee returned by the beforeSwap hook, only used if it has the override flag set
+    pub lp_fee_override: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapResult {
+    //The balance delta of the swap from the caller's perspective
+    pub delta: BalanceDelta,
+    //The amount of the input currency owed to the protocol
+    pub amount_to_protocol: U256,
+    //The combined lp and protocol fee charged on the swap, in pips
+    pub swap_fee: u32,
+}
+
+//The state of a single v4 pool, mirroring Pool.State
+#[derive(Debug, Clone, Default)]
+pub struct Pool {
+    pub slot0: Slot0,
+    pub fee_growth_global_0_x_128: U256,
+    pub fee_growth_global_1_x_128: U256,
+    pub liquidity: u128,
+    pub ticks: HashMap<i32, Tick>,
+    pub tick_bitmap: HashMap<i16, U256>,
+    pub positions: HashMap<PositionKey, Position>,
+}
+
+//The entries of a pool a change to a position can write, kept to undo a change that fails part way
+struct PositionSnapshot {
+    position: (PositionKey, Option<Position>),
+    ticks: [(i32, Option<Tick>); 2],
+    words: Vec<(i16, Option<U256>)>,
+    liquidity: u128,
+}
+
+impl PositionSnapshot {
+    fn take(pool: &Pool, params: &ModifyLiquidityParams) -> PositionSnapshot {
+        let key = PositionKey {
+            owner: params.owner,
+            tick_lower: params.tick_lower,
+            tick_upper: params.tick_upper,
+            salt: params.salt,
+        };
+        let words = [params.tick_lower, params.tick_upper]
+            .into_iter()
+            .filter_map(|tick| tick.checked_div(params.tick_spacing))
+            .map(|compressed| {
+                let (word_pos, _) = position(compressed);
+                (word_pos, pool.tick_bitmap.get(&word_pos).copied())
+            })
+            .collect();
+
+        PositionSnapshot {
+            position: (key, pool.positions.get(&key).copied()),
+            ticks: [params.tick_lower, params.tick_upper]
+                .map(|tick| (tick, pool.ticks.get(&tick).copied())),
+            words,
+            liquidity: pool.liquidity,
+        }
+    }
+
+    fn restore(self, pool: &mut Pool) {
+        restore_entry(&mut pool.positions, self.position);
+        for entry in self.ticks {
+            restore_entry(&mut pool.ticks, entry);
+        }
+        for entry in self.words {
+            restore_entry(&mut pool.tick_bitmap, entry);
+        }
+        pool.liquidity = self.liquidity;
+    }
+}
+
+//Derives max liquidity per tick from given tick spacing. Unlike v3, a partial tick range at MIN_TICK is counted.
+pub fn tick_spacing_to_max_liquidity_per_tick(
+    tick_spacing: i32,
+) -> Result<u128, UniswapV3MathError> {
+    if tick_spacing <= 0 {
+        return Err(UniswapV3MathError::TickSpacingOutOfBounds(tick_spacing));
+    }
+
+    let mut min_tick = MIN_TICK / tick_spacing;
+    if MIN_TICK % tick_spacing != 0 {
+        min_tick -= 1;
+    }
+    let max_tick = MAX_TICK / tick_spacing;
+    let num_ticks = (max_tick - min_tick) as u128 + 1;
+    Ok(u128::MAX / num_ticks)
+}
+
+impl Pool {
+    //Initializes a pool at the given price with the lp fee of its pool key, dynamic fee pools start with an lp fee of 0
+    pub fn initialize(sqrt_price_x_96: U256, fee: u32) -> Result<Pool, UniswapV3MathError> {
+        let tick = get_tick_at_sqrt_ratio(sqrt_price_x_96)?;
+        let lp_fee = lp_fee_library::get_initial_lp_fee(fee)?;
+
+        Ok(Pool {
+            slot0: Slot0 {
+                sqrt_price_x_96,
+                tick,
+                protocol_fee: 0,
+                lp_fee,
+            },
+            ..Default::default()
+        })
+    }
+
+    pub fn check_pool_initialized(&self) -> Result<(), UniswapV3MathError> {
+        if self.slot0.sqrt_price_x_96.is_zero() {
+            Err(UniswapV3MathError::PoolNotInitialized)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn set_protocol_fee(&mut self, protocol_fee: u32) -> Result<(), UniswapV3MathError> {
+        self.check_pool_initialized()?;
+        protocol_fee_library::validate(protocol_fee)?;
+        self.slot0.protocol_fee = protocol_fee;
+        Ok(())
+    }
+
+    //Only dynamic fee pools may update the lp fee, this is enforced by the caller
+    pub fn set_lp_fee(&mut self, lp_fee: u32) -> Result<(), UniswapV3MathError> {
+        self.check_pool_initialized()?;
+        lp_fee_library::validate(lp_fee)?;
+        self.slot0.lp_fee = lp_fee;
+        Ok(())
+    }
+
+    // returns (uint256 feeGrowthInside0X128, uint256 feeGrowthInside1X128)
+    pub fn get_fee_growth_inside(&self, tick_lower: i32, tick_upper: i32) -> (U256, U256) {
+        tick::get_fee_growth_inside(
+            &self.ticks,
+            tick_lower,
+            tick_upper,
+            self.slot0.tick,
+            self.fee_growth_global_0_x_128,
+            self.fee_growth_global_1_x_128,
+        )
+    }
+
+    //Effect changes to a position in a pool, returning the principal delta and the fees accrued by the position
+    // returns (BalanceDelta delta, BalanceDelta feeDelta)
+    pub fn modify_liquidity(
+        &mut self,
+        params: &ModifyLiquidityParams,
+    ) -> Result<(BalanceDelta, BalanceDelta), UniswapV3MathError> {
+        // a change failing part way leaves the pool as it was, as the contract's revert would
+        let snapshot = PositionSnapshot::take(self, params);
+        let result = self.apply_liquidity_change(params);
+        if result.is_err() {
+            snapshot.restore(self);
+        }
+        result
+    }
+
+    // returns (BalanceDelta delta, BalanceDelta feeDelta)
+    fn apply_liquidity_change(
+        &mut self,
+        params: &ModifyLiquidityParams,
+    ) -> Result<(BalanceDelta, BalanceDelta), UniswapV3MathError> {
+        self.check_pool_initialized()?;
+
+        let liquidity_delta = params.liquidity_delta;
+        let tick_lower = params.tick_lower;
+        let tick_upper = params.tick_upper;
+        check_ticks(tick_lower, tick_upper)?;
+
+        let mut flipped_lower = false;
+        let mut flipped_upper = false;
+
+        // if we need to update the ticks, do it
+        if liquidity_delta != 0 {
+            let max_liquidity_per_tick =
+                tick_spacing_to_max_liquidity_per_tick(params.tick_spacing)?;
+
+            flipped_lower =
+                self.update_tick(tick_lower, liquidity_delta, false, max_liquidity_per_tick)?;
+            flipped_upper =
+                self.update_tick(tick_upper, liquidity_delta, true, max_liquidity_per_tick)?;
+
+            if flipped_lower {
+                flip_tick(&mut self.tick_bitmap, tick_lower, params.tick_spacing)?;
+            }
+            if flipped_upper {
+                flip_tick(&mut self.tick_bitmap, tick_upper, params.tick_spacing)?;
+            }
+        }
+
+        let (fee_growth_inside_0_x_128, fee_growth_inside_1_x_128) =
+            self.get_fee_growth_inside(tick_lower, tick_upper);
+
+        let key = PositionKey {
+            owner: params.owner,
+            tick_lower,
+            tick_upper,
+            salt: params.salt,
+        };
+        let position = self.positions.entry(key).or_default();
+        let (fees_owed_0, fees_owed_1) = position.update(
+            liquidity_delta,
+            fee_growth_inside_0_x_128,
+            fee_growth_inside_1_x_128,
+        )?;
+        if position.liquidity == 0 {
+            self.positions.remove(&key);
+        }
+
+        // fees earned from LPing are calculated, and returned
+        let fee_delta = BalanceDelta::new(
+            to_int128(to_int256(fees_owed_0)?)?,
+            to_int128(to_int256(fees_owed_1)?)?,
+        );
+
+        // clear any tick data that is no longer needed
+        if liquidity_delta < 0 {
+            if flipped_lower {
+                tick::clear(&mut self.ticks, tick_lower);
+            }
+            if flipped_upper {
+                tick::clear(&mut self.ticks, tick_upper);
+            }
+        }
+
+        let mut delta = ZERO_DELTA;
+
+        if liquidity_delta != 0 {
+            let tick = self.slot0.tick;
+            let sqrt_price_x_96 = self.slot0.sqrt_price_x_96;
+            let sqrt_price_lower_x_96 = get_sqrt_ratio_at_tick(tick_lower)?;
+            let sqrt_price_upper_x_96 = get_sqrt_ratio_at_tick(tick_upper)?;
+
+            // v4 amounts are from the caller's perspective, the opposite sign of the v3 amount deltas
+            if tick < tick_lower {
+                // current tick is below the passed range; liquidity can only become in range by crossing from left to
+                // right, when we'll need _more_ currency0 (it's becoming more valuable) so user must provide it
+                delta.amount_0 = to_int128(-get_amount_0_delta(
+                    sqrt_price_lower_x_96,
+                    sqrt_price_upper_x_96,
+                    liquidity_delta,
+                )?)?;
+            } else if tick < tick_upper {
+                delta.amount_0 = to_int128(-get_amount_0_delta(
+                    sqrt_price_x_96,
+                    sqrt_price_upper_x_96,
+                    liquidity_delta,
+                )?)?;
+                delta.amount_1 = to_int128(-get_amount_1_delta(
+                    sqrt_price_lower_x_96,
+                    sqrt_price_x_96,
+                    liquidity_delta,
+                )?)?;
+
+                self.liquidity = liquidity_math::add_delta(self.liquidity, liquidity_delta)?;
+            } else {
+                // current tick is above the passed range; liquidity can only become in range by crossing from right to
+                // left, when we'll need _more_ currency1 (it's becoming more valuable) so user must provide it
+                delta.amount_1 = to_int128(-get_amount_1_delta(
+                    sqrt_price_lower_x_96,
+                    sqrt_price_upper_x_96,
+                    liquidity_delta,
+                )?)?;
+            }
+        }
+
+        Ok((delta, fee_delta))
+    }
+
+    fn update_tick(
+        &mut self,
+        tick: i32,
+        liquidity_delta: i128,
+        upper: bool,
+        max_liquidity_per_tick: u128,
+    ) -> Result<bool, UniswapV3MathError> {
+        tick::update(
+            &mut self.ticks,
+            tick,
+            self.slot0.tick,
+            liquidity_delta,
+            self.fee_growth_global_0_x_128,
+            self.fee_growth_global_1_x_128,
+            U256::ZERO,
+            U256::ZERO,
+            0,
+            upper,
+            max_liquidity_per_tick,
+        )
+    }
+
+    //Executes a swap against the state, and returns the amount deltas of the pool
+    pub fn swap(&mut self, params: &SwapParams) -> Result<SwapResult, UniswapV3MathError> {
+        // the ticks crossed by a swap failing part way are restored, as the contract's revert would
+        let mut crossed_ticks = vec![];
+        let result = self.swap_loop(params, &mut crossed_ticks);
+        if result.is_err() {
+            for entry in crossed_ticks {
+                restore_entry(&mut self.ticks, entry);
+            }
+        }
+        result
+    }
+
+    fn swap_loop(
+        &mut self,
+        params: &SwapParams,
+        crossed_ticks: &mut Vec<(i32, Option<Tick>)>,
+    ) -> Result<SwapResult, UniswapV3MathError> {
+        self.check_pool_initialized()?;
+
+        let slot0_start = self.slot0;
+        let zero_for_one = params.zero_for_one;
+
+        let protocol_fee = if zero_for_one {
+            protocol_fee_library::get_zero_for_one_fee(slot0_start.protocol_fee)
+        } else {
+            protocol_fee_library::get_one_for_zero_fee(slot0_start.protocol_fee)
+        };
+
+        // the amount remaining to be swapped in/out of the input/output asset. initially set to the amountSpecified
+        let mut amount_specified_remaining = params.amount_specified;
+        // the amount swapped out/in of the output/input asset. initially set to 0
+        let mut amount_calculated = I256::ZERO;
+
+        let mut sqrt_price_x_96 = slot0_start.sqrt_price_x_96;
+        let mut tick = slot0_start.tick;
+        let mut liquidity = self.liquidity;
+
+        // if the beforeSwap hook returned a valid fee override, use that as the LP fee, otherwise load from storage
+        let lp_fee = if lp_fee_library::is_override(params.lp_fee_override) {
+            lp_fee_library::remove_override_flag_and_validate(params.lp_fee_override)?
+        } else {
+            slot0_start.lp_fee
+        };
+        let swap_fee = if protocol_fee == 0 {
+            lp_fee
+        } else {
+            calculate_swap_fee(protocol_fee, lp_fee)
+        };
+
+        // a swap fee totaling MAX_SWAP_FEE (100%) makes exact output swaps impossible since the input is entirely
+        // consumed by the fee
+        if swap_fee >= MAX_SWAP_FEE && params.amount_specified > I256::ZERO {
+            return Err(UniswapV3MathError::InvalidFeeForExactOut);
+        }
+
+        // when the amount swapped is 0, there is no protocolFee applied and the fee amount paid to the protocol is
+        // set to 0
+        if params.amount_specified.is_zero() {
+            return Ok(SwapResult {
+                delta: ZERO_DELTA,
+                amount_to_protocol: U256::ZERO,
+                swap_fee,
+            });
+        }
+
+        if zero_for_one {
+            if params.sqrt_price_limit_x_96 >= slot0_start.sqrt_price_x_96 {
+                return Err(UniswapV3MathError::PriceLimitAlreadyExceeded(
+                    slot0_start.sqrt_price_x_96,
+                    params.sqrt_price_limit_x_96,
+                ));
+            }
+            // swaps can never occur at MIN_TICK, only at MIN_TICK + 1, except at initialization of a pool
+            if params.sqrt_price_limit_x_96 <= MIN_SQRT_RATIO {
+                return Err(UniswapV3MathError::PriceLimitOutOfBounds(
+                    params.sqrt_price_limit_x_96,
+                ));
+            }
+        } else {
+            if params.sqrt_price_limit_x_96 <= slot0_start.sqrt_price_x_96 {
+                return Err(UniswapV3MathError::PriceLimitAlreadyExceeded(
+                    slot0_start.sqrt_price_x_96,
+                    params.sqrt_price_limit_x_96,
+                ));
+            }
+            if params.sqrt_price_limit_x_96 >= MAX_SQRT_RATIO {
+                return Err(UniswapV3MathError::PriceLimitOutOfBounds(
+                    params.sqrt_price_limit_x_96,
+                ));
+            }
+        }
+
+        let mut amount_to_protocol = U256::ZERO;
+        let mut fee_growth_global_x_128 = if zero_for_one {
+            self.fee_growth_global_0_x_128
+        } else {
+            self.fee_growth_global_1_x_128
+        };
+
+        // continue swapping as long as we haven't used the entire input/output and haven't reached the price limit
+        while !(amount_specified_remaining.is_zero()
+            || sqrt_price_x_96 == params.sqrt_price_limit_x_96)
+        {
+            let sqrt_price_start_x_96 = sqrt_price_x_96;
+
+            let (mut tick_next, initialized) = next_initialized_tick_within_one_word(
+                &self.tick_bitmap,
+                tick,
+                params.tick_spacing,
+                zero_for_one,
+            )?;
+
+            // ensure that we do not overshoot the min/max tick, as the tick bitmap is not aware of these bounds
+            tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+
+            // get the price for the next tick
+            let sqrt_price_next_x_96 = get_sqrt_ratio_at_tick(tick_next)?;
+
+            // compute values to swap to the target tick, price limit, or point where input/output amount is exhausted
+            let (amount_remaining, kind) = swap_kind(amount_specified_remaining);
+            let mut step = compute_swap_step_with_kind(
+                sqrt_price_x_96,
+                get_sqrt_price_target(
+                    zero_for_one,
+                    sqrt_price_next_x_96,
+                    params.sqrt_price_limit_x_96,
+                ),
+                liquidity,
+                amount_remaining,
+                kind,
+                swap_fee,
+            )?;
+            sqrt_price_x_96 = step.sqrt_ratio_next_x_96;
+
+            let amount_in_plus_fee = step.amount_in + step.fee_amount;
+            if params.amount_specified > I256::ZERO {
+                amount_specified_remaining -= to_int256(step.amount_out)?;
+                amount_calculated -= to_int256(amount_in_plus_fee)?;
+            } else {
+                amount_specified_remaining += to_int256(amount_in_plus_fee)?;
+                amount_calculated += to_int256(step.amount_out)?;
+            }
+
+            // if the protocol fee is on, calculate how much is owed, decrement fee_amount, and increment protocol fee
+            if protocol_fee > 0 {
+                // the amount in does not include the swap fee, so it is added back to get the total amount in.
+                // this rounds down to favor LPs over the protocol
+                let delta = if swap_fee == protocol_fee as u32 {
+                    // lp fee is 0, so the entire fee is owed to the protocol instead
+                    step.fee_amount
+                } else {
+                    amount_in_plus_fee * U256::from(protocol_fee) / U256::from(PIPS_DENOMINATOR)
+                };
+                step.fee_amount -= delta;
+                amount_to_protocol += delta;
+            }
+
+            // update global fee tracker
+            if liquidity > 0 {
+                fee_growth_global_x_128 = fee_growth_global_x_128.wrapping_add(mul_div(
+                    step.fee_amount,
+                    Q128,
+                    U256::from(liquidity),
+                )?);
+            }
+
+            // shift tick if we reached the next price, and preemptively decrement for zero for one swaps to
+            // tick_next - 1
+            if sqrt_price_x_96 == sqrt_price_next_x_96 {
+                // if the tick is initialized, run the tick transition
+                if initialized {
+                    let (fee_growth_global_0_x_128, fee_growth_global_1_x_128) = if zero_for_one {
+                        (fee_growth_global_x_128, self.fee_growth_global_1_x_128)
+                    } else {
+                        (self.fee_growth_global_0_x_128, fee_growth_global_x_128)
+                    };
+
+                    crossed_ticks.push((tick_next, self.ticks.get(&tick_next).copied()));
+                    let mut liquidity_net = tick::cross(
+                        &mut self.ticks,
+                        tick_next,
+                        fee_growth_global_0_x_128,
+                        fee_growth_global_1_x_128,
+                        U256::ZERO,
+                        U256::ZERO,
+                        0,
+                    );
+
+                    // if we're moving leftward, we interpret liquidity_net as the opposite sign
+                    if zero_for_one {
+                        liquidity_net = -liquidity_net;
+                    }
+
+                    liquidity = liquidity_math::add_delta(liquidity, liquidity_net)?;
+                }
+
+                tick = if zero_for_one {
+                    tick_next - 1
+                } else {
+                    tick_next
+                };
+            } else if sqrt_price_x_96 != sqrt_price_start_x_96 {
+                // recompute unless we're on a lower tick boundary (i.e. already transitioned ticks), and haven't moved
+                tick = get_tick_at_sqrt_ratio(sqrt_price_x_96)?;
+            }
+        }
+
+        // the deltas are cast before any state is written, so a swap too large for them leaves the pool as it was
+        let amount_specified = to_int128(params.amount_specified - amount_specified_remaining)?;
+        let amount_calculated = to_int128(amount_calculated)?;
+
+        self.slot0.tick = tick;
+        self.slot0.sqrt_price_x_96 = sqrt_price_x_96;
+        self.liquidity = liquidity;
+
+        if zero_for_one {
+            self.fee_growth_global_0_x_128 = fee_growth_global_x_128;
+        } else {
+            self.fee_growth_global_1_x_128 = fee_growth_global_x_128;
+        }
+
+        // "if currency1 is specified"
+        let delta = if zero_for_one != (params.amount_specified < I256::ZERO) {
+            BalanceDelta::new(amount_calculated, amount_specified)
+        } else {
+            BalanceDelta::new(amount_specified, amount_calculated)
+        };
+
+        Ok(SwapResult {
+            delta,
+            amount_to_protocol,
+            swap_fee,
+        })
+    }
+
+    //Donates the given amount of currency0 and currency1 to the in range liquidity providers
+    pub fn donate(
+        &mut self,
+        amount_0: U256,
+        amount_1: U256,
+    ) -> Result<BalanceDelta, UniswapV3MathError> {
+        self.check_pool_initialized()?;
+
+        let liquidity = self.liquidity;
+        if liquidity == 0 {
+            return Err(UniswapV3MathError::NoLiquidityToReceiveFees);
+        }
+
+        let delta = BalanceDelta::new(
+            -to_int128(to_int256(amount_0)?)?,
+            -to_int128(to_int256(amount_1)?)?,
+        );
+
+        if !amount_0.is_zero() {
+            self.fee_growth_global_0_x_128 = self.fee_growth_global_0_x_128.wrapping_add(mul_div(
+                amount_0,
+                Q128,
+                U256::from(liquidity),
+            )?);
+        }
+        if !amount_1.is_zero() {
+            self.fee_growth_global_1_x_128 = self.fee_growth_global_1_x_128.wrapping_add(mul_div(
+                amount_1,
+                Q128,
+                U256::from(liquidity),
+            )?);
+        }
+
+        Ok(delta)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test_utils::SQRT_PRICE_1_1,
+        v4::lp_fee_library::{DYNAMIC_FEE_FLAG, OVERRIDE_FEE_FLAG},
+    };
+    use std::str::FromStr;
+
+    fn liquidity_params(liquidity_delta: i128) -> ModifyLiquidityParams {
+        ModifyLiquidityParams {
+            tick_lower: -120,
+            tick_upper: 120,
+            liquidity_delta,
+            tick_spacing: 60,
+            ..Default::default()
+        }
+    }
+
+    fn swap_params(amount_specified: i128, zero_for_one: bool) -> SwapParams {
+        SwapParams {
+            amount_specified: I256::try_from(amount_specified).unwrap(),
+            tick_spacing: 60,
+            zero_for_one,
+            sqrt_price_limit_x_96: if zero_for_one {
+                MIN_SQRT_RATIO + U256::from(1)
+            } else {
+                MAX_SQRT_RATIO - U256::from(1)
+            },
+            lp_fee_override: 0,
+        }
+    }
+
+    fn pool_with_liquidity(fee: u32) -> Pool {
+        let mut pool = Pool::initialize(SQRT_PRICE_1_1, fee).unwrap();
+        pool.modify_liquidity(&liquidity_params(1e18 as i128))
+            .unwrap();
+        pool
+    }
+
+    #[test]
+    fn test_tick_spacing_to_max_liquidity_per_tick() {
+        // v4 counts the partial range at MIN_TICK, so it differs from v3 whenever MIN_TICK is not a multiple
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(60).unwrap(),
+            11505354575363080317263139282924270
+        );
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(1).unwrap(),
+            crate::tick::tick_spacing_to_max_liquidity_per_tick(1).unwrap()
+        );
+        assert!(matches!(
+            tick_spacing_to_max_liquidity_per_tick(0),
+            Err(UniswapV3MathError::TickSpacingOutOfBounds(0))
+        ));
+    }
+
+    #[test]
+    fn test_initialize() {
+        let pool = Pool::initialize(SQRT_PRICE_1_1, 3000).unwrap();
+        assert_eq!(pool.slot0.tick, 0);
+        assert_eq!(pool.slot0.lp_fee, 3000);
+
+        let pool = Pool::initialize(SQRT_PRICE_1_1, DYNAMIC_FEE_FLAG).unwrap();
+        assert_eq!(pool.slot0.lp_fee, 0);
+
+        assert!(Pool::initialize(MIN_SQRT_RATIO - U256::from(1), 3000).is_err());
+        assert!(Pool::default()
+            .modify_liquidity(&liquidity_params(1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_modify_liquidity() {
+        let mut pool = Pool::initialize(SQRT_PRICE_1_1, 3000).unwrap();
+
+        let (delta, fee_delta) = pool
+            .modify_liquidity(&liquidity_params(1e18 as i128))
+            .unwrap();
+        assert_eq!(
+            delta,
+            BalanceDelta::new(-5981737760509663, -5981737760509663)
+        );
+        assert_eq!(fee_delta, ZERO_DELTA);
+        assert_eq!(pool.liquidity, 1e18 as u128);
+        assert_eq!(pool.ticks[&-120].liquidity_net, 1e18 as i128);
+        assert_eq!(pool.ticks[&120].liquidity_net, -1e18 as i128);
+
+        // out of range positions only take a single currency
+        let (delta, _) = pool
+            .modify_liquidity(&ModifyLiquidityParams {
+                tick_lower: 60,
+                tick_upper: 120,
+                ..liquidity_params(1e18 as i128)
+            })
+            .unwrap();
+        assert_eq!(delta.amount_1, 0);
+        assert!(delta.amount_0 < 0);
+
+        // removing rounds in favor of the pool
+        let (delta, _) = pool
+            .modify_liquidity(&liquidity_params(-1e18 as i128))
+            .unwrap();
+        assert_eq!(delta, BalanceDelta::new(5981737760509662, 5981737760509662));
+        assert_eq!(pool.liquidity, 0);
+        assert!(!pool.ticks.contains_key(&-120));
+
+        let result = pool.modify_liquidity(&liquidity_params(0));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Cannot update an empty position"
+        );
+
+        let result = pool.modify_liquidity(&ModifyLiquidityParams {
+            tick_lower: 120,
+            tick_upper: -120,
+            ..liquidity_params(1)
+        });
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Tick lower 120 is not less than tick upper -120"
+        );
+
+        // failed changes leave the pool as it was
+        pool.modify_liquidity(&liquidity_params(1000)).unwrap();
+        let state = |pool: &Pool| {
+            (
+                pool.ticks.clone(),
+                pool.tick_bitmap.clone(),
+                pool.positions.clone(),
+                pool.liquidity,
+            )
+        };
+        let before = state(&pool);
+
+        for params in [
+            ModifyLiquidityParams {
+                tick_upper: 121,
+                ..liquidity_params(1)
+            },
+            ModifyLiquidityParams {
+                tick_lower: -121,
+                ..liquidity_params(1)
+            },
+            // another salt owns a different position on the same ticks
+            ModifyLiquidityParams {
+                salt: B256::repeat_byte(1),
+                ..liquidity_params(-400)
+            },
+            liquidity_params(-1001),
+        ] {
+            assert!(pool.modify_liquidity(&params).is_err());
+            assert_eq!(state(&pool), before);
+        }
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut pool = pool_with_liquidity(3000);
+
+        // exact input zero for one
+        let result = pool.swap(&swap_params(-100, true)).unwrap();
+        assert_eq!(result.delta, BalanceDelta::new(-100, 98));
+        assert_eq!(result.swap_fee, 3000);
+        assert_eq!(result.amount_to_protocol, U256::ZERO);
+        assert!(pool.slot0.sqrt_price_x_96 < SQRT_PRICE_1_1);
+        assert_eq!(pool.slot0.tick, -1);
+
+        // exact output one for zero
+        let result = pool.swap(&swap_params(100, false)).unwrap();
+        assert_eq!(result.delta.amount_0, 100);
+        assert!(result.delta.amount_1 < -100);
+
+        // swapping zero is a no op
+        let result = pool.swap(&swap_params(0, false)).unwrap();
+        assert_eq!(result.delta, ZERO_DELTA);
+
+        let result = pool.swap(&SwapParams {
+            sqrt_price_limit_x_96: MAX_SQRT_RATIO,
+            ..swap_params(-100, true)
+        });
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::PriceLimitAlreadyExceeded(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_swap_crosses_ticks_and_accrues_fees() {
+        let mut pool = pool_with_liquidity(3000);
+
+        // swap through the whole position, leaving no liquidity in range
+        let result = pool.swap(&swap_params(-1e18 as i128, true)).unwrap();
+        assert_eq!(pool.liquidity, 0);
+        assert_eq!(pool.slot0.sqrt_price_x_96, MIN_SQRT_RATIO + U256::from(1));
+        // only the liquidity in the range was swapped against
+        assert!(result.delta.amount_0 > -1e18 as i128);
+        assert!(result.delta.amount_1 <= 5981737760509663);
+
+        // the position earned the lp fee on the input
+        let (_, fee_delta) = pool.modify_liquidity(&liquidity_params(0)).unwrap();
+        let amount_in = (-result.delta.amount_0) as u128;
+        let expected_fee = amount_in * 3000 / 1_000_000;
+        assert!(fee_delta.amount_0.abs_diff(expected_fee as i128) <= 1);
+        assert_eq!(fee_delta.amount_1, 0);
+    }
+
+    #[test]
+    fn test_failed_swap_restores_crossed_ticks() {
+        let mut pool = pool_with_liquidity(3000);
+        pool.modify_liquidity(&ModifyLiquidityParams {
+            tick_lower: -887220,
+            tick_upper: 887220,
+            liquidity_delta: 1e30 as i128,
+            tick_spacing: 60,
+            ..Default::default()
+        })
+        .unwrap();
+        let ticks = pool.ticks.clone();
+        let slot0 = pool.slot0;
+        let liquidity = pool.liquidity;
+        let fee_growth_global_1_x_128 = pool.fee_growth_global_1_x_128;
+
+        // the input needed to reach the max price is wider than int128 once tick 120 is crossed
+        let result = pool.swap(&SwapParams {
+            amount_specified: -(I256::ONE << 200usize),
+            ..swap_params(0, false)
+        });
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::SafeCastToI128Overflow)
+        ));
+        assert_eq!(pool.ticks, ticks);
+        assert_eq!(pool.slot0, slot0);
+        assert_eq!(pool.liquidity, liquidity);
+        assert_eq!(pool.fee_growth_global_1_x_128, fee_growth_global_1_x_128);
+    }
+
+    #[test]
+    fn test_swap_protocol_fee() {
+        let mut pool = pool_with_liquidity(3000);
+        pool.set_protocol_fee((1000 << 12) | 1000).unwrap();
+
+        let result = pool.swap(&swap_params(-1e15 as i128, true)).unwrap();
+        assert_eq!(result.swap_fee, 3997);
+        // 1e15 * 1000 / 1e6
+        assert_eq!(result.amount_to_protocol, U256::from(1e12 as u64));
+
+        // the lp fee override only applies with the override flag set
+        let result = pool
+            .swap(&SwapParams {
+                lp_fee_override: 10_000,
+                ..swap_params(-1e15 as i128, true)
+            })
+            .unwrap();
+        assert_eq!(result.swap_fee, 3997);
+
+        let result = pool
+            .swap(&SwapParams {
+                lp_fee_override: 10_000 | OVERRIDE_FEE_FLAG,
+                ..swap_params(-1e15 as i128, true)
+            })
+            .unwrap();
+        assert_eq!(result.swap_fee, calculate_swap_fee(1000, 10_000));
+
+        let result = pool.swap(&SwapParams {
+            lp_fee_override: 1_000_000 | OVERRIDE_FEE_FLAG,
+            ..swap_params(1e15 as i128, true)
+        });
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::InvalidFeeForExactOut)
+        ));
+    }
+
+    #[test]
+    fn test_donate() {
+        let mut pool = pool_with_liquidity(3000);
+
+        let delta = pool.donate(U256::from(1e18 as u64), U256::ZERO).unwrap();
+        assert_eq!(delta, BalanceDelta::new(-1e18 as i128, 0));
+        assert_eq!(
+            pool.fee_growth_global_0_x_128,
+            U256::from_str("340282366920938463463374607431768211456").unwrap()
+        );
+
+        let (_, fee_delta) = pool.modify_liquidity(&liquidity_params(0)).unwrap();
+        assert_eq!(fee_delta, BalanceDelta::new(1e18 as i128, 0));
+
+        let mut pool = Pool::initialize(SQRT_PRICE_1_1, 3000).unwrap();
+        assert!(matches!(
+            pool.donate(U256::from(1), U256::ZERO),
+            Err(UniswapV3MathError::NoLiquidityToReceiveFees)
+        ));
+    }
+}
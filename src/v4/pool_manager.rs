@@ -0,0 +1,577 @@
+use alloy_primitives::{Address, B256, I256, U256};
+
+use crate::{
+    error::UniswapV3MathError,
+    v4::{
+        balance_delta::{BalanceDelta, ZERO_DELTA},
+        hooks::Hooks,
+        lp_fee_library,
+        pool::{self, Pool, MAX_TICK_SPACING, MIN_TICK_SPACING},
+    },
+};
+
+//Identifies a pool. The hooks address is informational here, the callbacks are supplied to the PoolManager directly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PoolKey {
+    pub currency_0: Address,
+    pub currency_1: Address,
+    //The lp fee of the pool in pips, or DYNAMIC_FEE_FLAG if the fee is set by the hook
+    pub fee: u32,
+    pub tick_spacing: i32,
+    pub hooks: Address,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModifyLiquidityParams {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity_delta: i128,
+    pub salt: B256,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapParams {
+    pub zero_for_one: bool,
+    //Negative for exact input, positive for exact output
+    pub amount_specified: I256,
+    pub sqrt_price_limit_x_96: U256,
+}
+
+//A single v4 pool together with its hooks, modelling the PoolManager entry points for that pool. Deltas returned to
+//the caller are from the caller's perspective with the hook's delta already removed.
+#[derive(Debug, Clone)]
+pub struct PoolManager<H> {
+    pub key: PoolKey,
+    pub pool: Pool,
+    pub hooks: H,
+    pub protocol_fees_accrued_0: U256,
+    pub protocol_fees_accrued_1: U256,
+}
+
+impl<H: Hooks> PoolManager<H> {
+    //Initializes the pool, returning the manager and the initial tick
+    pub fn initialize(
+        key: PoolKey,
+        mut hooks: H,
+        sqrt_price_x_96: U256,
+    ) -> Result<(Self, i32), UniswapV3MathError> {
+        if !(MIN_TICK_SPACING..=MAX_TICK_SPACING).contains(&key.tick_spacing) {
+            return Err(UniswapV3MathError::TickSpacingOutOfBounds(key.tick_spacing));
+        }
+
+        hooks.before_initialize(&key, sqrt_price_x_96)?;
+        let pool = Pool::initialize(sqrt_price_x_96, key.fee)?;
+        let tick = pool.slot0.tick;
+        hooks.after_initialize(&key, sqrt_price_x_96, tick)?;
+
+        Ok((
+            PoolManager {
+                key,
+                pool,
+                hooks,
+                protocol_fees_accrued_0: U256::ZERO,
+                protocol_fees_accrued_1: U256::ZERO,
+            },
+            tick,
+        ))
+    }
+
+    //Modifies the liquidity of the sender's position
+    // returns (BalanceDelta callerDelta, BalanceDelta feesAccrued, BalanceDelta hookDelta)
+    pub fn modify_liquidity(
+        &mut self,
+        sender: Address,
+        params: &ModifyLiquidityParams,
+    ) -> Result<(BalanceDelta, BalanceDelta, BalanceDelta), UniswapV3MathError> {
+        self.pool.check_pool_initialized()?;
+
+        self.hooks
+            .before_modify_liquidity(&self.key, &self.pool, sender, params)?;
+
+        // the change is made to a copy of the pool, kept only once the hook and the delta arithmetic succeed, as a
+        // failure would revert the whole call on chain
+        let mut pool = self.pool.clone();
+        let (principal_delta, fees_accrued) =
+            pool.modify_liquidity(&pool::ModifyLiquidityParams {
+                owner: sender,
+                salt: params.salt,
+                tick_lower: params.tick_lower,
+                tick_upper: params.tick_upper,
+                liquidity_delta: params.liquidity_delta,
+                tick_spacing: self.key.tick_spacing,
+            })?;
+
+        // fee delta and principal delta are both accrued to the caller
+        let caller_delta = principal_delta.checked_add(fees_accrued)?;
+
+        let hook_delta = self.hooks.after_modify_liquidity(
+            &self.key,
+            &pool,
+            sender,
+            params,
+            caller_delta,
+            fees_accrued,
+        )?;
+        let caller_delta = caller_delta.checked_sub(hook_delta)?;
+
+        self.pool = pool;
+        Ok((caller_delta, fees_accrued, hook_delta))
+    }
+
+    //Swaps against the pool, accruing any protocol fee to the manager
+    // returns (BalanceDelta swapDelta, BalanceDelta hookDelta)
+    pub fn swap(
+        &mut self,
+        sender: Address,
+        params: &SwapParams,
+    ) -> Result<(BalanceDelta, BalanceDelta), UniswapV3MathError> {
+        if params.amount_specified.is_zero() {
+            return Err(UniswapV3MathError::SwapAmountCannotBeZero);
+        }
+        self.pool.check_pool_initialized()?;
+
+        let (before_swap_delta, lp_fee_override) = self
+            .hooks
+            .before_swap(&self.key, &self.pool, sender, params)?;
+
+        // dynamic fee pools that want to override the cached fee return a valid fee with the override flag
+        let lp_fee_override = if lp_fee_library::is_dynamic_fee(self.key.fee) {
+            lp_fee_override
+        } else {
+            0
+        };
+
+        let mut amount_to_swap = params.amount_specified;
+        let hook_delta_specified = before_swap_delta.specified;
+        if hook_delta_specified != 0 {
+            let exact_input = amount_to_swap < I256::ZERO;
+            amount_to_swap += I256::unchecked_from(hook_delta_specified);
+            // the hook may not change the swap from exact input to exact output or vice versa
+            if exact_input && amount_to_swap > I256::ZERO
+                || !exact_input && amount_to_swap < I256::ZERO
+            {
+                return Err(UniswapV3MathError::HookDeltaExceedsSwapAmount);
+            }
+        }
+
+        // the swap is made to a copy of the pool, kept only once the hook and the delta arithmetic succeed, as a
+        // failure would revert the whole call on chain
+        let mut pool = self.pool.clone();
+        let result = pool.swap(&pool::SwapParams {
+            amount_specified: amount_to_swap,
+            tick_spacing: self.key.tick_spacing,
+            zero_for_one: params.zero_for_one,
+            sqrt_price_limit_x_96: params.sqrt_price_limit_x_96,
+            lp_fee_override,
+        })?;
+
+        let mut swap_delta = result.delta;
+        let hook_delta_unspecified = before_swap_delta
+            .unspecified
+            .checked_add(
+                self.hooks
+                    .after_swap(&self.key, &pool, sender, params, swap_delta)?,
+            )
+            .ok_or(UniswapV3MathError::SafeCastToI128Overflow)?;
+
+        let mut hook_delta = ZERO_DELTA;
+        if hook_delta_unspecified != 0 || hook_delta_specified != 0 {
+            // currency0 is specified when exact input zero for one or exact output one for zero
+            hook_delta = if (params.amount_specified < I256::ZERO) == params.zero_for_one {
+                BalanceDelta::new(hook_delta_specified, hook_delta_unspecified)
+            } else {
+                BalanceDelta::new(hook_delta_unspecified, hook_delta_specified)
+            };
+            swap_delta = swap_delta.checked_sub(hook_delta)?;
+        }
+
+        self.pool = pool;
+        // the protocol fee is charged in the input currency
+        if !result.amount_to_protocol.is_zero() {
+            if params.zero_for_one {
+                self.protocol_fees_accrued_0 += result.amount_to_protocol;
+            } else {
+                self.protocol_fees_accrued_1 += result.amount_to_protocol;
+            }
+        }
+
+        Ok((swap_delta, hook_delta))
+    }
+
+    //Donates to the in range liquidity providers, returning the caller's delta
+    pub fn donate(
+        &mut self,
+        sender: Address,
+        amount_0: U256,
+        amount_1: U256,
+    ) -> Result<BalanceDelta, UniswapV3MathError> {
+        self.pool.check_pool_initialized()?;
+
+        self.hooks
+            .before_donate(&self.key, &self.pool, sender, amount_0, amount_1)?;
+        let mut pool = self.pool.clone();
+        let delta = pool.donate(amount_0, amount_1)?;
+        self.hooks
+            .after_donate(&self.key, &pool, sender, amount_0, amount_1)?;
+
+        self.pool = pool;
+        Ok(delta)
+    }
+
+    //Updates the lp fee of a dynamic fee pool, on chain this may only be called by the hook
+    pub fn update_dynamic_lp_fee(
+        &mut self,
+        new_dynamic_lp_fee: u32,
+    ) -> Result<(), UniswapV3MathError> {
+        if !lp_fee_library::is_dynamic_fee(self.key.fee) {
+            return Err(UniswapV3MathError::UnauthorizedDynamicLPFeeUpdate);
+        }
+        self.pool.set_lp_fee(new_dynamic_lp_fee)
+    }
+
+    pub fn set_protocol_fee(&mut self, new_protocol_fee: u32) -> Result<(), UniswapV3MathError> {
+        self.pool.set_protocol_fee(new_protocol_fee)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        safe_cast::to_int128,
+        test_utils::SQRT_PRICE_1_1,
+        tick_math::{MAX_SQRT_RATIO, MIN_SQRT_RATIO},
+        v4::{
+            balance_delta::BeforeSwapDelta,
+            hooks::NoHooks,
+            lp_fee_library::{DYNAMIC_FEE_FLAG, OVERRIDE_FEE_FLAG},
+        },
+    };
+
+    fn key(fee: u32) -> PoolKey {
+        PoolKey {
+            fee,
+            tick_spacing: 60,
+            ..Default::default()
+        }
+    }
+
+    fn liquidity_params(liquidity_delta: i128) -> ModifyLiquidityParams {
+        ModifyLiquidityParams {
+            tick_lower: -120,
+            tick_upper: 120,
+            liquidity_delta,
+            salt: B256::ZERO,
+        }
+    }
+
+    fn swap_params(amount_specified: i128, zero_for_one: bool) -> SwapParams {
+        SwapParams {
+            zero_for_one,
+            amount_specified: I256::try_from(amount_specified).unwrap(),
+            sqrt_price_limit_x_96: if zero_for_one {
+                MIN_SQRT_RATIO + U256::from(1)
+            } else {
+                MAX_SQRT_RATIO - U256::from(1)
+            },
+        }
+    }
+
+    fn manager<H: Hooks>(fee: u32, hooks: H) -> PoolManager<H> {
+        let (mut manager, _) = PoolManager::initialize(key(fee), hooks, SQRT_PRICE_1_1).unwrap();
+        manager
+            .modify_liquidity(Address::ZERO, &liquidity_params(1e18 as i128))
+            .unwrap();
+        manager
+    }
+
+    //Takes a flat fee in the unspecified currency after every swap
+    struct FlatFeeHook(i128);
+
+    impl Hooks for FlatFeeHook {
+        fn after_swap(
+            &mut self,
+            _key: &PoolKey,
+            _pool: &Pool,
+            _sender: Address,
+            _params: &SwapParams,
+            _delta: BalanceDelta,
+        ) -> Result<i128, UniswapV3MathError> {
+            Ok(self.0)
+        }
+    }
+
+    //Fills exact input swaps 1:1 itself without touching the pool
+    struct CustomCurveHook;
+
+    impl Hooks for CustomCurveHook {
+        fn before_swap(
+            &mut self,
+            _key: &PoolKey,
+            _pool: &Pool,
+            _sender: Address,
+            params: &SwapParams,
+        ) -> Result<(BeforeSwapDelta, u32), UniswapV3MathError> {
+            let amount = to_int128(params.amount_specified)?;
+            Ok((BeforeSwapDelta::new(-amount, amount), 0))
+        }
+    }
+
+    //Overrides the lp fee of every swap
+    struct FeeOverrideHook(u32);
+
+    impl Hooks for FeeOverrideHook {
+        fn before_swap(
+            &mut self,
+            _key: &PoolKey,
+            _pool: &Pool,
+            _sender: Address,
+            _params: &SwapParams,
+        ) -> Result<(BeforeSwapDelta, u32), UniswapV3MathError> {
+            Ok((BeforeSwapDelta::default(), self.0 | OVERRIDE_FEE_FLAG))
+        }
+    }
+
+    //Reverts after every swap and liquidity change, like a hook failing a slippage check
+    struct RevertingHook;
+
+    impl Hooks for RevertingHook {
+        fn after_modify_liquidity(
+            &mut self,
+            _key: &PoolKey,
+            _pool: &Pool,
+            _sender: Address,
+            _params: &ModifyLiquidityParams,
+            _delta: BalanceDelta,
+            _fees_accrued: BalanceDelta,
+        ) -> Result<BalanceDelta, UniswapV3MathError> {
+            Err(UniswapV3MathError::PriceSlippageCheck)
+        }
+
+        fn after_swap(
+            &mut self,
+            _key: &PoolKey,
+            _pool: &Pool,
+            _sender: Address,
+            _params: &SwapParams,
+            _delta: BalanceDelta,
+        ) -> Result<i128, UniswapV3MathError> {
+            Err(UniswapV3MathError::PriceSlippageCheck)
+        }
+    }
+
+    #[test]
+    fn test_initialize() {
+        let (manager, tick) = PoolManager::initialize(key(3000), NoHooks, SQRT_PRICE_1_1).unwrap();
+        assert_eq!(tick, 0);
+        assert_eq!(manager.pool.slot0.lp_fee, 3000);
+
+        let result = PoolManager::initialize(
+            PoolKey {
+                tick_spacing: 0,
+                ..key(3000)
+            },
+            NoHooks,
+            SQRT_PRICE_1_1,
+        );
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::TickSpacingOutOfBounds(0))
+        ));
+
+        assert!(PoolManager::initialize(key(1_000_001), NoHooks, SQRT_PRICE_1_1).is_err());
+    }
+
+    #[test]
+    fn test_swap_without_hooks_matches_pool() {
+        let mut manager = manager(3000, NoHooks);
+        let mut pool = manager.pool.clone();
+
+        let (delta, hook_delta) = manager
+            .swap(Address::ZERO, &swap_params(-1e15 as i128, true))
+            .unwrap();
+        let result = pool
+            .swap(&pool::SwapParams {
+                amount_specified: I256::try_from(-1e15 as i128).unwrap(),
+                tick_spacing: 60,
+                zero_for_one: true,
+                sqrt_price_limit_x_96: MIN_SQRT_RATIO + U256::from(1),
+                lp_fee_override: 0,
+            })
+            .unwrap();
+
+        assert_eq!(delta, result.delta);
+        assert_eq!(hook_delta, ZERO_DELTA);
+        assert_eq!(manager.pool.slot0, pool.slot0);
+
+        assert!(matches!(
+            manager.swap(Address::ZERO, &swap_params(0, true)),
+            Err(UniswapV3MathError::SwapAmountCannotBeZero)
+        ));
+    }
+
+    #[test]
+    fn test_swap_protocol_fees_accrue() {
+        let mut manager = manager(3000, NoHooks);
+        manager.set_protocol_fee((1000 << 12) | 1000).unwrap();
+
+        manager
+            .swap(Address::ZERO, &swap_params(-1e15 as i128, true))
+            .unwrap();
+        assert_eq!(manager.protocol_fees_accrued_0, U256::from(1e12 as u64));
+        assert_eq!(manager.protocol_fees_accrued_1, U256::ZERO);
+    }
+
+    #[test]
+    fn test_after_swap_hook_delta() {
+        let mut no_hooks = manager(3000, NoHooks);
+        let mut manager = manager(3000, FlatFeeHook(10));
+
+        let (expected, _) = no_hooks
+            .swap(Address::ZERO, &swap_params(-1e15 as i128, true))
+            .unwrap();
+        let (delta, hook_delta) = manager
+            .swap(Address::ZERO, &swap_params(-1e15 as i128, true))
+            .unwrap();
+
+        // exact input zero for one, so currency1 is unspecified
+        assert_eq!(hook_delta, BalanceDelta::new(0, 10));
+        assert_eq!(
+            delta,
+            BalanceDelta::new(expected.amount_0, expected.amount_1 - 10)
+        );
+
+        // exact output zero for one, so currency0 is unspecified
+        let (_, hook_delta) = manager
+            .swap(Address::ZERO, &swap_params(1e15 as i128, true))
+            .unwrap();
+        assert_eq!(hook_delta, BalanceDelta::new(10, 0));
+    }
+
+    #[test]
+    fn test_failed_after_swap_hook_leaves_pool_unchanged() {
+        let (mut manager, _) =
+            PoolManager::initialize(key(3000), RevertingHook, SQRT_PRICE_1_1).unwrap();
+        // the liquidity is added directly, as the hook reverts every liquidity change through the manager
+        manager
+            .pool
+            .modify_liquidity(&pool::ModifyLiquidityParams {
+                tick_lower: -120,
+                tick_upper: 120,
+                liquidity_delta: 1e18 as i128,
+                tick_spacing: 60,
+                ..Default::default()
+            })
+            .unwrap();
+        manager.set_protocol_fee((1000 << 12) | 1000).unwrap();
+        let pool = manager.pool.clone();
+
+        // the swap crosses tick -120 before the hook reverts
+        assert!(matches!(
+            manager.swap(Address::ZERO, &swap_params(-1e18 as i128, true)),
+            Err(UniswapV3MathError::PriceSlippageCheck)
+        ));
+        assert_eq!(manager.pool.slot0, pool.slot0);
+        assert_eq!(manager.pool.liquidity, pool.liquidity);
+        assert_eq!(manager.pool.ticks, pool.ticks);
+        assert_eq!(
+            manager.pool.fee_growth_global_0_x_128,
+            pool.fee_growth_global_0_x_128
+        );
+        assert_eq!(manager.protocol_fees_accrued_0, U256::ZERO);
+    }
+
+    #[test]
+    fn test_failed_after_modify_liquidity_hook_leaves_pool_unchanged() {
+        let (mut manager, _) =
+            PoolManager::initialize(key(3000), RevertingHook, SQRT_PRICE_1_1).unwrap();
+        let pool = manager.pool.clone();
+
+        assert!(matches!(
+            manager.modify_liquidity(Address::ZERO, &liquidity_params(1e18 as i128)),
+            Err(UniswapV3MathError::PriceSlippageCheck)
+        ));
+        assert_eq!(manager.pool.liquidity, pool.liquidity);
+        assert!(manager.pool.ticks.is_empty());
+        assert!(manager.pool.tick_bitmap.is_empty());
+        assert!(manager.pool.positions.is_empty());
+    }
+
+    #[test]
+    fn test_before_swap_hook_fills_swap() {
+        let mut manager = manager(3000, CustomCurveHook);
+        let slot0 = manager.pool.slot0;
+
+        let (delta, hook_delta) = manager
+            .swap(Address::ZERO, &swap_params(-100, true))
+            .unwrap();
+        assert_eq!(delta, BalanceDelta::new(-100, 100));
+        assert_eq!(hook_delta, BalanceDelta::new(100, -100));
+        // the pool was not swapped against
+        assert_eq!(manager.pool.slot0, slot0);
+    }
+
+    #[test]
+    fn test_before_swap_hook_delta_exceeds_swap_amount() {
+        struct GreedyHook;
+
+        impl Hooks for GreedyHook {
+            fn before_swap(
+                &mut self,
+                _key: &PoolKey,
+                _pool: &Pool,
+                _sender: Address,
+                _params: &SwapParams,
+            ) -> Result<(BeforeSwapDelta, u32), UniswapV3MathError> {
+                Ok((BeforeSwapDelta::new(101, 0), 0))
+            }
+        }
+
+        let mut manager = manager(3000, GreedyHook);
+        assert!(matches!(
+            manager.swap(Address::ZERO, &swap_params(-100, true)),
+            Err(UniswapV3MathError::HookDeltaExceedsSwapAmount)
+        ));
+    }
+
+    #[test]
+    fn test_lp_fee_override() {
+        // the override is ignored for static fee pools
+        let mut manager_static = manager(3000, FeeOverrideHook(10_000));
+        let mut manager_dynamic = manager(DYNAMIC_FEE_FLAG, FeeOverrideHook(3000));
+
+        let (static_delta, _) = manager_static
+            .swap(Address::ZERO, &swap_params(-1e15 as i128, true))
+            .unwrap();
+        let (dynamic_delta, _) = manager_dynamic
+            .swap(Address::ZERO, &swap_params(-1e15 as i128, true))
+            .unwrap();
+        assert_eq!(static_delta, dynamic_delta);
+
+        assert!(matches!(
+            manager_static.update_dynamic_lp_fee(500),
+            Err(UniswapV3MathError::UnauthorizedDynamicLPFeeUpdate)
+        ));
+        manager_dynamic.update_dynamic_lp_fee(500).unwrap();
+        assert_eq!(manager_dynamic.pool.slot0.lp_fee, 500);
+    }
+
+    #[test]
+    fn test_modify_liquidity_and_donate() {
+        let mut manager = manager(3000, NoHooks);
+
+        manager
+            .donate(Address::ZERO, U256::from(1e18 as u64), U256::ZERO)
+            .unwrap();
+        let (caller_delta, fees_accrued, hook_delta) = manager
+            .modify_liquidity(Address::ZERO, &liquidity_params(-1e18 as i128))
+            .unwrap();
+
+        assert_eq!(fees_accrued, BalanceDelta::new(1e18 as i128, 0));
+        assert_eq!(hook_delta, ZERO_DELTA);
+        assert_eq!(
+            caller_delta,
+            BalanceDelta::new(1e18 as i128 + 5981737760509662, 5981737760509662)
+        );
+    }
+}
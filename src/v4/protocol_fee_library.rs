@@ -0,0 +1,80 @@
+use crate::error::UniswapV3MathError;
+
+//Max protocol fee is 0.1% (1000 pips)
+pub const MAX_PROTOCOL_FEE: u16 = 1000;
+//Thresholds used for optimized bounds checks on protocol fees
+pub const FEE_0_THRESHOLD: u32 = 1001;
+pub const FEE_1_THRESHOLD: u32 = 1001 << 12;
+//The protocol fee is taken from the input amount first and then the LP fee is taken from the remaining
+pub const PIPS_DENOMINATOR: u32 = 1_000_000;
+
+//The protocol fee for zero for one swaps, stored in the lower 12 bits
+pub fn get_zero_for_one_fee(protocol_fee: u32) -> u16 {
+    (protocol_fee & 0xfff) as u16
+}
+
+//The protocol fee for one for zero swaps, stored in the upper 12 bits
+pub fn get_one_for_zero_fee(protocol_fee: u32) -> u16 {
+    (protocol_fee >> 12) as u16
+}
+
+//Returns true if both directional fees are at most MAX_PROTOCOL_FEE
+pub fn is_valid_protocol_fee(protocol_fee: u32) -> bool {
+    protocol_fee & 0xfff < FEE_0_THRESHOLD && protocol_fee & 0xfff000 < FEE_1_THRESHOLD
+}
+
+//Errors if either directional fee is larger than MAX_PROTOCOL_FEE
+pub fn validate(protocol_fee: u32) -> Result<(), UniswapV3MathError> {
+    if is_valid_protocol_fee(protocol_fee) {
+        Ok(())
+    } else {
+        Err(UniswapV3MathError::ProtocolFeeTooLarge(protocol_fee))
+    }
+}
+
+//Combines the protocol fee and the lp fee into the fee charged on the swap input, equivalent to
+//protocolFee + lpFee(1_000_000 - protocolFee) / 1_000_000
+pub fn calculate_swap_fee(protocol_fee: u16, lp_fee: u32) -> u32 {
+    let protocol_fee = (protocol_fee & 0xfff) as u64;
+    let lp_fee = (lp_fee & 0xffffff) as u64;
+    let numerator = protocol_fee * lp_fee;
+
+    (protocol_fee + lp_fee - numerator / PIPS_DENOMINATOR as u64) as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::v4::lp_fee_library::MAX_LP_FEE;
+
+    #[test]
+    fn test_get_directional_fees() {
+        let protocol_fee = (500 << 12) | 1000;
+        assert_eq!(get_zero_for_one_fee(protocol_fee), 1000);
+        assert_eq!(get_one_for_zero_fee(protocol_fee), 500);
+    }
+
+    #[test]
+    fn test_is_valid_protocol_fee() {
+        assert!(is_valid_protocol_fee(0));
+        assert!(is_valid_protocol_fee((1000 << 12) | 1000));
+        assert!(!is_valid_protocol_fee(1001));
+        assert!(!is_valid_protocol_fee(1001 << 12));
+
+        let result = validate((1001 << 12) | 1000);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Protocol fee 4101096 is larger than the maximum"
+        );
+    }
+
+    #[test]
+    fn test_calculate_swap_fee() {
+        assert_eq!(calculate_swap_fee(0, 3000), 3000);
+        assert_eq!(calculate_swap_fee(1000, 0), 1000);
+        // 1000 + 3000 - 1000 * 3000 / 1e6
+        assert_eq!(calculate_swap_fee(1000, 3000), 3997);
+        // capped at 100%
+        assert_eq!(calculate_swap_fee(MAX_PROTOCOL_FEE, MAX_LP_FEE), MAX_LP_FEE);
+    }
+}
@@ -0,0 +1,340 @@
+use alloy_primitives::{I256, U256};
+
+use crate::{
+    error::UniswapV3MathError,
+    full_math::{mul_div, mul_div_rounding_up},
+    low_gas_safe_math,
+    sqrt_price_math::{
+        _get_amount_0_delta, _get_amount_1_delta, get_next_sqrt_price_from_input,
+        get_next_sqrt_price_from_output,
+    },
+    swap_math::{SwapKind, SwapStep},
+};
+
+//The swap fee is represented in hundredths of a bip, so the max is 100%
+pub const MAX_SWAP_FEE: u32 = 1_000_000;
+
+//Computes the sqrt price target for the next swap step, the closer of the next initialized tick's price and
+//the price limit in the direction of the swap
+pub fn get_sqrt_price_target(
+    zero_for_one: bool,
+    sqrt_price_next_x_96: U256,
+    sqrt_price_limit_x_96: U256,
+) -> U256 {
+    if zero_for_one {
+        sqrt_price_next_x_96.max(sqrt_price_limit_x_96)
+    } else {
+        sqrt_price_next_x_96.min(sqrt_price_limit_x_96)
+    }
+}
+
+//Splits a v4 amountSpecified into its magnitude and kind. Unlike v3, negative amounts are exact input.
+pub fn swap_kind(amount_specified: I256) -> (U256, SwapKind) {
+    if amount_specified < I256::ZERO {
+        (amount_specified.unsigned_abs(), SwapKind::ExactIn)
+    } else {
+        (amount_specified.into_raw(), SwapKind::ExactOut)
+    }
+}
+
+// //returns (
+//         uint160 sqrtPriceNextX96,
+//         uint256 amountIn,
+//         uint256 amountOut,
+//         uint256 feeAmount
+//     )
+pub fn compute_swap_step(
+    sqrt_price_current_x_96: U256,
+    sqrt_price_target_x_96: U256,
+    liquidity: u128,
+    amount_remaining: I256,
+    fee_pips: u32,
+) -> Result<(U256, U256, U256, U256), UniswapV3MathError> {
+    let (amount_remaining, kind) = swap_kind(amount_remaining);
+
+    let step = compute_swap_step_with_kind(
+        sqrt_price_current_x_96,
+        sqrt_price_target_x_96,
+        liquidity,
+        amount_remaining,
+        kind,
+        fee_pips,
+    )?;
+
+    Ok((
+        step.sqrt_ratio_next_x_96,
+        step.amount_in,
+        step.amount_out,
+        step.fee_amount,
+    ))
+}
+
+//Same as compute_swap_step, with the amount remaining given as an unsigned magnitude and an explicit swap kind
+pub fn compute_swap_step_with_kind(
+    sqrt_price_current_x_96: U256,
+    sqrt_price_target_x_96: U256,
+    liquidity: u128,
+    amount_remaining: U256,
+    kind: SwapKind,
+    fee_pips: u32,
+) -> Result<SwapStep, UniswapV3MathError> {
+    let zero_for_one = sqrt_price_current_x_96 >= sqrt_price_target_x_96;
+    let fee_complement = low_gas_safe_math::sub(U256::from(MAX_SWAP_FEE), U256::from(fee_pips))?; //MAX_SWAP_FEE - fee_pips
+
+    let sqrt_price_next_x_96: U256;
+    let amount_in: U256;
+    let amount_out: U256;
+    let fee_amount: U256;
+
+    match kind {
+        SwapKind::ExactIn => {
+            let amount_remaining_less_fee =
+                mul_div(amount_remaining, fee_complement, U256::from(MAX_SWAP_FEE))?;
+
+            let amount_in_to_target = if zero_for_one {
+                _get_amount_0_delta(
+                    sqrt_price_target_x_96,
+                    sqrt_price_current_x_96,
+                    liquidity,
+                    true,
+                )?
+            } else {
+                _get_amount_1_delta(
+                    sqrt_price_current_x_96,
+                    sqrt_price_target_x_96,
+                    liquidity,
+                    true,
+                )?
+            };
+
+            if amount_remaining_less_fee >= amount_in_to_target {
+                // amount_in is capped by the target price
+                sqrt_price_next_x_96 = sqrt_price_target_x_96;
+                amount_in = amount_in_to_target;
+                fee_amount = if fee_pips == MAX_SWAP_FEE {
+                    // amount_in is always 0 here, as amount_remaining_less_fee == 0
+                    amount_in
+                } else {
+                    mul_div_rounding_up(amount_in, U256::from(fee_pips), fee_complement)?
+                };
+            } else {
+                // exhaust the remaining amount
+                amount_in = amount_remaining_less_fee;
+                sqrt_price_next_x_96 = get_next_sqrt_price_from_input(
+                    sqrt_price_current_x_96,
+                    liquidity,
+                    amount_remaining_less_fee,
+                    zero_for_one,
+                )?;
+                // the target was not reached, so the remainder of the maximum input is taken as fee
+                fee_amount = amount_remaining - amount_in;
+            }
+
+            amount_out = if zero_for_one {
+                _get_amount_1_delta(
+                    sqrt_price_next_x_96,
+                    sqrt_price_current_x_96,
+                    liquidity,
+                    false,
+                )?
+            } else {
+                _get_amount_0_delta(
+                    sqrt_price_current_x_96,
+                    sqrt_price_next_x_96,
+                    liquidity,
+                    false,
+                )?
+            };
+        }
+        SwapKind::ExactOut => {
+            let amount_out_to_target = if zero_for_one {
+                _get_amount_1_delta(
+                    sqrt_price_target_x_96,
+                    sqrt_price_current_x_96,
+                    liquidity,
+                    false,
+                )?
+            } else {
+                _get_amount_0_delta(
+                    sqrt_price_current_x_96,
+                    sqrt_price_target_x_96,
+                    liquidity,
+                    false,
+                )?
+            };
+
+            if amount_remaining >= amount_out_to_target {
+                // amount_out is capped by the target price
+                sqrt_price_next_x_96 = sqrt_price_target_x_96;
+                amount_out = amount_out_to_target;
+            } else {
+                // cap the output amount to not exceed the remaining output amount
+                amount_out = amount_remaining;
+                sqrt_price_next_x_96 = get_next_sqrt_price_from_output(
+                    sqrt_price_current_x_96,
+                    liquidity,
+                    amount_out,
+                    zero_for_one,
+                )?;
+            }
+
+            amount_in = if zero_for_one {
+                _get_amount_0_delta(
+                    sqrt_price_next_x_96,
+                    sqrt_price_current_x_96,
+                    liquidity,
+                    true,
+                )?
+            } else {
+                _get_amount_1_delta(
+                    sqrt_price_current_x_96,
+                    sqrt_price_next_x_96,
+                    liquidity,
+                    true,
+                )?
+            };
+
+            // fee_pips cannot be MAX_SWAP_FEE for exact out, the division by zero errors
+            fee_amount = mul_div_rounding_up(amount_in, U256::from(fee_pips), fee_complement)?;
+        }
+    }
+
+    Ok(SwapStep {
+        sqrt_ratio_next_x_96: sqrt_price_next_x_96,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{sqrt_price_math::get_next_sqrt_price_from_input, test_utils::SQRT_PRICE_1_1};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_get_sqrt_price_target() {
+        let lower = U256::from(100);
+        let higher = U256::from(200);
+
+        assert_eq!(get_sqrt_price_target(true, lower, higher), higher);
+        assert_eq!(get_sqrt_price_target(true, higher, lower), higher);
+        assert_eq!(get_sqrt_price_target(false, lower, higher), lower);
+        assert_eq!(get_sqrt_price_target(false, higher, lower), lower);
+    }
+
+    #[test]
+    fn test_compute_swap_step() {
+        //------------------------------------------------------------
+
+        //exact amount in that gets capped at price target in one for zero
+        let price_target = U256::from_str("79623317895830914510639640423").unwrap();
+        let (sqrt_p, amount_in, amount_out, fee_amount) = compute_swap_step(
+            SQRT_PRICE_1_1,
+            price_target,
+            2e18 as u128,
+            I256::from_str("-1000000000000000000").unwrap(),
+            600,
+        )
+        .unwrap();
+
+        assert_eq!(sqrt_p, price_target);
+        assert_eq!(amount_in, U256::from_str("9975124224178055").unwrap());
+        assert_eq!(fee_amount, U256::from_str("5988667735148").unwrap());
+        assert_eq!(amount_out, U256::from_str("9925619580021728").unwrap());
+
+        //------------------------------------------------------------
+
+        //exact amount out that gets capped at price target in one for zero
+        let (sqrt_p, amount_in, amount_out, fee_amount) = compute_swap_step(
+            SQRT_PRICE_1_1,
+            price_target,
+            2e18 as u128,
+            I256::from_str("1000000000000000000").unwrap(),
+            600,
+        )
+        .unwrap();
+
+        assert_eq!(sqrt_p, price_target);
+        assert_eq!(amount_in, U256::from_str("9975124224178055").unwrap());
+        assert_eq!(fee_amount, U256::from_str("5988667735148").unwrap());
+        assert_eq!(amount_out, U256::from_str("9925619580021728").unwrap());
+
+        //------------------------------------------------------------
+
+        //exact amount in that is fully spent in one for zero
+        let price_target = U256::from_str("250541448375047931186413801569").unwrap();
+        let amount = U256::from_str("1000000000000000000").unwrap();
+        let (sqrt_p, amount_in, amount_out, fee_amount) = compute_swap_step(
+            SQRT_PRICE_1_1,
+            price_target,
+            2e18 as u128,
+            -I256::from_raw(amount),
+            600,
+        )
+        .unwrap();
+
+        assert_eq!(amount_in, U256::from_str("999400000000000000").unwrap());
+        assert_eq!(fee_amount, U256::from_str("600000000000000").unwrap());
+        assert_eq!(amount_out, U256::from_str("666399946655997866").unwrap());
+        assert_eq!(amount_in + fee_amount, amount);
+        assert!(sqrt_p < price_target);
+        assert_eq!(
+            sqrt_p,
+            get_next_sqrt_price_from_input(SQRT_PRICE_1_1, 2e18 as u128, amount_in, false).unwrap()
+        );
+
+        //------------------------------------------------------------
+
+        //input amount too small to move the price is still consumed, unlike v3 where it is all taken as fee
+        let (sqrt_p, amount_in, amount_out, fee_amount) = compute_swap_step(
+            U256::from(2413),
+            U256::from_str("79887613182836312").unwrap(),
+            1985041575832132834610021537970_u128,
+            I256::from_str("-10").unwrap(),
+            1872,
+        )
+        .unwrap();
+
+        assert_eq!(amount_in, U256::from(9));
+        assert_eq!(fee_amount, U256::from(1));
+        assert_eq!(amount_out, U256::ZERO);
+        assert_eq!(sqrt_p, U256::from(2413));
+
+        //------------------------------------------------------------
+
+        //a fee of 100% takes the entire exact input and reverts for exact output
+        let (sqrt_p, amount_in, amount_out, fee_amount) = compute_swap_step(
+            SQRT_PRICE_1_1,
+            price_target,
+            2e18 as u128,
+            -I256::from_raw(amount),
+            MAX_SWAP_FEE,
+        )
+        .unwrap();
+
+        assert_eq!(sqrt_p, SQRT_PRICE_1_1);
+        assert_eq!(amount_in, U256::ZERO);
+        assert_eq!(amount_out, U256::ZERO);
+        assert_eq!(fee_amount, amount);
+
+        let result = compute_swap_step(
+            SQRT_PRICE_1_1,
+            price_target,
+            2e18 as u128,
+            I256::from_raw(amount),
+            MAX_SWAP_FEE,
+        );
+        assert_eq!(result.unwrap_err().to_string(), "Denominator is 0");
+    }
+
+    #[test]
+    fn test_swap_kind() {
+        assert_eq!(
+            swap_kind(I256::MINUS_ONE),
+            (U256::from(1), SwapKind::ExactIn)
+        );
+        assert_eq!(swap_kind(I256::ONE), (U256::from(1), SwapKind::ExactOut));
+    }
+}
@@ -0,0 +1,364 @@
+//Bindings for JavaScript through wasm-bindgen, built with wasm-pack build -- --features wasm. Integers wider than 53
+//bits are passed as decimal or 0x-prefixed hex strings and returned as decimal strings, which BigInt() parses
+//directly. Errors are thrown as JavaScript errors with the message of the UniswapV3MathError.
+
+use std::str::FromStr;
+
+use alloy_primitives::{Address, I256, U256};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    dialect::{Dialect, PoolConfig},
+    error::UniswapV3MathError,
+    pool::Pool,
+    sqrt_price_math::{_get_amount_0_delta, _get_amount_1_delta},
+    swap_math::compute_swap_step,
+    tick_math::{get_sqrt_ratio_at_tick, get_tick_at_sqrt_ratio},
+};
+
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, JsError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| JsError::new(&format!("Invalid {name}: {value}")))
+}
+
+//Parses through U256 so that u128 values accept hex as well
+fn parse_u128(name: &str, value: &str) -> Result<u128, JsError> {
+    u128::try_from(parse::<U256>(name, value)?)
+        .map_err(|_| JsError::new(&format!("Invalid {name}: {value}")))
+}
+
+fn js_error(err: UniswapV3MathError) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+#[wasm_bindgen(js_name = getSqrtRatioAtTick)]
+pub fn get_sqrt_ratio_at_tick_js(tick: i32) -> Result<String, JsError> {
+    Ok(get_sqrt_ratio_at_tick(tick).map_err(js_error)?.to_string())
+}
+
+#[wasm_bindgen(js_name = getTickAtSqrtRatio)]
+pub fn get_tick_at_sqrt_ratio_js(sqrt_price_x_96: &str) -> Result<i32, JsError> {
+    get_tick_at_sqrt_ratio(parse::<U256>("sqrtPriceX96", sqrt_price_x_96)?).map_err(js_error)
+}
+
+#[wasm_bindgen(js_name = getAmount0Delta)]
+pub fn get_amount_0_delta_js(
+    sqrt_ratio_a_x_96: &str,
+    sqrt_ratio_b_x_96: &str,
+    liquidity: &str,
+    round_up: bool,
+) -> Result<String, JsError> {
+    Ok(_get_amount_0_delta(
+        parse("sqrtRatioAX96", sqrt_ratio_a_x_96)?,
+        parse("sqrtRatioBX96", sqrt_ratio_b_x_96)?,
+        parse_u128("liquidity", liquidity)?,
+        round_up,
+    )
+    .map_err(js_error)?
+    .to_string())
+}
+
+#[wasm_bindgen(js_name = getAmount1Delta)]
+pub fn get_amount_1_delta_js(
+    sqrt_ratio_a_x_96: &str,
+    sqrt_ratio_b_x_96: &str,
+    liquidity: &str,
+    round_up: bool,
+) -> Result<String, JsError> {
+    Ok(_get_amount_1_delta(
+        parse("sqrtRatioAX96", sqrt_ratio_a_x_96)?,
+        parse("sqrtRatioBX96", sqrt_ratio_b_x_96)?,
+        parse_u128("liquidity", liquidity)?,
+        round_up,
+    )
+    .map_err(js_error)?
+    .to_string())
+}
+
+#[wasm_bindgen(getter_with_clone)]
+pub struct SwapStepOutput {
+    #[wasm_bindgen(js_name = sqrtRatioNextX96)]
+    pub sqrt_ratio_next_x_96: String,
+    #[wasm_bindgen(js_name = amountIn)]
+    pub amount_in: String,
+    #[wasm_bindgen(js_name = amountOut)]
+    pub amount_out: String,
+    #[wasm_bindgen(js_name = feeAmount)]
+    pub fee_amount: String,
+}
+
+//A positive amount remaining is the exact input, a negative one the exact output
+#[wasm_bindgen(js_name = computeSwapStep)]
+pub fn compute_swap_step_js(
+    sqrt_ratio_current_x_96: &str,
+    sqrt_ratio_target_x_96: &str,
+    liquidity: &str,
+    amount_remaining: &str,
+    fee_pips: u32,
+) -> Result<SwapStepOutput, JsError> {
+    let (sqrt_ratio_next_x_96, amount_in, amount_out, fee_amount) = compute_swap_step(
+        parse("sqrtRatioCurrentX96", sqrt_ratio_current_x_96)?,
+        parse("sqrtRatioTargetX96", sqrt_ratio_target_x_96)?,
+        parse_u128("liquidity", liquidity)?,
+        parse::<I256>("amountRemaining", amount_remaining)?,
+        fee_pips,
+    )
+    .map_err(js_error)?;
+
+    Ok(SwapStepOutput {
+        sqrt_ratio_next_x_96: sqrt_ratio_next_x_96.to_string(),
+        amount_in: amount_in.to_string(),
+        amount_out: amount_out.to_string(),
+        fee_amount: fee_amount.to_string(),
+    })
+}
+
+#[wasm_bindgen(getter_with_clone)]
+pub struct AmountsOutput {
+    pub amount0: String,
+    pub amount1: String,
+}
+
+#[wasm_bindgen(getter_with_clone)]
+pub struct SwapOutput {
+    //The deltas of the pool's balances, positive when paid to the pool
+    pub amount0: String,
+    pub amount1: String,
+    #[wasm_bindgen(js_name = sqrtPriceX96)]
+    pub sqrt_price_x_96: String,
+    pub tick: i32,
+    pub liquidity: String,
+}
+
+//A UniswapV3 pool simulator holding its ticks and positions
+#[wasm_bindgen(js_name = Pool)]
+pub struct WasmPool {
+    pool: Pool,
+}
+
+#[wasm_bindgen(js_class = Pool)]
+impl WasmPool {
+    #[wasm_bindgen(constructor)]
+    pub fn new(fee: u32, sqrt_price_x_96: &str) -> Result<WasmPool, JsError> {
+        let config = PoolConfig::new(Dialect::UniswapV3, fee)
+            .ok_or_else(|| JsError::new(&format!("Invalid fee: {fee}")))?;
+        let pool =
+            Pool::initialize(config, parse("sqrtPriceX96", sqrt_price_x_96)?).map_err(js_error)?;
+        Ok(WasmPool { pool })
+    }
+
+    #[wasm_bindgen(getter, js_name = sqrtPriceX96)]
+    pub fn sqrt_price_x_96(&self) -> String {
+        self.pool.slot0.sqrt_price_x_96.to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn tick(&self) -> i32 {
+        self.pool.slot0.tick
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn liquidity(&self) -> String {
+        self.pool.liquidity.to_string()
+    }
+
+    #[wasm_bindgen(getter, js_name = tickSpacing)]
+    pub fn tick_spacing(&self) -> i32 {
+        self.pool.config.tick_spacing
+    }
+
+    //Returns the amounts owed to the pool for the liquidity
+    pub fn mint(
+        &mut self,
+        owner: &str,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: &str,
+    ) -> Result<AmountsOutput, JsError> {
+        let (amount_0, amount_1) = self
+            .pool
+            .mint(
+                parse::<Address>("owner", owner)?,
+                tick_lower,
+                tick_upper,
+                parse_u128("amount", amount)?,
+            )
+            .map_err(js_error)?;
+        Ok(AmountsOutput {
+            amount0: amount_0.to_string(),
+            amount1: amount_1.to_string(),
+        })
+    }
+
+    //Returns the amounts credited to the position's tokens owed
+    pub fn burn(
+        &mut self,
+        owner: &str,
+        tick_lower: i32,
+        tick_upper: i32,
+        amount: &str,
+    ) -> Result<AmountsOutput, JsError> {
+        let (amount_0, amount_1) = self
+            .pool
+            .burn(
+                parse::<Address>("owner", owner)?,
+                tick_lower,
+                tick_upper,
+                parse_u128("amount", amount)?,
+            )
+            .map_err(js_error)?;
+        Ok(AmountsOutput {
+            amount0: amount_0.to_string(),
+            amount1: amount_1.to_string(),
+        })
+    }
+
+    //A positive amount specified is the exact input, a negative amount the exact output
+    pub fn swap(
+        &mut self,
+        zero_for_one: bool,
+        amount_specified: &str,
+        sqrt_price_limit_x_96: &str,
+    ) -> Result<SwapOutput, JsError> {
+        let result = self
+            .pool
+            .swap(
+                zero_for_one,
+                parse("amountSpecified", amount_specified)?,
+                parse("sqrtPriceLimitX96", sqrt_price_limit_x_96)?,
+            )
+            .map_err(js_error)?;
+        Ok(SwapOutput {
+            amount0: result.amount_0.to_string(),
+            amount1: result.amount_1.to_string(),
+            sqrt_price_x_96: self.pool.slot0.sqrt_price_x_96.to_string(),
+            tick: self.pool.slot0.tick,
+            liquidity: self.pool.liquidity.to_string(),
+        })
+    }
+}
+
+//Run with wasm-pack test --node --features wasm, or --headless --chrome/--firefox
+#[cfg(all(test, target_arch = "wasm32"))]
+mod test {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    const SQRT_PRICE_1_1: &str = "79228162514264337593543950336";
+    const MIN_SQRT_RATIO_PLUS_1: &str = "4295128740";
+
+    #[wasm_bindgen_test]
+    fn test_tick_math() {
+        assert_eq!(get_sqrt_ratio_at_tick_js(0).unwrap(), SQRT_PRICE_1_1);
+        assert_eq!(get_sqrt_ratio_at_tick_js(-887272).unwrap(), "4295128739");
+        assert_eq!(get_tick_at_sqrt_ratio_js(SQRT_PRICE_1_1).unwrap(), 0);
+        // hex is accepted as well
+        assert_eq!(
+            get_tick_at_sqrt_ratio_js("0x1000000000000000000000000").unwrap(),
+            0
+        );
+        assert!(get_sqrt_ratio_at_tick_js(887273).is_err());
+        assert!(get_tick_at_sqrt_ratio_js("not a number").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_amount_deltas_and_swap_step() {
+        let sqrt_price_121_100 = "87150978765690771352898345369";
+        assert_eq!(
+            get_amount_0_delta_js(
+                SQRT_PRICE_1_1,
+                sqrt_price_121_100,
+                "1000000000000000000",
+                true
+            )
+            .unwrap(),
+            "90909090909090910"
+        );
+        assert_eq!(
+            get_amount_1_delta_js(
+                SQRT_PRICE_1_1,
+                sqrt_price_121_100,
+                "1000000000000000000",
+                true
+            )
+            .unwrap(),
+            "100000000000000000"
+        );
+        // liquidity in hex, 1e18
+        assert_eq!(
+            get_amount_0_delta_js(
+                SQRT_PRICE_1_1,
+                sqrt_price_121_100,
+                "0xde0b6b3a7640000",
+                true
+            )
+            .unwrap(),
+            "90909090909090910"
+        );
+        // liquidity wider than uint128
+        assert!(get_amount_1_delta_js(
+            SQRT_PRICE_1_1,
+            sqrt_price_121_100,
+            "340282366920938463463374607431768211456",
+            true
+        )
+        .is_err());
+
+        // an exact input more than enough to reach the target
+        let step = compute_swap_step_js(
+            SQRT_PRICE_1_1,
+            sqrt_price_121_100,
+            "2000000000000000000",
+            "1000000000000000000",
+            600,
+        )
+        .unwrap();
+        assert_eq!(step.sqrt_ratio_next_x_96, sqrt_price_121_100);
+        // liquidity in hex, 2e18
+        let hex_step = compute_swap_step_js(
+            SQRT_PRICE_1_1,
+            sqrt_price_121_100,
+            "0x1bc16d674ec80000",
+            "1000000000000000000",
+            600,
+        )
+        .unwrap();
+        assert_eq!(hex_step.amount_in, step.amount_in);
+        assert_eq!(step.amount_in, "200000000000000000");
+        assert_eq!(step.amount_out, "181818181818181818");
+        assert_eq!(step.fee_amount, "120072043225936");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_pool() {
+        let mut pool = WasmPool::new(3000, SQRT_PRICE_1_1).unwrap();
+        assert_eq!(pool.tick_spacing(), 60);
+
+        let owner = "0x0000000000000000000000000000000000000001";
+        let amounts = pool.mint(owner, -120, 120, "1000000000000000000").unwrap();
+        assert_eq!(amounts.amount0, "5981737760509663");
+        assert_eq!(pool.liquidity(), "1000000000000000000");
+        // amounts in hex, 1e18
+        let hex_owner = "0x0000000000000000000000000000000000000002";
+        let hex_amounts = pool
+            .mint(hex_owner, -120, 120, "0xde0b6b3a7640000")
+            .unwrap();
+        assert_eq!(hex_amounts.amount0, amounts.amount0);
+        pool.burn(hex_owner, -120, 120, "0xde0b6b3a7640000")
+            .unwrap();
+        assert_eq!(pool.liquidity(), "1000000000000000000");
+
+        let swap = pool
+            .swap(true, "1000000000000000", MIN_SQRT_RATIO_PLUS_1)
+            .unwrap();
+        assert_eq!(swap.amount0, "1000000000000000");
+        assert_eq!(swap.amount1, "-996006981039903");
+        assert_eq!(swap.tick, -20);
+        assert_eq!(pool.tick(), swap.tick);
+
+        assert!(pool.swap(true, "0", MIN_SQRT_RATIO_PLUS_1).is_err());
+        assert!(WasmPool::new(1234, SQRT_PRICE_1_1).is_err());
+    }
+}
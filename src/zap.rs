@@ -0,0 +1,253 @@
+use alloy_primitives::{Address, I256, U256};
+
+use crate::{
+    error::UniswapV3MathError,
+    liquidity_amounts::{
+        get_liquidity_for_amount_0, get_liquidity_for_amount_1, get_liquidity_for_amounts,
+    },
+    pool::{Pool, SwapResult},
+    tick::check_ticks,
+    tick_math::{get_sqrt_ratio_at_tick, MAX_SQRT_RATIO, MIN_SQRT_RATIO},
+    U256_1,
+};
+
+//A single token deposit split into a swap and a mint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Zap {
+    //The direction of the swap, true when token0 is deposited
+    pub zero_for_one: bool,
+    //The exact input of the swap, in the deposited token
+    pub amount_to_swap: U256,
+    pub swap: SwapResult,
+    //The sqrt price after the swap, at which the liquidity is added
+    pub sqrt_price_x_96: U256,
+    pub liquidity: u128,
+    //The amounts paid for the liquidity
+    pub amount_0: U256,
+    pub amount_1: U256,
+    //The balances left over after the mint
+    pub dust_0: U256,
+    pub dust_1: U256,
+}
+
+//Swaps part of the deposit on a copy of the pool
+// returns (Pool pool, SwapResult swap, uint256 balance0, uint256 balance1)
+fn swap(
+    pool: &Pool,
+    zero_for_one: bool,
+    amount_in: U256,
+    amount_to_swap: U256,
+) -> Result<(Pool, SwapResult, U256, U256), UniswapV3MathError> {
+    let mut pool = pool.clone();
+    let result = if amount_to_swap.is_zero() {
+        SwapResult::default()
+    } else {
+        let sqrt_price_limit_x_96 = if zero_for_one {
+            MIN_SQRT_RATIO + U256_1
+        } else {
+            MAX_SQRT_RATIO - U256_1
+        };
+        pool.swap(
+            zero_for_one,
+            I256::from_raw(amount_to_swap),
+            sqrt_price_limit_x_96,
+        )?
+    };
+
+    // the pool's amounts are positive when paid to it
+    let amount_0 =
+        I256::from_raw(if zero_for_one { amount_in } else { U256::ZERO }) - result.amount_0;
+    let amount_1 =
+        I256::from_raw(if zero_for_one { U256::ZERO } else { amount_in }) - result.amount_1;
+
+    Ok((pool, result, amount_0.into_raw(), amount_1.into_raw()))
+}
+
+//Whether the balances left after swapping hold more of the deposited token than the range needs at the post-swap
+//price, i.e. whether more of it should be swapped
+fn excess_of_deposit(
+    zero_for_one: bool,
+    sqrt_price_x_96: U256,
+    sqrt_price_lower_x_96: U256,
+    sqrt_price_upper_x_96: U256,
+    balance_0: U256,
+    balance_1: U256,
+) -> Result<bool, UniswapV3MathError> {
+    // out of range, only one of the tokens can be added
+    if sqrt_price_x_96 <= sqrt_price_lower_x_96 {
+        return Ok(!zero_for_one);
+    }
+    if sqrt_price_x_96 >= sqrt_price_upper_x_96 {
+        return Ok(zero_for_one);
+    }
+
+    let liquidity_0 =
+        get_liquidity_for_amount_0(sqrt_price_x_96, sqrt_price_upper_x_96, balance_0)?;
+    let liquidity_1 =
+        get_liquidity_for_amount_1(sqrt_price_lower_x_96, sqrt_price_x_96, balance_1)?;
+    Ok(if zero_for_one {
+        liquidity_0 > liquidity_1
+    } else {
+        liquidity_1 > liquidity_0
+    })
+}
+
+//Returns the swap that lets a single token deposit add the most liquidity to a range, with the balances after the
+//swap matching the range ratio at the post-swap price. Swapping more of the deposit lowers the liquidity its
+//remainder can add and raises that of the swap output, so the amount is found by binary search on which side limits
+//the liquidity, with every candidate evaluated on the swap simulator.
+pub fn zap_in(
+    pool: &Pool,
+    tick_lower: i32,
+    tick_upper: i32,
+    zero_for_one: bool,
+    amount_in: U256,
+) -> Result<Zap, UniswapV3MathError> {
+    check_ticks(tick_lower, tick_upper)?;
+    let sqrt_price_lower_x_96 = get_sqrt_ratio_at_tick(tick_lower)?;
+    let sqrt_price_upper_x_96 = get_sqrt_ratio_at_tick(tick_upper)?;
+
+    let liquidity_after_swap = |amount_to_swap: U256| -> Result<_, UniswapV3MathError> {
+        let (pool, result, balance_0, balance_1) =
+            swap(pool, zero_for_one, amount_in, amount_to_swap)?;
+        let liquidity = get_liquidity_for_amounts(
+            pool.slot0.sqrt_price_x_96,
+            sqrt_price_lower_x_96,
+            sqrt_price_upper_x_96,
+            balance_0,
+            balance_1,
+        )?;
+        Ok((liquidity, pool, result, balance_0, balance_1))
+    };
+
+    // the largest amount to swap that still leaves an excess of the deposited token
+    let mut low = U256::ZERO;
+    let mut high = amount_in;
+    while low < high {
+        let mid = low + (high - low + U256_1) / U256::from(2);
+        let (pool, _, balance_0, balance_1) = swap(pool, zero_for_one, amount_in, mid)?;
+        if excess_of_deposit(
+            zero_for_one,
+            pool.slot0.sqrt_price_x_96,
+            sqrt_price_lower_x_96,
+            sqrt_price_upper_x_96,
+            balance_0,
+            balance_1,
+        )? {
+            low = mid;
+        } else {
+            high = mid - U256_1;
+        }
+    }
+
+    // the optimum is on one side of the crossing point, the first step past it may already be limited by the output
+    let mut best = liquidity_after_swap(low)?;
+    let mut amount_to_swap = low;
+    if low < amount_in {
+        let next = liquidity_after_swap(low + U256_1)?;
+        if next.0 > best.0 {
+            best = next;
+            amount_to_swap = low + U256_1;
+        }
+    }
+    let (liquidity, mut pool, result, balance_0, balance_1) = best;
+
+    let (amount_0, amount_1) = if liquidity == 0 {
+        (U256::ZERO, U256::ZERO)
+    } else {
+        pool.mint(Address::ZERO, tick_lower, tick_upper, liquidity)?
+    };
+
+    Ok(Zap {
+        zero_for_one,
+        amount_to_swap,
+        swap: result,
+        sqrt_price_x_96: pool.slot0.sqrt_price_x_96,
+        liquidity,
+        amount_0,
+        amount_1,
+        dust_0: balance_0 - amount_0,
+        dust_1: balance_1 - amount_1,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils;
+
+    fn new_pool() -> Pool {
+        test_utils::new_pool(
+            Address::ZERO,
+            &[(-600, 600, 1e18 as u128), (-6000, 6000, 1e17 as u128)],
+        )
+    }
+
+    #[test]
+    fn test_zap_in() {
+        let pool = new_pool();
+        for (tick_lower, tick_upper, zero_for_one, amount_in) in [
+            // a small deposit into a symmetric range swaps about half
+            (-120, 120, true, 1e15 as u64),
+            (-120, 120, false, 1e15 as u64),
+            // larger deposits into asymmetric ranges, with a noticeable price impact
+            (-1200, 600, true, 5e16 as u64),
+            (-600, 1200, false, 5e16 as u64),
+        ] {
+            let amount_in = U256::from(amount_in);
+            let zap = zap_in(&pool, tick_lower, tick_upper, zero_for_one, amount_in).unwrap();
+            assert_eq!(zap.zero_for_one, zero_for_one);
+            assert!(zap.liquidity > 0);
+
+            // the deposit is fully accounted for
+            let (paid, received) = if zero_for_one {
+                (zap.swap.amount_0, zap.swap.amount_1)
+            } else {
+                (zap.swap.amount_1, zap.swap.amount_0)
+            };
+            assert_eq!(paid.into_raw(), zap.amount_to_swap);
+            let (deposited, output) = if zero_for_one {
+                (zap.amount_0 + zap.dust_0, zap.amount_1 + zap.dust_1)
+            } else {
+                (zap.amount_1 + zap.dust_1, zap.amount_0 + zap.dust_0)
+            };
+            assert_eq!(deposited + zap.amount_to_swap, amount_in);
+            assert_eq!(output, (-received).into_raw());
+
+            // the dust is a tiny fraction of the deposit
+            let dust = if zero_for_one { zap.dust_0 } else { zap.dust_1 };
+            assert!(dust * U256::from(10_000) < amount_in, "dust = {dust}");
+
+            // swapping one more or one less does not add more liquidity
+            for amount_to_swap in [zap.amount_to_swap - U256_1, zap.amount_to_swap + U256_1] {
+                let (pool, _, balance_0, balance_1) =
+                    swap(&pool, zero_for_one, amount_in, amount_to_swap).unwrap();
+                let liquidity = get_liquidity_for_amounts(
+                    pool.slot0.sqrt_price_x_96,
+                    get_sqrt_ratio_at_tick(tick_lower).unwrap(),
+                    get_sqrt_ratio_at_tick(tick_upper).unwrap(),
+                    balance_0,
+                    balance_1,
+                )
+                .unwrap();
+                assert!(liquidity <= zap.liquidity);
+            }
+        }
+    }
+
+    #[test]
+    fn test_zap_in_out_of_range() {
+        let pool = new_pool();
+
+        // a range above the price only takes token0, nothing is swapped
+        let zap = zap_in(&pool, 600, 1200, true, U256::from(1e15 as u64)).unwrap();
+        assert_eq!(zap.amount_to_swap, U256::ZERO);
+        assert_eq!(zap.amount_1, U256::ZERO);
+
+        // while a token1 deposit has to be swapped entirely
+        let zap = zap_in(&pool, 600, 1200, false, U256::from(1e15 as u64)).unwrap();
+        assert_eq!(zap.amount_to_swap, U256::from(1e15 as u64));
+        assert_eq!(zap.amount_1, U256::ZERO);
+        assert!(zap.liquidity > 0);
+    }
+}